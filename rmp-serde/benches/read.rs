@@ -0,0 +1,71 @@
+#![feature(test)]
+
+extern crate test;
+
+use test::Bencher;
+
+#[bench]
+fn bench_read_str_64k(bencher: &mut Bencher) {
+    let buf = encoded_str(65536);
+    let mut out = vec![0; 65536];
+
+    bencher.iter(|| {
+        messpack_serde::decode::read_str(&mut &buf[..], &mut out)
+            .unwrap()
+            .len()
+    });
+}
+
+#[bench]
+fn bench_read_str_raw_64k(bencher: &mut Bencher) {
+    let buf = encoded_str(65536);
+    let mut out = vec![0; 65536];
+
+    bencher.iter(|| {
+        messpack_serde::decode::read_str_raw(&mut &buf[..], &mut out)
+            .unwrap()
+            .len()
+    });
+}
+
+#[bench]
+fn bench_read_u32_array_4k(bencher: &mut Bencher) {
+    let buf = encoded_u32s(4096);
+    let mut out = Vec::with_capacity(4096);
+
+    bencher.iter(|| {
+        out.clear();
+        messpack_serde::decode::read_u32_array(&mut &buf[..], &mut out).unwrap();
+        out.len()
+    });
+}
+
+#[bench]
+fn bench_read_u32_vec_serde_4k(bencher: &mut Bencher) {
+    let buf = encoded_u32s(4096);
+
+    bencher.iter(|| messpack_serde::from_slice::<Vec<u32>>(&buf).unwrap().len());
+}
+
+fn encoded_u32s(len: u32) -> Vec<u8> {
+    // Spread over every integer width up to u32.
+    let vals: Vec<u32> = (0..len)
+        .map(|i| i.wrapping_mul(0x9e37_79b9) >> (i % 32))
+        .collect();
+    messpack_serde::to_vec(&vals).unwrap()
+}
+
+fn encoded_str(len: usize) -> Vec<u8> {
+    let s: String = "abcdéfghijklmnopqrstuvwxyz€"
+        .chars()
+        .cycle()
+        .scan(0, |size, c| {
+            *size += c.len_utf8();
+            (*size <= len).then_some(c)
+        })
+        .collect();
+
+    let mut buf = Vec::new();
+    rmp::encode::write_str(&mut buf, &s).unwrap();
+    buf
+}
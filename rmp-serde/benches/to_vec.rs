@@ -0,0 +1,52 @@
+#![feature(test)]
+
+extern crate test;
+
+use serde::Serialize;
+use test::Bencher;
+
+#[path = "../tests/common/counting_alloc.rs"]
+mod counting_alloc;
+
+use counting_alloc::{allocations, CountingAlloc};
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+#[derive(Serialize)]
+struct Record {
+    id: u64,
+    name: String,
+    scores: Vec<f64>,
+}
+
+fn records() -> Vec<Record> {
+    (0..1000)
+        .map(|id| Record {
+            id,
+            name: format!("record {id}"),
+            scores: vec![0.5; 8],
+        })
+        .collect()
+}
+
+#[bench]
+fn bench_to_vec(bencher: &mut Bencher) {
+    let val = records();
+    let f = || messpack_serde::to_vec(&val).unwrap();
+    // The buffer grows as it's written, reallocating along the way.
+    let (buf, count) = allocations(f);
+    assert!(count > 1, "{count} allocations for {} bytes", buf.len());
+
+    bencher.iter(f);
+}
+
+#[bench]
+fn bench_to_vec_exact(bencher: &mut Bencher) {
+    let val = records();
+    let f = || messpack_serde::to_vec_exact(&val).unwrap();
+    // The size is worked out up front, so the buffer is allocated once.
+    assert_eq!(1, allocations(f).1);
+
+    bencher.iter(f);
+}
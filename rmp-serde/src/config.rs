@@ -9,7 +9,7 @@ pub trait SerializerConfig: sealed::SerializerConfig {}
 impl<T: sealed::SerializerConfig> SerializerConfig for T {}
 
 pub(crate) mod sealed {
-    use crate::config::BytesMode;
+    use crate::config::{BytesMode, Signedness};
 
     /// This is the inner trait - the real `SerializerConfig`.
     ///
@@ -23,6 +23,15 @@ pub(crate) mod sealed {
         /// String struct fields
         fn is_named(&self) -> bool;
         fn bytes(&self) -> BytesMode;
+
+        /// Whether NaN and infinite floats are rejected instead of serialized
+        fn reject_non_finite(&self) -> bool;
+
+        /// Whether string map keys and struct field names are written as bin
+        fn bin_keys(&self) -> bool;
+
+        /// How non-negative signed integers are written
+        fn signedness(&self) -> Signedness;
     }
 }
 
@@ -31,6 +40,9 @@ pub(crate) struct RuntimeConfig {
     pub(crate) is_human_readable: bool,
     pub(crate) is_named: bool,
     pub(crate) bytes: BytesMode,
+    pub(crate) reject_non_finite: bool,
+    pub(crate) bin_keys: bool,
+    pub(crate) signedness: Signedness,
 }
 
 /// When to encode `[u8]` as `bytes` rather than a sequence
@@ -56,12 +68,31 @@ pub enum BytesMode {
     ForceAll,
 }
 
+/// How the serializer writes non-negative signed integers, such as `5i64`.
+///
+/// Negative integers always use the smallest signed encoding, and unsigned integers the smallest
+/// unsigned one.
+#[non_exhaustive]
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum Signedness {
+    /// Use the smallest encoding regardless of signedness, which is a positive fixnum or an
+    /// unsigned integer. This is the default, and matches what most other implementations do.
+    #[default]
+    PreferUnsigned,
+    /// Use the smallest signed encoding (`int 8` through `int 64`), so that the value reads back
+    /// as signed in languages that distinguish the two.
+    PreserveSigned,
+}
+
 impl RuntimeConfig {
     pub(crate) fn new(other: impl sealed::SerializerConfig) -> Self {
         Self {
             is_human_readable: other.is_human_readable(),
             is_named: other.is_named(),
             bytes: other.bytes(),
+            reject_non_finite: other.reject_non_finite(),
+            bin_keys: other.bin_keys(),
+            signedness: other.signedness(),
         }
     }
 }
@@ -81,6 +112,21 @@ impl sealed::SerializerConfig for RuntimeConfig {
     fn bytes(&self) -> BytesMode {
         self.bytes
     }
+
+    #[inline]
+    fn reject_non_finite(&self) -> bool {
+        self.reject_non_finite
+    }
+
+    #[inline]
+    fn bin_keys(&self) -> bool {
+        self.bin_keys
+    }
+
+    #[inline]
+    fn signedness(&self) -> Signedness {
+        self.signedness
+    }
 }
 
 /// The default serializer/deserializer configuration.
@@ -109,6 +155,21 @@ impl sealed::SerializerConfig for DefaultConfig {
     fn bytes(&self) -> BytesMode {
         BytesMode::default()
     }
+
+    #[inline(always)]
+    fn reject_non_finite(&self) -> bool {
+        false
+    }
+
+    #[inline(always)]
+    fn bin_keys(&self) -> bool {
+        false
+    }
+
+    #[inline(always)]
+    fn signedness(&self) -> Signedness {
+        Signedness::default()
+    }
 }
 
 /// Config wrapper, that overrides struct serialization by packing as a map with field names.
@@ -146,6 +207,18 @@ where
     fn bytes(&self) -> BytesMode {
         self.0.bytes()
     }
+
+    fn reject_non_finite(&self) -> bool {
+        self.0.reject_non_finite()
+    }
+
+    fn bin_keys(&self) -> bool {
+        self.0.bin_keys()
+    }
+
+    fn signedness(&self) -> Signedness {
+        self.0.signedness()
+    }
 }
 
 /// Config wrapper that overrides struct serlization by packing as a tuple without field
@@ -178,6 +251,18 @@ where
     fn bytes(&self) -> BytesMode {
         self.0.bytes()
     }
+
+    fn reject_non_finite(&self) -> bool {
+        self.0.reject_non_finite()
+    }
+
+    fn bin_keys(&self) -> bool {
+        self.0.bin_keys()
+    }
+
+    fn signedness(&self) -> Signedness {
+        self.0.signedness()
+    }
 }
 
 /// Config wrapper that overrides `Serializer::is_human_readable` and
@@ -210,6 +295,18 @@ where
     fn bytes(&self) -> BytesMode {
         self.0.bytes()
     }
+
+    fn reject_non_finite(&self) -> bool {
+        self.0.reject_non_finite()
+    }
+
+    fn bin_keys(&self) -> bool {
+        self.0.bin_keys()
+    }
+
+    fn signedness(&self) -> Signedness {
+        self.0.signedness()
+    }
 }
 
 /// Config wrapper that overrides `Serializer::is_human_readable` and
@@ -242,4 +339,16 @@ where
     fn bytes(&self) -> BytesMode {
         self.0.bytes()
     }
+
+    fn reject_non_finite(&self) -> bool {
+        self.0.reject_non_finite()
+    }
+
+    fn bin_keys(&self) -> bool {
+        self.0.bin_keys()
+    }
+
+    fn signedness(&self) -> Signedness {
+        self.0.signedness()
+    }
 }
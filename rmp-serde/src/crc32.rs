@@ -0,0 +1,68 @@
+//! CRC-32 (IEEE 802.3) over the bytes passing through a reader or writer.
+
+use std::io::{self, Read, Write};
+
+const TABLE: [u32; 256] = make_table();
+
+const fn make_table() -> [u32; 256] {
+    let mut table = [0; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+}
+
+/// Wraps a reader or writer, checksumming every byte that goes through it.
+pub(crate) struct Crc32<T> {
+    inner: T,
+    crc: u32,
+}
+
+impl<T> Crc32<T> {
+    pub(crate) const fn new(inner: T) -> Self {
+        Self { inner, crc: !0 }
+    }
+
+    /// Returns the checksum of the bytes seen so far.
+    pub(crate) const fn sum(&self) -> u32 {
+        !self.crc
+    }
+
+    fn update(&mut self, buf: &[u8]) {
+        for &byte in buf {
+            self.crc = TABLE[((self.crc ^ u32::from(byte)) & 0xff) as usize] ^ (self.crc >> 8);
+        }
+    }
+}
+
+impl<R: Read> Read for Crc32<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.update(&buf[..n]);
+        Ok(n)
+    }
+}
+
+impl<W: Write> Write for Crc32<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.update(&buf[..n]);
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
@@ -4,7 +4,7 @@ use crate::config::sealed::SerializerConfig as _;
 use std::convert::TryInto;
 use std::error;
 use std::fmt::{self, Display, Formatter};
-use std::io::{self, Cursor, ErrorKind, Read};
+use std::io::{self, Cursor, ErrorKind, Read, Seek, SeekFrom};
 use std::marker::PhantomData;
 use std::num::TryFromIntError;
 use std::str::{self, Utf8Error};
@@ -26,8 +26,28 @@ use rmp::decode::{
 use rmp::Marker;
 
 use crate::config::{BinaryConfig, DefaultConfig, HumanReadableConfig, SerializerConfig};
+use crate::marker::MarkerExt;
 use crate::MSGPACK_EXT_STRUCT_NAME;
 
+mod read;
+mod take;
+pub mod value;
+mod value_de;
+
+pub use self::read::{
+    read_array_iter, read_array_len_exact, read_array_raw_slices, read_array_with, read_bin,
+    read_bin_cow, read_bin_owned, read_decimal_ext, read_ext_array, read_ext_data, read_ext_owned,
+    read_f64_loosely, read_integer, read_integer_typed, read_map_iter, read_map_with,
+    read_seq_len_loose, read_str, read_str_len_with_class, read_str_owned, read_str_raw,
+    read_str_ref, read_u32_array, read_u32_loosely, read_with_default, skip_value, ArrayIter,
+    DecodeInto, Endian, ExtMetaExt, ExtReader, IntWidth, MapIter, StrClass,
+};
+pub use self::take::{
+    take_array_len, take_bin, take_bool, take_f64, take_i64, take_map_len, take_nil, take_str,
+    take_u64,
+};
+pub use self::value_de::ValueDeserializer;
+
 /// Enum representing errors that can occur while decoding MessagePack data.
 #[derive(Debug)]
 pub enum Error {
@@ -41,8 +61,16 @@ pub enum Error {
     TypeMismatch(Marker),
     /// A numeric cast failed due to an out-of-range error.
     OutOfRange,
-    /// A decoded array did not have the enclosed expected length.
-    LengthMismatch(u32),
+    /// An integer decoded into a float field can't be represented exactly, see
+    /// [`Deserializer::with_strict_floats`].
+    PrecisionLoss,
+    /// A decoded array or map did not have the expected length.
+    LengthMismatch {
+        /// The length the target type needed.
+        expected: usize,
+        /// The length found in the data.
+        actual: u32,
+    },
     /// An otherwise uncategorized error occurred. See the enclosed `String` for
     /// details.
     Uncategorized(String),
@@ -53,6 +81,47 @@ pub enum Error {
     Utf8Error(Utf8Error),
     /// The depth limit was exceeded.
     DepthLimitExceeded,
+    /// The caller-provided buffer can't hold the decoded data.
+    BufferSizeTooSmall {
+        /// The number of bytes the encoded data needs.
+        required: u32,
+        /// The number of bytes the buffer actually has, if known. Errors converted from
+        /// `rmp`'s `DecodeStringError` only carry the required length.
+        available: Option<usize>,
+    },
+    /// The encoded data declared a length above the caller-provided limit, or more elements than
+    /// the rest of the input could hold.
+    LengthLimitExceeded {
+        /// The length declared by the encoded data.
+        len: u32,
+        /// The largest length allowed.
+        max_len: u32,
+    },
+    /// The checksum trailing a value did not match the value's bytes.
+    ChecksumMismatch {
+        /// The checksum that was stored after the value.
+        expected: u32,
+        /// The checksum computed over the value as read.
+        actual: u32,
+    },
+    /// A value did not match the schema it was validated against.
+    SchemaMismatch {
+        /// Where the mismatching value is, such as `$.items[3]`.
+        path: String,
+        /// The marker of the mismatching value.
+        found: Marker,
+    },
+    /// A map had a key outside the expected set, or had an expected key twice.
+    UnexpectedKey(String),
+    /// A map lacked one of the expected keys.
+    MissingKey(String),
+    /// A MessagePack-RPC message was of a different type than expected, see [`rpc`](crate::rpc).
+    RpcTypeMismatch {
+        /// The message type that was expected.
+        expected: u8,
+        /// The message type that was read.
+        actual: u8,
+    },
 }
 
 macro_rules! depth_count(
@@ -76,12 +145,20 @@ impl error::Error for Error {
             Self::TypeMismatch(..) => None,
             Self::InvalidMarkerRead(ref err) => Some(err),
             Self::InvalidDataRead(ref err) => Some(err),
-            Self::LengthMismatch(..) => None,
+            Self::LengthMismatch { .. } => None,
             Self::OutOfRange => None,
+            Self::PrecisionLoss => None,
             Self::Uncategorized(..) => None,
             Self::Syntax(..) => None,
             Self::Utf8Error(ref err) => Some(err),
             Self::DepthLimitExceeded => None,
+            Self::BufferSizeTooSmall { .. } => None,
+            Self::LengthLimitExceeded { .. } => None,
+            Self::ChecksumMismatch { .. } => None,
+            Self::SchemaMismatch { .. } => None,
+            Self::UnexpectedKey(..) => None,
+            Self::MissingKey(..) => None,
+            Self::RpcTypeMismatch { .. } => None,
         }
     }
 }
@@ -103,14 +180,46 @@ impl Display for Error {
                 write!(fmt, "wrong msgpack marker {actual_marker:?}")
             }
             Self::OutOfRange => fmt.write_str("numeric cast found out of range"),
-            Self::LengthMismatch(expected_length) => write!(
+            Self::PrecisionLoss => fmt.write_str("integer can't be represented exactly as a float"),
+            Self::LengthMismatch { expected, actual } => write!(
                 fmt,
-                "array had incorrect length, expected {expected_length}"
+                "array had incorrect length, expected {expected} but found {actual}"
             ),
             Self::Uncategorized(ref msg) => write!(fmt, "uncategorized error: {msg}"),
             Self::Syntax(ref msg) => fmt.write_str(msg),
             Self::Utf8Error(ref err) => write!(fmt, "string found to be invalid utf8: {err}"),
             Self::DepthLimitExceeded => fmt.write_str("depth limit exceeded"),
+            Self::BufferSizeTooSmall {
+                required,
+                available: Some(available),
+            } => write!(
+                fmt,
+                "buffer too small, required {required} bytes but only {available} available"
+            ),
+            Self::BufferSizeTooSmall {
+                required,
+                available: None,
+            } => write!(fmt, "buffer too small, required {required} bytes"),
+            Self::LengthLimitExceeded { len, max_len } => {
+                write!(fmt, "length {len} exceeds the limit of {max_len}")
+            }
+            Self::ChecksumMismatch { expected, actual } => write!(
+                fmt,
+                "checksum mismatch, expected {expected:#010x} but computed {actual:#010x}"
+            ),
+            Self::SchemaMismatch {
+                ref path,
+                ref found,
+            } => write!(
+                fmt,
+                "value at {path} does not match the schema, found {found:?}"
+            ),
+            Self::UnexpectedKey(ref key) => write!(fmt, "unexpected map key {key:?}"),
+            Self::MissingKey(ref key) => write!(fmt, "missing map key {key:?}"),
+            Self::RpcTypeMismatch { expected, actual } => write!(
+                fmt,
+                "expected msgpack-rpc message type {expected}, got {actual}"
+            ),
         }
     }
 }
@@ -161,10 +270,22 @@ impl From<DecodeStringError<'_>> for Error {
             DecodeStringError::InvalidMarkerRead(err) => Self::InvalidMarkerRead(err),
             DecodeStringError::InvalidDataRead(err) => Self::InvalidDataRead(err),
             DecodeStringError::TypeMismatch(marker) => Self::TypeMismatch(marker),
-            DecodeStringError::BufferSizeTooSmall(..) => {
-                Self::Uncategorized("BufferSizeTooSmall".to_string())
-            }
-            DecodeStringError::InvalidUtf8(..) => Self::Uncategorized("InvalidUtf8".to_string()),
+            DecodeStringError::BufferSizeTooSmall(len) => Self::BufferSizeTooSmall {
+                required: len,
+                available: None,
+            },
+            DecodeStringError::InvalidUtf8(_, err) => Self::Utf8Error(err),
+        }
+    }
+}
+
+impl From<rmpv::decode::Error> for Error {
+    #[cold]
+    fn from(err: rmpv::decode::Error) -> Self {
+        match err {
+            rmpv::decode::Error::InvalidMarkerRead(err) => Self::InvalidMarkerRead(err),
+            rmpv::decode::Error::InvalidDataRead(err) => Self::InvalidDataRead(err),
+            rmpv::decode::Error::DepthLimitExceeded => Self::DepthLimitExceeded,
         }
     }
 }
@@ -189,14 +310,35 @@ pub struct Deserializer<R, C = DefaultConfig> {
     is_human_readable: bool,
     marker: Option<Marker>,
     depth: u16,
+    skip_leading: usize,
+    legacy_raw: bool,
+    bin_as_str: bool,
+    map_as_pairs: bool,
+    ignore_extra_struct_fields: bool,
+    unwrap_singletons: bool,
+    byte_arrays: bool,
+    strict_floats: bool,
+    max_ext_len: u32,
+    ext_handler: Option<ExtHandler>,
+}
+
+/// Callback mapping the type id and data of an ext value to a [`Value`](value::Value), see
+/// [`Deserializer::with_ext_handler`].
+pub type ExtHandlerFn = dyn FnMut(i8, &[u8]) -> Result<value::Value, Error> + Send;
+
+/// Wrapper giving the ext handler a `Debug` impl so `Deserializer` can keep deriving it.
+struct ExtHandler(Box<ExtHandlerFn>);
+
+impl fmt::Debug for ExtHandler {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str("ExtHandler")
+    }
 }
 
 impl<R: Read, C> Deserializer<R, C> {
     #[inline]
     fn take_or_read_marker(&mut self) -> Result<Marker, MarkerReadError> {
-        self.marker
-            .take()
-            .map_or_else(|| rmp::decode::read_marker(&mut self.rd), Ok)
+        self.marker.take().map_or_else(|| self.read_marker(), Ok)
     }
 
     #[inline]
@@ -204,10 +346,28 @@ impl<R: Read, C> Deserializer<R, C> {
         if let Some(m) = self.marker {
             Ok(m)
         } else {
-            let m = rmp::decode::read_marker(&mut self.rd)?;
+            let m = self.read_marker()?;
             Ok(self.marker.insert(m).to_owned())
         }
     }
+
+    #[inline]
+    fn read_marker(&mut self) -> Result<Marker, MarkerReadError> {
+        if self.skip_leading > 0 {
+            self.discard_leading()?;
+        }
+        rmp::decode::read_marker(&mut self.rd)
+    }
+
+    #[cold]
+    fn discard_leading(&mut self) -> Result<(), MarkerReadError> {
+        let len = std::mem::take(&mut self.skip_leading) as u64;
+        let skipped = io::copy(&mut (&mut self.rd).take(len), &mut io::sink())?;
+        if skipped < len {
+            return Err(io::Error::from(ErrorKind::UnexpectedEof).into());
+        }
+        Ok(())
+    }
 }
 
 impl<R: Read> Deserializer<ReadReader<R>, DefaultConfig> {
@@ -221,6 +381,25 @@ impl<R: Read> Deserializer<ReadReader<R>, DefaultConfig> {
             // Cached marker in case of deserializing optional values.
             marker: None,
             depth: 1024,
+            skip_leading: 0,
+            legacy_raw: false,
+            bin_as_str: false,
+            map_as_pairs: false,
+            ignore_extra_struct_fields: false,
+            unwrap_singletons: false,
+            byte_arrays: false,
+            strict_floats: false,
+            max_ext_len: u32::MAX,
+            ext_handler: None,
+        }
+    }
+
+    /// Constructs a reader of length-prefixed frames, see [`FramedReader`].
+    #[inline]
+    pub fn framed(rd: R) -> FramedReader<R> {
+        FramedReader {
+            rd,
+            buf: Vec::new(),
         }
     }
 }
@@ -259,6 +438,16 @@ impl<R: Read, C: SerializerConfig> Deserializer<R, C> {
             is_human_readable: _,
             marker,
             depth,
+            skip_leading,
+            legacy_raw,
+            bin_as_str,
+            map_as_pairs,
+            ignore_extra_struct_fields,
+            unwrap_singletons,
+            byte_arrays,
+            strict_floats,
+            max_ext_len,
+            ext_handler,
         } = self;
         Deserializer {
             rd,
@@ -266,6 +455,16 @@ impl<R: Read, C: SerializerConfig> Deserializer<R, C> {
             _config: PhantomData,
             marker,
             depth,
+            skip_leading,
+            legacy_raw,
+            bin_as_str,
+            map_as_pairs,
+            ignore_extra_struct_fields,
+            unwrap_singletons,
+            byte_arrays,
+            strict_floats,
+            max_ext_len,
+            ext_handler,
         }
     }
 
@@ -282,6 +481,16 @@ impl<R: Read, C: SerializerConfig> Deserializer<R, C> {
             is_human_readable: _,
             marker,
             depth,
+            skip_leading,
+            legacy_raw,
+            bin_as_str,
+            map_as_pairs,
+            ignore_extra_struct_fields,
+            unwrap_singletons,
+            byte_arrays,
+            strict_floats,
+            max_ext_len,
+            ext_handler,
         } = self;
         Deserializer {
             rd,
@@ -289,8 +498,150 @@ impl<R: Read, C: SerializerConfig> Deserializer<R, C> {
             _config: PhantomData,
             marker,
             depth,
+            skip_leading,
+            legacy_raw,
+            bin_as_str,
+            map_as_pairs,
+            ignore_extra_struct_fields,
+            unwrap_singletons,
+            byte_arrays,
+            strict_floats,
+            max_ext_len,
+            ext_handler,
         }
     }
+
+    /// Discards `n` bytes from the reader before decoding the first value.
+    ///
+    /// This is a compatibility shim for producers that prepend a framing byte, a length prefix or
+    /// similar to the MessagePack payload. The bytes are skipped lazily, on the first read, and a
+    /// reader with fewer than `n` bytes fails with `Error::InvalidMarkerRead`.
+    #[inline]
+    pub fn with_skip_leading(mut self, n: usize) -> Self {
+        self.skip_leading = n;
+        self
+    }
+
+    /// Treats str markers as the untyped "raw" markers of the pre-2013 MessagePack spec.
+    ///
+    /// Old encoders used the same markers (0xa0-0xbf, 0xda and 0xdb) for both strings and bytes.
+    /// With this enabled, a str value can be decoded into a sequence of bytes such as `Vec<u8>`,
+    /// in addition to a string.
+    #[inline]
+    pub fn with_legacy_raw(mut self, enabled: bool) -> Self {
+        self.legacy_raw = enabled;
+        self
+    }
+
+    /// Presents bin values that are valid UTF-8 as strings, where any type is accepted.
+    ///
+    /// Types that name what they expect already read a valid UTF-8 bin into a `String`, and a str
+    /// into a byte buffer. This setting is for self-describing targets, such as untagged enums and
+    /// [`Value`](value::Value), which otherwise see every bin as bytes. Bin values that aren't
+    /// valid UTF-8 are presented as bytes either way.
+    #[inline]
+    pub fn with_bin_as_str(mut self, enabled: bool) -> Self {
+        self.bin_as_str = enabled;
+        self
+    }
+
+    /// Allows decoding a map into a sequence of `(key, value)` pairs, such as `Vec<(K, V)>`.
+    ///
+    /// Without this, a map can only be decoded into a map type or a struct. With it, a sequence
+    /// type given a map receives one two-element tuple per entry, in the order they were encoded.
+    #[inline]
+    pub fn with_map_as_pairs(mut self, enabled: bool) -> Self {
+        self.map_as_pairs = enabled;
+        self
+    }
+
+    /// Allows structs encoded as arrays to have more elements than the struct has fields.
+    ///
+    /// The struct's fields are read from the start of the array and any elements after them are
+    /// skipped. This lets an older struct definition read data written by a newer one that
+    /// appended fields. Without this, the extra elements fail with `Error::LengthMismatch`.
+    #[inline]
+    pub fn with_ignore_extra_struct_fields(mut self, enabled: bool) -> Self {
+        self.ignore_extra_struct_fields = enabled;
+        self
+    }
+
+    /// Allows a one-element array to be decoded as its element when a scalar is expected.
+    ///
+    /// This is for producers that wrap single values in arrays: with it, `[5]` decodes into an
+    /// `i32` just like `5` does. It applies to booleans, numbers, chars and strings, and only to
+    /// arrays with the single-byte fixarray header, which is what encoders emit for one element.
+    ///
+    /// Be aware that this makes the encoding ambiguous. A reader expecting a scalar can no longer
+    /// tell a wrapped value from a plain one, so a producer that wrongly sent a list of one item
+    /// where a single item belongs goes unnoticed.
+    #[inline]
+    pub fn with_unwrap_singletons(mut self, enabled: bool) -> Self {
+        self.unwrap_singletons = enabled;
+        self
+    }
+
+    /// Allows a str or bin of `N` bytes to be decoded into a fixed-size array of `N` bytes.
+    ///
+    /// This makes `[u8; N]`, and tuples of `N` `u8`s, accept compact byte strings as well as
+    /// arrays of integers, which helps with fixed-size identifiers stored as strings. A str or
+    /// bin of any other length fails with `Error::LengthMismatch`.
+    #[inline]
+    pub fn with_byte_arrays(mut self, enabled: bool) -> Self {
+        self.byte_arrays = enabled;
+        self
+    }
+
+    /// Rejects integers decoded into `f32` or `f64` fields that the float can't hold exactly.
+    ///
+    /// By default an integer is accepted where a float is expected, and converted with the usual
+    /// rounding, so a `u64` above 2^53 silently comes out as a nearby `f64`. With this enabled
+    /// such values fail with `Error::PrecisionLoss` instead. Floats, and integers that convert
+    /// exactly, are unaffected.
+    #[inline]
+    pub fn with_strict_floats(mut self, enabled: bool) -> Self {
+        self.strict_floats = enabled;
+        self
+    }
+
+    /// Rejects ext values with more than `max_len` bytes of data.
+    ///
+    /// The ext header is checked before any of the data is read, so a bogus `ext 32` header
+    /// claiming gigabytes fails with `Error::LengthLimitExceeded` right away, instead of making
+    /// the deserializer allocate a buffer for it. There is no limit by default.
+    #[inline]
+    pub fn with_max_ext_len(mut self, max_len: u32) -> Self {
+        self.max_ext_len = max_len;
+        self
+    }
+
+    /// Accepts data written by msgpack-python with `use_bin_type=False`.
+    ///
+    /// With that setting Python writes both `str` and `bytes` objects with the str markers, so
+    /// which one a value was can't be told from the data. Enabling this assumes the Rust type
+    /// knows, and decodes str values into sequences of bytes such as `Vec<u8>`, whether or not
+    /// they are valid UTF-8, as well as into strings. Bin values, which newer producers may mix
+    /// in, are read as text wherever a str would be. Strings still have to be valid UTF-8, and ext
+    /// values are unaffected.
+    ///
+    /// This is shorthand for both [`with_legacy_raw`](Self::with_legacy_raw) and
+    /// [`with_bin_as_str`](Self::with_bin_as_str), which can also be set on their own.
+    #[inline]
+    pub fn with_python_compat(self, enabled: bool) -> Self {
+        self.with_legacy_raw(enabled).with_bin_as_str(enabled)
+    }
+
+    /// Registers a callback for decoding application-defined ext types.
+    ///
+    /// Whenever an ext value is met where any type is accepted, such as in `deserialize_any`, its
+    /// type id and data are passed to `handler`, and the returned [`Value`](value::Value) is
+    /// deserialized in place of the ext. This allows mapping ext payloads like UUIDs or decimals
+    /// to ordinary values. Types that ask for an ext explicitly still get the raw ext.
+    #[inline]
+    pub fn with_ext_handler(mut self, handler: Box<ExtHandlerFn>) -> Self {
+        self.ext_handler = Some(ExtHandler(handler));
+        self
+    }
 }
 
 impl<R: AsRef<[u8]>> Deserializer<ReadReader<Cursor<R>>> {
@@ -301,6 +652,47 @@ impl<R: AsRef<[u8]>> Deserializer<ReadReader<Cursor<R>>> {
     }
 }
 
+impl<R: Read + Seek, C> Deserializer<ReadReader<R>, C> {
+    /// Tries to realign the reader on the next value after a decoding error, so that decoding a
+    /// stream of values can carry on past a corrupt one.
+    ///
+    /// MessagePack has no frame delimiters, so this is only a heuristic. Starting at the current
+    /// position, it looks for the first offset at which a complete, well-formed value can be
+    /// skipped, and rewinds to that offset. Bytes that can't start a value, such as the reserved
+    /// marker `0xc1`, and values that run past the end of the input are stepped over one byte at a
+    /// time. Leftovers of the corrupt value that happen to be well-formed can't be told apart from
+    /// the next value, and will be decoded as such.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::InvalidMarkerRead` if the end of the input is reached without finding a
+    /// value, or `Error::InvalidDataRead` if seeking fails.
+    pub fn recover_to_next(&mut self) -> Result<(), Error> {
+        self.marker = None;
+        let seek = |rd: &mut R, pos| rd.seek(pos).map_err(Error::InvalidDataRead);
+
+        let mut start = seek(&mut self.rd.rd, SeekFrom::Current(0))?;
+        loop {
+            match skip_value(&mut self.rd).map_err(Error::from) {
+                Ok(()) => {
+                    seek(&mut self.rd.rd, SeekFrom::Start(start))?;
+                    return Ok(());
+                }
+                // Nothing left at all, as opposed to a value cut short.
+                Err(Error::InvalidMarkerRead(err))
+                    if seek(&mut self.rd.rd, SeekFrom::Current(0))? == start =>
+                {
+                    return Err(Error::InvalidMarkerRead(err));
+                }
+                Err(..) => {
+                    start += 1;
+                    seek(&mut self.rd.rd, SeekFrom::Start(start))?;
+                }
+            }
+        }
+    }
+}
+
 impl<'de, R> Deserializer<ReadRefReader<'de, R>>
 where
     R: AsRef<[u8]> + ?Sized,
@@ -314,6 +706,16 @@ where
             _config: PhantomData,
             marker: None,
             depth: 1024,
+            skip_leading: 0,
+            legacy_raw: false,
+            bin_as_str: false,
+            map_as_pairs: false,
+            ignore_extra_struct_fields: false,
+            unwrap_singletons: false,
+            byte_arrays: false,
+            strict_floats: false,
+            max_ext_len: u32::MAX,
+            ext_handler: None,
         }
     }
 
@@ -352,7 +754,7 @@ fn read_i128_marker<'de, R: ReadSlice<'de>>(marker: Marker, rd: &mut R) -> Resul
         }
         Marker::FixArray(len) => read_128_buf(rd, len)?,
         marker => {
-            consume_unexpected_value(rd, marker)?;
+            read::skip_rest(rd, marker)?;
             return Err(Error::TypeMismatch(marker));
         }
     })
@@ -360,15 +762,21 @@ fn read_i128_marker<'de, R: ReadSlice<'de>>(marker: Marker, rd: &mut R) -> Resul
 
 fn read_128_buf<'de, R: ReadSlice<'de>>(rd: &mut R, len: u8) -> Result<i128, Error> {
     if len != 16 {
-        return Err(Error::LengthMismatch(16));
+        return Err(Error::LengthMismatch {
+            expected: 16,
+            actual: len.into(),
+        });
     }
     let buf = match read_bin_data(rd, 16)? {
         Reference::Borrowed(buf) => buf,
         Reference::Copied(buf) => buf,
     };
-    Ok(i128::from_be_bytes(
-        buf.try_into().map_err(|_| Error::LengthMismatch(16))?,
-    ))
+    Ok(i128::from_be_bytes(buf.try_into().map_err(|_| {
+        Error::LengthMismatch {
+            expected: 16,
+            actual: buf.len() as u32,
+        }
+    })?))
 }
 
 fn read_str_data<'de, V, R>(rd: &mut R, len: u32, visitor: V) -> Result<V::Value, Error>
@@ -436,7 +844,7 @@ fn ext_len<'de, R: Read + ReadSlice<'de>>(rd: &mut R, marker: Marker) -> Result<
         Marker::Ext16 => u32::from(read_u16(rd)?),
         Marker::Ext32 => read_u32(rd)?,
         _ => {
-            consume_unexpected_value(rd, marker)?;
+            read::skip_rest(rd, marker)?;
             return Err(Error::TypeMismatch(marker));
         }
     })
@@ -570,120 +978,22 @@ fn any_num<'de, R: ReadSlice<'de>, V: Visitor<'de>>(
         Marker::F32 => visitor.visit_f32(rd.read_data_f32()?),
         Marker::F64 => visitor.visit_f64(rd.read_data_f64()?),
         other_marker => {
-            consume_unexpected_value(rd, marker)?;
+            read::skip_rest(rd, marker)?;
             Err(Error::TypeMismatch(other_marker))
         }
     }
 }
 
-fn consume_unexpected_values<'de, R: ReadSlice<'de>>(
-    rd: &mut R,
-    count: usize,
-) -> Result<(), Error> {
-    for _ in 0..count {
-        let marker = rmp::decode::read_marker(rd)?;
-        consume_unexpected_value(rd, marker)?;
-    }
-    Ok(())
-}
-
-fn consume_unexpected_value<'de, R: ReadSlice<'de>>(
-    rd: &mut R,
-    marker: Marker,
-) -> Result<(), Error> {
-    // This function is for when we read a marker that indicates a type we don't expect to see.
-    // but in order for future reads to be correct, we need to consume the data indicated by the marker.
-    // note that the only errors we expect to arise from here are invalid data reads,
-    // which the decoder is generally unable to recover from
-    match marker {
-        Marker::Null => (),
-        Marker::True | Marker::False => (),
-        Marker::FixPos(_) | Marker::FixNeg(_) => (),
-        Marker::U8 => {
-            rd.read_data_u8()?;
-        }
-        Marker::U16 => {
-            rd.read_data_u16()?;
-        }
-        Marker::U32 => {
-            rd.read_data_u32()?;
-        }
-        Marker::U64 => {
-            rd.read_data_u64()?;
-        }
-        Marker::I8 => {
-            rd.read_data_i8()?;
-        }
-        Marker::I16 => {
-            rd.read_data_i16()?;
-        }
-        Marker::I32 => {
-            rd.read_data_i32()?;
-        }
-        Marker::I64 => {
-            rd.read_data_i64()?;
-        }
-        Marker::F32 => {
-            rd.read_data_f32()?;
-        }
-        Marker::F64 => {
-            rd.read_data_f64()?;
-        }
-        Marker::FixStr(len) => {
-            rd.read_slice(len as usize)
-                .map_err(Error::InvalidDataRead)?;
-        }
-        Marker::Str8 | Marker::Bin8 => {
-            let len = rd.read_data_u8()?;
-            rd.read_slice(len as usize)
-                .map_err(Error::InvalidDataRead)?;
-        }
-        Marker::Str16 | Marker::Bin16 => {
-            let len = rd.read_data_u16()?;
-            rd.read_slice(len as usize)
-                .map_err(Error::InvalidDataRead)?;
-        }
-        Marker::Str32 | Marker::Bin32 => {
-            let len = rd.read_data_u32()?;
-            rd.read_slice(len as usize)
-                .map_err(Error::InvalidDataRead)?;
-        }
-        Marker::FixArray(len) => consume_unexpected_values(rd, len as usize)?,
-        Marker::Array16 => {
-            let len = rd.read_data_u16()?;
-            consume_unexpected_values(rd, len as usize)?
-        }
-        Marker::Array32 => {
-            let len = rd.read_data_u32()?;
-            consume_unexpected_values(rd, len as usize)?
-        }
-        Marker::FixMap(len) => consume_unexpected_values(rd, len as usize * 2)?,
-        Marker::Map16 => {
-            let len = rd.read_data_u16()?;
-            consume_unexpected_values(rd, len as usize * 2)?
-        }
-        Marker::Map32 => {
-            let len = rd.read_data_u32()?;
-            consume_unexpected_values(rd, len as usize * 2)?
-        }
-        Marker::FixExt1
-        | Marker::FixExt2
-        | Marker::FixExt4
-        | Marker::FixExt8
-        | Marker::FixExt16
-        | Marker::Ext8
-        | Marker::Ext16
-        | Marker::Ext32 => {
-            let len = ext_len(rd, marker)?;
-            rd.read_slice(len as usize + 1)
-                .map_err(Error::InvalidDataRead)?;
+impl<'de, R: ReadSlice<'de>, C: SerializerConfig> Deserializer<R, C> {
+    /// Takes the marker of a value expected to be a scalar, stepping into a one-element array
+    /// around it if `unwrap_singletons` is enabled.
+    fn take_scalar_marker(&mut self) -> Result<Marker, MarkerReadError> {
+        match self.take_or_read_marker()? {
+            Marker::FixArray(1) if self.unwrap_singletons => self.read_marker(),
+            marker => Ok(marker),
         }
-        Marker::Reserved => (),
-    };
-    Ok(())
-}
+    }
 
-impl<'de, R: ReadSlice<'de>, C: SerializerConfig> Deserializer<R, C> {
     fn any_inner<V: Visitor<'de>>(
         &mut self,
         visitor: V,
@@ -714,6 +1024,13 @@ impl<'de, R: ReadSlice<'de>, C: SerializerConfig> Deserializer<R, C> {
                     Marker::Str32 => read_u32(&mut self.rd),
                     _ => return Err(Error::TypeMismatch(Marker::Reserved)),
                 }?;
+                if self.legacy_raw && !allow_bytes {
+                    return match read_bin_data(&mut self.rd, len)? {
+                        Reference::Borrowed(buf) | Reference::Copied(buf) => {
+                            visitor.visit_seq(SeqDeserializer::new(buf.iter().copied()))
+                        }
+                    };
+                }
                 read_str_data(&mut self.rd, len, visitor)
             }
             Marker::FixArray(_) | Marker::Array16 | Marker::Array32 => {
@@ -724,25 +1041,7 @@ impl<'de, R: ReadSlice<'de>, C: SerializerConfig> Deserializer<R, C> {
                     _ => return Err(Error::TypeMismatch(Marker::Reserved)),
                 };
 
-                depth_count!(self.depth, {
-                    let mut seq = SeqAccess::new(self, len);
-                    fn exhaust_seq_access<'a, R: ReadSlice<'a>, C: SerializerConfig>(
-                        seq: &mut SeqAccess<R, C>,
-                    ) {
-                        // we need to exhaust the sequence to keep the reader in a consistent state
-                        while let Ok(Some(_)) = seq.next_element::<IgnoredAny>() {}
-                    }
-                    let res = visitor.visit_seq(&mut seq).inspect_err(|_| {
-                        exhaust_seq_access(&mut seq);
-                    })?;
-                    match seq.left {
-                        0 => Ok(res),
-                        excess => {
-                            exhaust_seq_access(&mut seq);
-                            Err(Error::LengthMismatch(len - excess))
-                        }
-                    }
-                })
+                self.visit_array(len, visitor, false)
             }
             Marker::FixMap(_) | Marker::Map16 | Marker::Map32 => {
                 let len = match marker {
@@ -751,6 +1050,7 @@ impl<'de, R: ReadSlice<'de>, C: SerializerConfig> Deserializer<R, C> {
                     Marker::Map32 => read_u32(&mut self.rd)?,
                     _ => return Err(Error::TypeMismatch(Marker::Reserved)),
                 };
+                self.check_declared_len(len, 2)?;
 
                 depth_count!(self.depth, {
                     let mut seq = MapAccess::new(self, len);
@@ -767,7 +1067,10 @@ impl<'de, R: ReadSlice<'de>, C: SerializerConfig> Deserializer<R, C> {
                         0 => Ok(res),
                         excess => {
                             exhaust_map_access(&mut seq);
-                            Err(Error::LengthMismatch(len - excess))
+                            Err(Error::LengthMismatch {
+                                expected: (len - excess) as usize,
+                                actual: len,
+                            })
                         }
                     }
                 })
@@ -779,6 +1082,9 @@ impl<'de, R: ReadSlice<'de>, C: SerializerConfig> Deserializer<R, C> {
                     Marker::Bin32 => read_u32(&mut self.rd),
                     _ => return Err(Error::TypeMismatch(Marker::Reserved)),
                 }?;
+                if self.bin_as_str && allow_bytes {
+                    return read_str_data(&mut self.rd, len, visitor);
+                }
                 match read_bin_data(&mut self.rd, len)? {
                     Reference::Borrowed(buf) if allow_bytes => visitor.visit_borrowed_bytes(buf),
                     Reference::Copied(buf) if allow_bytes => visitor.visit_bytes(buf),
@@ -796,6 +1102,20 @@ impl<'de, R: ReadSlice<'de>, C: SerializerConfig> Deserializer<R, C> {
             | Marker::Ext16
             | Marker::Ext32 => {
                 let len = ext_len(&mut self.rd, marker)?;
+                self.check_ext_len(len)?;
+                if let Some(ExtHandler(handler)) = &mut self.ext_handler {
+                    let typeid = self.rd.read_data_i8()?;
+                    let val = match self.rd.read_slice(len as usize) {
+                        Ok(Reference::Borrowed(data) | Reference::Copied(data)) => {
+                            handler(typeid, data)?
+                        }
+                        Err(err) => return Err(Error::InvalidDataRead(err)),
+                    };
+                    return de::Deserializer::deserialize_any(
+                        value_de::TreeDeserializer::copying(&val),
+                        visitor,
+                    );
+                }
                 depth_count!(
                     self.depth,
                     visitor.visit_newtype_struct(ExtDeserializer::new(self, len))
@@ -806,6 +1126,147 @@ impl<'de, R: ReadSlice<'de>, C: SerializerConfig> Deserializer<R, C> {
     }
 }
 
+impl<'de, R: ReadSlice<'de>, C: SerializerConfig> Deserializer<R, C> {
+    fn visit_array<V: Visitor<'de>>(
+        &mut self,
+        len: u32,
+        visitor: V,
+        allow_excess: bool,
+    ) -> Result<V::Value, Error> {
+        self.check_declared_len(len, 1)?;
+        depth_count!(self.depth, {
+            let mut seq = SeqAccess::new(self, len);
+            let res = visitor.visit_seq(&mut seq).inspect_err(|_| {
+                exhaust_seq_access(&mut seq);
+            })?;
+            match seq.left {
+                0 => Ok(res),
+                _ if allow_excess => {
+                    exhaust_seq_access(&mut seq);
+                    Ok(res)
+                }
+                excess => {
+                    exhaust_seq_access(&mut seq);
+                    Err(Error::LengthMismatch {
+                        expected: (len - excess) as usize,
+                        actual: len,
+                    })
+                }
+            }
+        })
+    }
+
+    /// Fails fast if `len` elements of at least `min_size` bytes each can't fit into the rest of
+    /// the input, so that a bogus header can't make us loop over billions of missing elements.
+    fn check_declared_len(&self, len: u32, min_size: u64) -> Result<(), Error> {
+        match self.rd.remaining_len() {
+            Some(remaining) if u64::from(len) * min_size > remaining as u64 => {
+                Err(Error::LengthLimitExceeded {
+                    len,
+                    max_len: (remaining as u64 / min_size).min(u32::MAX.into()) as u32,
+                })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn check_ext_len(&self, len: u32) -> Result<(), Error> {
+        if len > self.max_ext_len {
+            return Err(Error::LengthLimitExceeded {
+                len,
+                max_len: self.max_ext_len,
+            });
+        }
+        Ok(())
+    }
+
+    fn read_array_len(&mut self) -> Result<u32, Error> {
+        match self.take_or_read_marker()? {
+            Marker::FixArray(len) => Ok(len.into()),
+            Marker::Array16 => Ok(read_u16(&mut self.rd)?.into()),
+            Marker::Array32 => read_u32(&mut self.rd),
+            marker => Err(Error::TypeMismatch(marker)),
+        }
+    }
+
+    fn tuple<V: Visitor<'de>>(&mut self, tuple_len: usize, visitor: V) -> Result<V::Value, Error> {
+        let len = self.read_array_len()?;
+
+        if len as usize != tuple_len {
+            depth_count!(
+                self.depth,
+                exhaust_seq_access(&mut SeqAccess::new(self, len))
+            );
+            return Err(Error::LengthMismatch {
+                expected: tuple_len,
+                actual: len,
+            });
+        }
+        self.visit_array(len, visitor, false)
+    }
+
+    /// Decodes a str or bin, whose marker was already taken, as a tuple of its bytes.
+    fn byte_array<V: Visitor<'de>>(
+        &mut self,
+        marker: Marker,
+        tuple_len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        let len = match marker {
+            Marker::FixStr(len) => len.into(),
+            Marker::Str8 | Marker::Bin8 => read_u8(&mut self.rd)?.into(),
+            Marker::Str16 | Marker::Bin16 => read_u16(&mut self.rd)?.into(),
+            Marker::Str32 | Marker::Bin32 => read_u32(&mut self.rd)?,
+            marker => return Err(Error::TypeMismatch(marker)),
+        };
+        match read_bin_data(&mut self.rd, len)? {
+            Reference::Borrowed(buf) | Reference::Copied(buf) if buf.len() == tuple_len => {
+                visitor.visit_seq(SeqDeserializer::new(buf.iter().copied()))
+            }
+            _ => Err(Error::LengthMismatch {
+                expected: tuple_len,
+                actual: len,
+            }),
+        }
+    }
+
+    fn map_as_pairs<V: Visitor<'de>>(&mut self, visitor: V) -> Result<V::Value, Error> {
+        let len = match self.take_or_read_marker()? {
+            Marker::FixMap(len) => len.into(),
+            Marker::Map16 => read_u16(&mut self.rd)?.into(),
+            Marker::Map32 => read_u32(&mut self.rd)?,
+            marker => return Err(Error::TypeMismatch(marker)),
+        };
+        self.check_declared_len(len, 2)?;
+
+        depth_count!(self.depth, {
+            let mut seq = PairSeqAccess {
+                de: self,
+                left: len,
+            };
+            fn exhaust_pair_access<'a, R: ReadSlice<'a>, C: SerializerConfig>(
+                seq: &mut PairSeqAccess<R, C>,
+            ) {
+                // we need to exhaust the map to keep the reader in a consistent state
+                while let Ok(Some(_)) = seq.next_element::<IgnoredAny>() {}
+            }
+            let res = visitor.visit_seq(&mut seq).inspect_err(|_| {
+                exhaust_pair_access(&mut seq);
+            })?;
+            match seq.left {
+                0 => Ok(res),
+                excess => {
+                    exhaust_pair_access(&mut seq);
+                    Err(Error::LengthMismatch {
+                        expected: (len - excess) as usize,
+                        actual: len,
+                    })
+                }
+            }
+        })
+    }
+}
+
 impl<'de, R: ReadSlice<'de>, C: SerializerConfig> serde::Deserializer<'de>
     for &mut Deserializer<R, C>
 {
@@ -871,9 +1332,14 @@ impl<'de, R: ReadSlice<'de>, C: SerializerConfig> serde::Deserializer<'de>
                     self.marker = None;
                     visitor.visit_enum(VariantAccess::new(self))
                 }
-                n => Err(Error::LengthMismatch(n)),
+                n => Err(Error::LengthMismatch {
+                    expected: 1,
+                    actual: n,
+                }),
             },
-            // TODO: Check this is a string
+            // Anything else is a unit variant written as just its identifier, which is how the
+            // serializer writes unit variants. The variant visitor resolves a str by name and an
+            // integer by index.
             Err(_) => visitor.visit_enum(UnitVariantAccess::new(self)),
         }
     }
@@ -890,6 +1356,7 @@ impl<'de, R: ReadSlice<'de>, C: SerializerConfig> serde::Deserializer<'de>
             let marker = self.take_or_read_marker()?;
 
             let len = ext_len(&mut self.rd, marker)?;
+            self.check_ext_len(len)?;
             let ext_de = ExtDeserializer::new(self, len);
             return visitor.visit_newtype_struct(ext_de);
         }
@@ -938,15 +1405,35 @@ impl<'de, R: ReadSlice<'de>, C: SerializerConfig> serde::Deserializer<'de>
     where
         V: Visitor<'de>,
     {
+        if self.map_as_pairs {
+            if let Marker::FixMap(_) | Marker::Map16 | Marker::Map32 = self.peek_or_read_marker()? {
+                return self.map_as_pairs(visitor);
+            }
+        }
         self.any_inner(visitor, false)
     }
 
     #[inline]
-    fn deserialize_tuple<V>(self, _len: usize, visitor: V) -> Result<V::Value, Self::Error>
+    fn deserialize_tuple<V>(self, len: usize, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: Visitor<'de>,
     {
-        self.any_inner(visitor, false)
+        match self.peek_or_read_marker()? {
+            Marker::FixArray(_) | Marker::Array16 | Marker::Array32 => self.tuple(len, visitor),
+            marker @ (Marker::FixStr(_)
+            | Marker::Str8
+            | Marker::Str16
+            | Marker::Str32
+            | Marker::Bin8
+            | Marker::Bin16
+            | Marker::Bin32)
+                if self.byte_arrays =>
+            {
+                self.marker = None;
+                self.byte_array(marker, len, visitor)
+            }
+            _ => self.any_inner(visitor, false),
+        }
     }
 
     #[inline]
@@ -959,6 +1446,14 @@ impl<'de, R: ReadSlice<'de>, C: SerializerConfig> serde::Deserializer<'de>
     where
         V: Visitor<'de>,
     {
+        if self.ignore_extra_struct_fields {
+            if let Marker::FixArray(_) | Marker::Array16 | Marker::Array32 =
+                self.peek_or_read_marker()?
+            {
+                let len = self.read_array_len()?;
+                return self.visit_array(len, visitor, true);
+            }
+        }
         self.any_inner(visitor, false)
     }
 
@@ -977,15 +1472,52 @@ impl<'de, R: ReadSlice<'de>, C: SerializerConfig> serde::Deserializer<'de>
 
     forward_to_deserialize_any! {
         bytes byte_buf unit
-        map identifier str string char
+        map
         ignored_any
     }
 
+    fn deserialize_identifier<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        // Maps may have nil keys, but a field or variant name can't be nil. Fail with a clearer
+        // message than the visitor's complaint about a unit value.
+        if self.peek_or_read_marker()? == Marker::Null {
+            self.marker = None;
+            return Err(Error::Syntax(
+                "found nil where a field or variant name was expected".into(),
+            ));
+        }
+        self.any_inner(visitor, true)
+    }
+
+    fn deserialize_str<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.marker = Some(self.take_scalar_marker()?);
+        self.any_inner(visitor, true)
+    }
+
+    fn deserialize_string<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_char<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_str(visitor)
+    }
+
     fn deserialize_bool<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: Visitor<'de>,
     {
-        let marker = self.take_or_read_marker()?;
+        let marker = self.take_scalar_marker()?;
         any_num(&mut self.rd, visitor, marker)
     }
 
@@ -993,7 +1525,7 @@ impl<'de, R: ReadSlice<'de>, C: SerializerConfig> serde::Deserializer<'de>
     where
         V: Visitor<'de>,
     {
-        let marker = self.take_or_read_marker()?;
+        let marker = self.take_scalar_marker()?;
         any_num(&mut self.rd, visitor, marker)
     }
 
@@ -1001,7 +1533,7 @@ impl<'de, R: ReadSlice<'de>, C: SerializerConfig> serde::Deserializer<'de>
     where
         V: Visitor<'de>,
     {
-        let marker = self.take_or_read_marker()?;
+        let marker = self.take_scalar_marker()?;
         any_num(&mut self.rd, visitor, marker)
     }
 
@@ -1009,7 +1541,7 @@ impl<'de, R: ReadSlice<'de>, C: SerializerConfig> serde::Deserializer<'de>
     where
         V: Visitor<'de>,
     {
-        let marker = self.take_or_read_marker()?;
+        let marker = self.take_scalar_marker()?;
         any_num(&mut self.rd, visitor, marker)
     }
 
@@ -1017,7 +1549,7 @@ impl<'de, R: ReadSlice<'de>, C: SerializerConfig> serde::Deserializer<'de>
     where
         V: Visitor<'de>,
     {
-        let marker = self.take_or_read_marker()?;
+        let marker = self.take_scalar_marker()?;
         any_num(&mut self.rd, visitor, marker)
     }
 
@@ -1025,7 +1557,7 @@ impl<'de, R: ReadSlice<'de>, C: SerializerConfig> serde::Deserializer<'de>
     where
         V: Visitor<'de>,
     {
-        let marker = self.take_or_read_marker()?;
+        let marker = self.take_scalar_marker()?;
         any_num(&mut self.rd, visitor, marker)
     }
 
@@ -1033,7 +1565,7 @@ impl<'de, R: ReadSlice<'de>, C: SerializerConfig> serde::Deserializer<'de>
     where
         V: Visitor<'de>,
     {
-        let marker = self.take_or_read_marker()?;
+        let marker = self.take_scalar_marker()?;
         any_num(&mut self.rd, visitor, marker)
     }
 
@@ -1041,7 +1573,7 @@ impl<'de, R: ReadSlice<'de>, C: SerializerConfig> serde::Deserializer<'de>
     where
         V: Visitor<'de>,
     {
-        let marker = self.take_or_read_marker()?;
+        let marker = self.take_scalar_marker()?;
         any_num(&mut self.rd, visitor, marker)
     }
 
@@ -1049,7 +1581,7 @@ impl<'de, R: ReadSlice<'de>, C: SerializerConfig> serde::Deserializer<'de>
     where
         V: Visitor<'de>,
     {
-        let marker = self.take_or_read_marker()?;
+        let marker = self.take_scalar_marker()?;
         any_num(&mut self.rd, visitor, marker)
     }
 
@@ -1057,7 +1589,15 @@ impl<'de, R: ReadSlice<'de>, C: SerializerConfig> serde::Deserializer<'de>
     where
         V: Visitor<'de>,
     {
-        let marker = self.take_or_read_marker()?;
+        let marker = self.take_scalar_marker()?;
+        if self.strict_floats && marker.is_int() {
+            let val = read_i128_marker(marker, &mut self.rd)?;
+            let float = val as f32;
+            if float as i128 != val {
+                return Err(Error::PrecisionLoss);
+            }
+            return visitor.visit_f32(float);
+        }
         any_num(&mut self.rd, visitor, marker)
     }
 
@@ -1065,7 +1605,15 @@ impl<'de, R: ReadSlice<'de>, C: SerializerConfig> serde::Deserializer<'de>
     where
         V: Visitor<'de>,
     {
-        let marker = self.take_or_read_marker()?;
+        let marker = self.take_scalar_marker()?;
+        if self.strict_floats && marker.is_int() {
+            let val = read_i128_marker(marker, &mut self.rd)?;
+            let float = val as f64;
+            if float as i128 != val {
+                return Err(Error::PrecisionLoss);
+            }
+            return visitor.visit_f64(float);
+        }
         any_num(&mut self.rd, visitor, marker)
     }
 }
@@ -1106,6 +1654,78 @@ impl<'de, 'a, R: ReadSlice<'de> + 'a, C: SerializerConfig> de::SeqAccess<'de>
     }
 }
 
+fn exhaust_seq_access<'a, R: ReadSlice<'a>, C: SerializerConfig>(seq: &mut SeqAccess<R, C>) {
+    // we need to exhaust the sequence to keep the reader in a consistent state
+    while let Ok(Some(_)) = seq.next_element::<IgnoredAny>() {}
+}
+
+/// Presents the entries of a map as a sequence of `(key, value)` pairs.
+struct PairSeqAccess<'a, R, C> {
+    de: &'a mut Deserializer<R, C>,
+    left: u32,
+}
+
+impl<'de, 'a, R: ReadSlice<'de> + 'a, C: SerializerConfig> de::SeqAccess<'de>
+    for PairSeqAccess<'a, R, C>
+{
+    type Error = Error;
+
+    #[inline]
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>, Self::Error>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        if self.left > 0 {
+            self.left -= 1;
+            Ok(Some(
+                seed.deserialize(PairDeserializer { de: &mut *self.de })?,
+            ))
+        } else {
+            Ok(None)
+        }
+    }
+
+    #[inline(always)]
+    fn size_hint(&self) -> Option<usize> {
+        self.left.try_into().ok()
+    }
+}
+
+/// Deserializes a single map entry as a two-element sequence.
+struct PairDeserializer<'a, R, C> {
+    de: &'a mut Deserializer<R, C>,
+}
+
+impl<'de, 'a, R: ReadSlice<'de> + 'a, C: SerializerConfig> de::Deserializer<'de>
+    for PairDeserializer<'a, R, C>
+{
+    type Error = Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let mut seq = SeqAccess::new(self.de, 2);
+        let res = visitor.visit_seq(&mut seq)?;
+        match seq.left {
+            0 => Ok(res),
+            excess => {
+                while let Ok(Some(_)) = seq.next_element::<IgnoredAny>() {}
+                Err(Error::LengthMismatch {
+                    expected: (2 - excess) as usize,
+                    actual: 2,
+                })
+            }
+        }
+    }
+
+    forward_to_deserialize_any! {
+        bool u8 u16 u32 u64 i8 i16 i32 i64 f32 f64 char str string unit option
+        seq bytes byte_buf map unit_struct newtype_struct
+        tuple_struct struct identifier tuple enum ignored_any
+    }
+}
+
 struct MapAccess<'a, R, C> {
     de: &'a mut Deserializer<R, C>,
     left: u32,
@@ -1298,6 +1918,15 @@ pub enum Reference<'b, 'c, T: ?Sized + 'static> {
 pub trait ReadSlice<'de>: Read {
     /// Reads the exact number of bytes from the underlying byte-array.
     fn read_slice<'a>(&'a mut self, len: usize) -> Result<Reference<'de, 'a, [u8]>, io::Error>;
+
+    /// Returns the number of bytes left to read, if known.
+    ///
+    /// When known, array and map headers declaring more elements than could fit into the rest of
+    /// the input are rejected before any element is decoded.
+    #[inline]
+    fn remaining_len(&self) -> Option<usize> {
+        None
+    }
 }
 
 /// Owned reader wrapper.
@@ -1308,8 +1937,9 @@ pub struct ReadReader<R: Read> {
 }
 
 impl<R: Read> ReadReader<R> {
+    /// Wraps a reader, copying slices into an internal buffer as they're read.
     #[inline]
-    fn new(rd: R) -> Self {
+    pub fn new(rd: R) -> Self {
         Self {
             rd,
             buf: Vec::with_capacity(128),
@@ -1393,6 +2023,28 @@ impl<'de, T: AsRef<[u8]> + ?Sized> ReadSlice<'de> for ReadRefReader<'de, T> {
         self.buf = b;
         Ok(Reference::Borrowed(a))
     }
+
+    #[inline]
+    fn remaining_len(&self) -> Option<usize> {
+        Some(self.buf.len())
+    }
+}
+
+impl<'de> ReadSlice<'de> for &'de [u8] {
+    #[inline]
+    fn read_slice<'a>(&'a mut self, len: usize) -> Result<Reference<'de, 'a, [u8]>, io::Error> {
+        if len > self.len() {
+            return Err(ErrorKind::UnexpectedEof.into());
+        }
+        let (a, b) = self.split_at(len);
+        *self = b;
+        Ok(Reference::Borrowed(a))
+    }
+
+    #[inline]
+    fn remaining_len(&self) -> Option<usize> {
+        Some(self.len())
+    }
 }
 
 #[test]
@@ -1412,6 +2064,117 @@ fn test_as_ref_reader() {
     );
 }
 
+/// Reads values written by [`encode::FramedWriter`](crate::encode::FramedWriter), each preceded
+/// by a big-endian `u32` byte length.
+///
+/// Every frame is read in full before it's decoded, so a value never consumes bytes of the next
+/// frame, even if it's malformed.
+///
+/// # Examples
+///
+/// ```
+/// use messpack_serde::Deserializer;
+///
+/// let buf = [0, 0, 0, 1, 0x05, 0, 0, 0, 1, 0x06];
+/// let mut framed = Deserializer::framed(&buf[..]);
+///
+/// assert_eq!(Some(5), framed.read::<u8>().unwrap());
+/// assert_eq!(Some(6), framed.read::<u8>().unwrap());
+/// assert_eq!(None, framed.read::<u8>().unwrap());
+/// ```
+#[derive(Debug)]
+pub struct FramedReader<R> {
+    rd: R,
+    buf: Vec<u8>,
+}
+
+impl<R: Read> FramedReader<R> {
+    /// Reads and decodes the next frame, returning `None` if the stream ends before it starts.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::InvalidDataRead` if the stream ends inside a frame, any error decoding the
+    /// value, and `Error::Uncategorized` if the value doesn't span the whole frame.
+    pub fn read<T: DeserializeOwned>(&mut self) -> Result<Option<T>, Error> {
+        let mut prefix = [0; 4];
+        let mut filled = 0;
+        while filled < prefix.len() {
+            match self.rd.read(&mut prefix[filled..]) {
+                Ok(0) if filled == 0 => return Ok(None),
+                Ok(0) => return Err(Error::InvalidDataRead(ErrorKind::UnexpectedEof.into())),
+                Ok(n) => filled += n,
+                Err(err) if err.kind() == ErrorKind::Interrupted => {}
+                Err(err) => return Err(Error::InvalidDataRead(err)),
+            }
+        }
+        let len = u32::from_be_bytes(prefix);
+
+        // Grows with the data actually received, rather than trusting the prefix up front.
+        self.buf.clear();
+        (&mut self.rd)
+            .take(u64::from(len))
+            .read_to_end(&mut self.buf)
+            .map_err(Error::InvalidDataRead)?;
+        if self.buf.len() != len as usize {
+            return Err(Error::InvalidDataRead(ErrorKind::UnexpectedEof.into()));
+        }
+
+        let mut rd = &self.buf[..];
+        let val = T::deserialize(&mut Deserializer::new(&mut rd))?;
+        if !rd.is_empty() {
+            return Err(Error::Uncategorized(format!(
+                "{} trailing bytes in frame",
+                rd.len()
+            )));
+        }
+        Ok(Some(val))
+    }
+
+    /// Gets a reference to the underlying reader.
+    #[inline(always)]
+    pub fn get_ref(&self) -> &R {
+        &self.rd
+    }
+
+    /// Gets a mutable reference to the underlying reader.
+    #[inline(always)]
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.rd
+    }
+
+    /// Unwraps this `FramedReader`, returning the underlying reader.
+    #[inline]
+    pub fn into_inner(self) -> R {
+        self.rd
+    }
+}
+
+/// Interprets a [`Value`](value::Value) tree as an instance of type `T`.
+///
+/// This is the counterpart of [`to_value`](crate::to_value), and accepts the same as decoding the
+/// encoded `Value` with [`from_slice`]. The tree is read in place by a [`ValueDeserializer`].
+///
+/// # Errors
+///
+/// This conversion can fail if the structure of the Value does not match the structure expected
+/// by `T`, or if `T`'s implementation of `Deserialize` decides that something is wrong with it.
+///
+/// # Examples
+///
+/// ```
+/// use messpack_serde::decode::value::Value;
+///
+/// let val = Value::Array(vec![Value::from(1), Value::from("a")]);
+///
+/// assert_eq!((1, "a".to_string()), messpack_serde::from_value(val).unwrap());
+/// ```
+pub fn from_value<T>(val: value::Value) -> Result<T, Error>
+where
+    T: DeserializeOwned,
+{
+    T::deserialize(ValueDeserializer::new(&val))
+}
+
 /// Deserialize an instance of type `T` from an I/O stream of MessagePack.
 ///
 /// # Errors
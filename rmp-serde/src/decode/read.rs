@@ -0,0 +1,1337 @@
+//! Helpers for decoding MessagePack by hand, without going through serde.
+//!
+//! These build on top of the `rmp::decode` primitives and can be freely mixed with them.
+
+use std::borrow::Cow;
+use std::io::{self, BufRead, Read};
+use std::str;
+
+use rmp::decode::{self, ExtMeta, RmpRead, ValueReadError};
+use rmp::Marker;
+
+use super::value::Integer;
+use super::{Error, ReadSlice, Reference};
+use crate::marker::MarkerExt;
+
+/// Upper bound on the number of elements to preallocate for, so that a bogus length header can't
+/// make us allocate huge amounts of memory up front.
+const MAX_PREALLOC: u32 = 4096;
+
+/// Reads an array header and then calls `f` once per element, collecting the results.
+///
+/// Decoding stops at the first error returned by `f`, leaving the reader positioned inside the
+/// array.
+///
+/// # Examples
+///
+/// ```
+/// use messpack_serde::decode::read_array_with;
+///
+/// let buf = [0x93, 0x01, 0xcd, 0x01, 0x2c, 0xce, 0x00, 0x01, 0x00, 0x00];
+/// let vals: Vec<u32> = read_array_with(&mut &buf[..], rmp::decode::read_int).unwrap();
+///
+/// assert_eq!(vec![1, 300, 65536], vals);
+/// ```
+pub fn read_array_with<R, T, E, F>(rd: &mut R, mut f: F) -> Result<Vec<T>, E>
+where
+    R: Read,
+    E: From<ValueReadError>,
+    F: FnMut(&mut R) -> Result<T, E>,
+{
+    let len = decode::read_array_len(rd)?;
+    let mut vals = Vec::with_capacity(len.min(MAX_PREALLOC) as usize);
+    for _ in 0..len {
+        vals.push(f(rd)?);
+    }
+    Ok(vals)
+}
+
+/// Reads a map header and then calls `fk` and `fv` once per entry, collecting the key-value pairs
+/// in their encoded order.
+///
+/// Decoding stops at the first error returned by either reader, leaving the reader positioned
+/// inside the map.
+///
+/// # Examples
+///
+/// ```
+/// use messpack_serde::decode::{read_map_with, Error};
+///
+/// // {1: true, 2: false}
+/// let buf = [0x82, 0x01, 0xc3, 0x02, 0xc2];
+/// let entries: Vec<(u8, bool)> = read_map_with(
+///     &mut &buf[..],
+///     |rd| Ok::<_, Error>(rmp::decode::read_int(rd)?),
+///     |rd| Ok(rmp::decode::read_bool(rd)?),
+/// )
+/// .unwrap();
+///
+/// assert_eq!(vec![(1, true), (2, false)], entries);
+/// ```
+pub fn read_map_with<R, K, V, E, FK, FV>(
+    rd: &mut R,
+    mut fk: FK,
+    mut fv: FV,
+) -> Result<Vec<(K, V)>, E>
+where
+    R: Read,
+    E: From<ValueReadError>,
+    FK: FnMut(&mut R) -> Result<K, E>,
+    FV: FnMut(&mut R) -> Result<V, E>,
+{
+    let len = decode::read_map_len(rd)?;
+    let mut entries = Vec::with_capacity(len.min(MAX_PREALLOC) as usize);
+    for _ in 0..len {
+        let key = fk(rd)?;
+        let val = fv(rd)?;
+        entries.push((key, val));
+    }
+    Ok(entries)
+}
+
+/// Reads an array header and returns an iterator calling `f` once per element.
+///
+/// This is the lazy counterpart of [`read_array_with`], for when the elements shouldn't all be
+/// collected, or when [`ArrayIter::remaining`] is needed along the way. The iterator ends after
+/// the first error returned by `f`, leaving the reader positioned inside the array.
+///
+/// # Examples
+///
+/// ```
+/// use messpack_serde::decode::read_array_iter;
+///
+/// let buf = [0x93, 0x01, 0x02, 0x03];
+/// let mut rd = &buf[..];
+/// let mut iter = read_array_iter(&mut rd, rmp::decode::read_int::<u8, _>).unwrap();
+///
+/// assert_eq!(3, iter.remaining());
+/// assert_eq!(1, iter.next().unwrap().unwrap());
+/// assert_eq!(2, iter.remaining());
+/// ```
+pub fn read_array_iter<R, T, E, F>(rd: &mut R, f: F) -> Result<ArrayIter<'_, R, F>, E>
+where
+    R: Read,
+    E: From<ValueReadError>,
+    F: FnMut(&mut R) -> Result<T, E>,
+{
+    let left = decode::read_array_len(rd)?;
+    Ok(ArrayIter { rd, left, f })
+}
+
+/// Iterator over the elements of an array, see [`read_array_iter`].
+#[derive(Debug)]
+pub struct ArrayIter<'a, R, F> {
+    rd: &'a mut R,
+    left: u32,
+    f: F,
+}
+
+impl<R, F> ArrayIter<'_, R, F> {
+    /// Returns the number of elements that haven't been read yet.
+    #[inline]
+    pub fn remaining(&self) -> usize {
+        self.left as usize
+    }
+}
+
+impl<R, T, E, F> Iterator for ArrayIter<'_, R, F>
+where
+    R: Read,
+    F: FnMut(&mut R) -> Result<T, E>,
+{
+    type Item = Result<T, E>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.left == 0 {
+            return None;
+        }
+        self.left -= 1;
+        let res = (self.f)(self.rd);
+        if res.is_err() {
+            self.left = 0;
+        }
+        Some(res)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.remaining()))
+    }
+}
+
+/// Reads a map header and returns an iterator calling `fk` and `fv` once per entry.
+///
+/// This is the lazy counterpart of [`read_map_with`]. The iterator ends after the first error
+/// returned by either reader, leaving the reader positioned inside the map.
+///
+/// # Examples
+///
+/// ```
+/// use messpack_serde::decode::{read_map_iter, Error};
+///
+/// // {1: true, 2: false}
+/// let buf = [0x82, 0x01, 0xc3, 0x02, 0xc2];
+/// let mut rd = &buf[..];
+/// let mut iter = read_map_iter(
+///     &mut rd,
+///     |rd| Ok::<u8, Error>(rmp::decode::read_int(rd)?),
+///     |rd| Ok(rmp::decode::read_bool(rd)?),
+/// )
+/// .unwrap();
+///
+/// assert_eq!(2, iter.remaining());
+/// assert_eq!((1, true), iter.next().unwrap().unwrap());
+/// assert_eq!(1, iter.remaining());
+/// ```
+pub fn read_map_iter<R, K, V, E, FK, FV>(
+    rd: &mut R,
+    fk: FK,
+    fv: FV,
+) -> Result<MapIter<'_, R, FK, FV>, E>
+where
+    R: Read,
+    E: From<ValueReadError>,
+    FK: FnMut(&mut R) -> Result<K, E>,
+    FV: FnMut(&mut R) -> Result<V, E>,
+{
+    let left = decode::read_map_len(rd)?;
+    Ok(MapIter { rd, left, fk, fv })
+}
+
+/// Iterator over the entries of a map, see [`read_map_iter`].
+#[derive(Debug)]
+pub struct MapIter<'a, R, FK, FV> {
+    rd: &'a mut R,
+    left: u32,
+    fk: FK,
+    fv: FV,
+}
+
+impl<R, FK, FV> MapIter<'_, R, FK, FV> {
+    /// Returns the number of entries that haven't been read yet.
+    #[inline]
+    pub fn remaining(&self) -> usize {
+        self.left as usize
+    }
+}
+
+impl<R, K, V, E, FK, FV> Iterator for MapIter<'_, R, FK, FV>
+where
+    R: Read,
+    FK: FnMut(&mut R) -> Result<K, E>,
+    FV: FnMut(&mut R) -> Result<V, E>,
+{
+    type Item = Result<(K, V), E>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.left == 0 {
+            return None;
+        }
+        self.left -= 1;
+        let res = (self.fk)(self.rd).and_then(|key| Ok((key, (self.fv)(self.rd)?)));
+        if res.is_err() {
+            self.left = 0;
+        }
+        Some(res)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.remaining()))
+    }
+}
+
+/// Reads a value with `f`, or returns `T::default()` if the value is `nil`.
+///
+/// This is for lenient decoding of fields that are sometimes sent as `nil` instead of being left
+/// out. The reader must be buffered so that the marker can be looked at before `f` reads it;
+/// slices and `std::io::BufReader` both are.
+///
+/// # Errors
+///
+/// Returns any error returned by `f`, or `ValueReadError::InvalidMarkerRead` at the end of the
+/// input.
+///
+/// # Examples
+///
+/// ```
+/// use messpack_serde::decode::read_with_default;
+///
+/// let buf = [0xc0, 0x05];
+/// let mut rd = &buf[..];
+///
+/// assert_eq!(0u32, read_with_default(&mut rd, rmp::decode::read_int).unwrap());
+/// assert_eq!(5u32, read_with_default(&mut rd, rmp::decode::read_int).unwrap());
+/// ```
+pub fn read_with_default<T, R, E, F>(rd: &mut R, f: F) -> Result<T, E>
+where
+    T: Default,
+    R: BufRead,
+    E: From<ValueReadError>,
+    F: FnOnce(&mut R) -> Result<T, E>,
+{
+    let buf = rd.fill_buf().map_err(ValueReadError::InvalidMarkerRead)?;
+    match buf.first().copied().map(Marker::from_u8) {
+        Some(Marker::Null) => {
+            rd.consume(1);
+            Ok(T::default())
+        }
+        Some(..) => f(rd),
+        None => Err(ValueReadError::InvalidMarkerRead(io::ErrorKind::UnexpectedEof.into()).into()),
+    }
+}
+
+/// Reads an array header, checking that the array has exactly `len` elements.
+///
+/// # Errors
+///
+/// Returns `Error::LengthMismatch` if the array has a different length.
+pub fn read_array_len_exact<R: Read>(rd: &mut R, len: u32) -> Result<(), Error> {
+    let actual = decode::read_array_len(rd)?;
+    if actual != len {
+        return Err(Error::LengthMismatch {
+            expected: len as usize,
+            actual,
+        });
+    }
+    Ok(())
+}
+
+/// Reads a fixed-size array of heterogeneous values into a tuple.
+///
+/// The first argument is the reader, followed by one reader function per element, called in order.
+/// Each function takes the reader and returns a `Result` whose error converts into
+/// [`decode::Error`](crate::decode::Error). The whole expression evaluates to a
+/// `Result<(..), decode::Error>`, failing with `LengthMismatch` if the array doesn't have exactly
+/// one element per reader.
+///
+/// # Examples
+///
+/// ```
+/// use messpack_serde::read_fixed_array;
+/// use rmp::decode::{read_bool, read_int};
+///
+/// // [-5, true]
+/// let buf = [0x92, 0xfb, 0xc3];
+/// let (num, flag): (i32, bool) = read_fixed_array!(&mut &buf[..], read_int, read_bool).unwrap();
+///
+/// assert_eq!((-5, true), (num, flag));
+/// ```
+#[macro_export]
+macro_rules! read_fixed_array {
+    ($rd:expr, $($read:expr),+ $(,)?) => {
+        (|| -> ::core::result::Result<_, $crate::decode::Error> {
+            let rd = $rd;
+            let arity = <[&str]>::len(&[$(stringify!($read)),+]) as u32;
+            $crate::decode::read_array_len_exact(&mut *rd, arity)?;
+            Ok(($(($read)(&mut *rd)?,)+))
+        })()
+    };
+}
+
+/// Reads an array header, optionally accepting a map header in its place.
+///
+/// This is a non-standard recovery mode for malformed encoders that write a sequence as a map of
+/// flattened pairs. With `accept_map` set, a map of `n` entries is reported as `2 * n` elements,
+/// which can then be read in order as key, value, key, value and so on. Without it, this behaves
+/// like `rmp::decode::read_array_len`.
+///
+/// The count is returned as `u64` because twice the size of a `Map32` may not fit into a `u32`.
+///
+/// # Errors
+///
+/// Returns `ValueReadError::TypeMismatch` for any other marker, and for map markers unless
+/// `accept_map` is set.
+///
+/// # Examples
+///
+/// ```
+/// use messpack_serde::decode::read_seq_len_loose;
+///
+/// // {1: 2}
+/// let buf = [0x81, 0x01, 0x02];
+///
+/// assert!(read_seq_len_loose(&mut &buf[..], false).is_err());
+/// assert_eq!(2, read_seq_len_loose(&mut &buf[..], true).unwrap());
+/// ```
+pub fn read_seq_len_loose<R: Read>(rd: &mut R, accept_map: bool) -> Result<u64, ValueReadError> {
+    match decode::read_marker(rd)? {
+        Marker::FixArray(len) => Ok(u64::from(len)),
+        Marker::Array16 => Ok(u64::from(rd.read_data_u16()?)),
+        Marker::Array32 => Ok(u64::from(rd.read_data_u32()?)),
+        marker @ (Marker::FixMap(_) | Marker::Map16 | Marker::Map32) if accept_map => {
+            Ok(u64::from(decode::marker_to_len(rd, marker)?) * 2)
+        }
+        marker => Err(ValueReadError::TypeMismatch(marker)),
+    }
+}
+
+/// Reads a string into the given buffer, returning the part of the buffer that was filled.
+///
+/// Unlike `rmp::decode::read_str`, a buffer that is too small is reported with both the number of
+/// bytes required and the number available. In that case only the string header has been
+/// consumed, so the caller can allocate `required` bytes and read the payload directly.
+///
+/// # Errors
+///
+/// Returns `Error::BufferSizeTooSmall` if the string does not fit into `buf`, and
+/// `Error::Utf8Error` if the payload is not valid UTF-8.
+///
+/// # Examples
+///
+/// ```
+/// let buf = [0xaa, 0x6c, 0x65, 0x20, 0x6d, 0x65, 0x73, 0x73, 0x61, 0x67, 0x65];
+/// let mut out = [0u8; 16];
+///
+/// assert_eq!("le message", messpack_serde::decode::read_str(&mut &buf[..], &mut out).unwrap());
+/// ```
+pub fn read_str<'r, R: Read>(rd: &mut R, buf: &'r mut [u8]) -> Result<&'r str, Error> {
+    Ok(str::from_utf8(read_str_raw(rd, buf)?)?)
+}
+
+/// Reads a string into the given buffer without validating it, returning the raw bytes.
+///
+/// This skips the UTF-8 check done by [`read_str`], which is wasted work when the string is only
+/// forwarded. The caller takes responsibility for the bytes being valid UTF-8, or for treating
+/// them as arbitrary bytes.
+///
+/// # Errors
+///
+/// Returns `Error::BufferSizeTooSmall` if the string does not fit into `buf`, after consuming
+/// only its header.
+///
+/// # Examples
+///
+/// ```
+/// // "caf" followed by a lone continuation byte
+/// let buf = [0xa4, 0x63, 0x61, 0x66, 0xa9];
+/// let mut out = [0u8; 16];
+///
+/// assert_eq!(
+///     [0x63, 0x61, 0x66, 0xa9],
+///     messpack_serde::decode::read_str_raw(&mut &buf[..], &mut out).unwrap()
+/// );
+/// ```
+pub fn read_str_raw<'r, R: Read>(rd: &mut R, buf: &'r mut [u8]) -> Result<&'r [u8], Error> {
+    let len = decode::read_str_len(rd)?;
+    let available = buf.len();
+    let buf = buf
+        .get_mut(..len as usize)
+        .ok_or(Error::BufferSizeTooSmall {
+            required: len,
+            available: Some(available),
+        })?;
+    rd.read_exact(buf).map_err(Error::InvalidDataRead)?;
+    Ok(buf)
+}
+
+/// Reads a string header from the start of `buf` and borrows the raw string bytes that follow.
+///
+/// Unlike the deprecated `rmp::decode::read_str_ref`, a header that claims more bytes than `buf`
+/// holds is an error rather than a panic. As with [`read_str_raw`], the bytes aren't checked to
+/// be UTF-8.
+///
+/// # Errors
+///
+/// Returns `Error::TypeMismatch` if `buf` doesn't start with a string, and
+/// `Error::InvalidDataRead` with `io::ErrorKind::UnexpectedEof` if it's too short, with the
+/// number of missing bytes in the message.
+///
+/// # Examples
+///
+/// ```
+/// let buf = [0xa2, 0x68, 0x69, 0xc0];
+///
+/// assert_eq!(b"hi", messpack_serde::decode::read_str_ref(&buf).unwrap());
+/// ```
+pub fn read_str_ref(buf: &[u8]) -> Result<&[u8], Error> {
+    let mut rd = buf;
+    let len = decode::read_str_len(&mut rd)?;
+    rd.get(..len as usize).ok_or_else(|| {
+        let missing = len as usize - rd.len();
+        Error::InvalidDataRead(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            format!("string of {len} bytes is missing {missing} of them"),
+        ))
+    })
+}
+
+/// Which of the four string encodings a string header used, see [`read_str_len_with_class`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum StrClass {
+    /// `fixstr`, with the length in the marker itself, for up to 31 bytes.
+    Fix,
+    /// `str 8`, with a one-byte length.
+    Str8,
+    /// `str 16`, with a two-byte length.
+    Str16,
+    /// `str 32`, with a four-byte length.
+    Str32,
+}
+
+impl StrClass {
+    /// Returns the smallest encoding that can hold a string of `len` bytes.
+    ///
+    /// A string whose header has a larger class than this is not minimally encoded.
+    #[must_use]
+    pub const fn minimal(len: u32) -> Self {
+        match len {
+            0..=31 => Self::Fix,
+            32..=0xff => Self::Str8,
+            0x100..=0xffff => Self::Str16,
+            _ => Self::Str32,
+        }
+    }
+}
+
+/// Reads a string header, returning the length along with the encoding it used.
+///
+/// The payload is left in the reader. Comparing the class against [`StrClass::minimal`] tells
+/// whether the encoder used the smallest header available.
+///
+/// # Errors
+///
+/// Returns `ValueReadError::TypeMismatch` if the value isn't a string.
+///
+/// # Examples
+///
+/// ```
+/// use messpack_serde::decode::{read_str_len_with_class, StrClass};
+///
+/// // "hi" as str 8
+/// let buf = [0xd9, 0x02, 0x68, 0x69];
+/// let (len, class) = read_str_len_with_class(&mut &buf[..]).unwrap();
+///
+/// assert_eq!((2, StrClass::Str8), (len, class));
+/// assert_ne!(StrClass::minimal(len), class);
+/// ```
+pub fn read_str_len_with_class<R: Read>(rd: &mut R) -> Result<(u32, StrClass), ValueReadError> {
+    match decode::read_marker(rd)? {
+        Marker::FixStr(len) => Ok((len.into(), StrClass::Fix)),
+        Marker::Str8 => Ok((rd.read_data_u8()?.into(), StrClass::Str8)),
+        Marker::Str16 => Ok((rd.read_data_u16()?.into(), StrClass::Str16)),
+        Marker::Str32 => Ok((rd.read_data_u32()?, StrClass::Str32)),
+        marker => Err(ValueReadError::TypeMismatch(marker)),
+    }
+}
+
+/// Reads a string into a newly allocated `String`, refusing lengths above `max_len`.
+///
+/// Unlike [`read_str`], the destination grows to fit, so the limit is what protects against a
+/// header declaring gigabytes on an unbounded reader such as a socket.
+///
+/// # Errors
+///
+/// Returns `Error::LengthLimitExceeded` right after the header if the string is longer than
+/// `max_len`, without reading any of the payload.
+///
+/// # Examples
+///
+/// ```
+/// use messpack_serde::decode::read_str_owned;
+///
+/// let buf = [0xa2, 0x68, 0x69];
+///
+/// assert_eq!("hi", read_str_owned(&mut &buf[..], 16).unwrap());
+/// assert!(read_str_owned(&mut &buf[..], 1).is_err());
+/// ```
+pub fn read_str_owned<R: Read>(rd: &mut R, max_len: u32) -> Result<String, Error> {
+    let len = decode::read_str_len(rd)?;
+    let buf = read_payload(rd, len, max_len)?;
+    String::from_utf8(buf).map_err(|err| Error::Utf8Error(err.utf8_error()))
+}
+
+/// Reads a binary blob into the given buffer, returning the part of it that was filled.
+///
+/// This is the `bin` counterpart of [`read_str_raw`], for readers that can't lend out their
+/// input, such as sockets.
+///
+/// # Errors
+///
+/// Returns `Error::BufferSizeTooSmall` if the blob does not fit into `buf`, after consuming only
+/// its header, and `Error::InvalidDataRead` if the input ends inside the blob.
+///
+/// # Examples
+///
+/// ```
+/// let buf = [0xc4, 0x02, 0xca, 0xfe];
+/// let mut out = [0u8; 16];
+///
+/// assert_eq!([0xca, 0xfe], messpack_serde::decode::read_bin(&mut &buf[..], &mut out).unwrap());
+/// ```
+pub fn read_bin<'r, R: Read>(rd: &mut R, buf: &'r mut [u8]) -> Result<&'r [u8], Error> {
+    let len = decode::read_bin_len(rd)?;
+    let available = buf.len();
+    let buf = buf
+        .get_mut(..len as usize)
+        .ok_or(Error::BufferSizeTooSmall {
+            required: len,
+            available: Some(available),
+        })?;
+    rd.read_exact(buf).map_err(Error::InvalidDataRead)?;
+    Ok(buf)
+}
+
+/// Reads a binary blob into a newly allocated `Vec`, refusing lengths above `max_len`.
+///
+/// # Errors
+///
+/// Returns `Error::LengthLimitExceeded` right after the header if the blob is longer than
+/// `max_len`, without reading any of the payload.
+///
+/// # Examples
+///
+/// ```
+/// use messpack_serde::decode::read_bin_owned;
+///
+/// let buf = [0xc4, 0x02, 0xca, 0xfe];
+///
+/// assert_eq!(vec![0xca, 0xfe], read_bin_owned(&mut &buf[..], 16).unwrap());
+/// ```
+pub fn read_bin_owned<R: Read>(rd: &mut R, max_len: u32) -> Result<Vec<u8>, Error> {
+    let len = decode::read_bin_len(rd)?;
+    read_payload(rd, len, max_len)
+}
+
+/// Reads a binary blob, borrowing it from the input when possible.
+///
+/// Readers that hand out slices of their input, such as `&[u8]`, lend the data without copying
+/// it. Other readers, such as a [`ReadReader`](super::ReadReader) around a socket, copy it into an
+/// owned buffer.
+///
+/// # Examples
+///
+/// ```
+/// use std::borrow::Cow;
+/// use messpack_serde::decode::read_bin_cow;
+///
+/// let buf = [0xc4, 0x02, 0xca, 0xfe];
+///
+/// let data = read_bin_cow(&mut &buf[..]).unwrap();
+/// assert!(matches!(data, Cow::Borrowed([0xca, 0xfe])));
+/// ```
+pub fn read_bin_cow<'de, R: ReadSlice<'de>>(rd: &mut R) -> Result<Cow<'de, [u8]>, ValueReadError> {
+    let len = decode::read_bin_len(rd)?;
+    match rd
+        .read_slice(len as usize)
+        .map_err(ValueReadError::InvalidDataRead)?
+    {
+        Reference::Borrowed(buf) => Ok(Cow::Borrowed(buf)),
+        Reference::Copied(buf) => Ok(Cow::Owned(buf.to_vec())),
+    }
+}
+
+fn read_payload<R: Read>(rd: &mut R, len: u32, max_len: u32) -> Result<Vec<u8>, Error> {
+    if len > max_len {
+        return Err(Error::LengthLimitExceeded { len, max_len });
+    }
+    let mut buf = vec![0; len as usize];
+    rd.read_exact(&mut buf).map_err(Error::InvalidDataRead)?;
+    Ok(buf)
+}
+
+/// Reads a floating point value of either width as an `f64`.
+///
+/// Both `F32` and `F64` markers are accepted. Widening an `f32` into an `f64` is exact, so this
+/// never loses precision, and NaN payloads, infinities and signed zeros are preserved.
+///
+/// # Errors
+///
+/// Returns `ValueReadError::TypeMismatch` for any non-float marker, including integers.
+///
+/// # Examples
+///
+/// ```
+/// use messpack_serde::decode::read_f64_loosely;
+///
+/// assert_eq!(1.5, read_f64_loosely(&mut &[0xca, 0x3f, 0xc0, 0x00, 0x00][..]).unwrap());
+/// assert_eq!(
+///     1.5,
+///     read_f64_loosely(&mut &[0xcb, 0x3f, 0xf8, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00][..]).unwrap()
+/// );
+/// ```
+pub fn read_f64_loosely<R: Read>(rd: &mut R) -> Result<f64, ValueReadError> {
+    match decode::read_marker(rd)? {
+        Marker::F32 => Ok(f64::from(rd.read_data_f32()?)),
+        Marker::F64 => rd.read_data_f64(),
+        marker => Err(ValueReadError::TypeMismatch(marker)),
+    }
+}
+
+/// Reads an integer of any width and signedness as a `u32`.
+///
+/// Encoders write integers in the smallest marker that fits, and may use signed markers for
+/// non-negative values, so this accepts every integer marker as long as the value fits.
+///
+/// # Errors
+///
+/// Returns `ValueReadError::TypeMismatch` for non-integer markers, and for integers that are
+/// negative or above `u32::MAX`, with their data consumed.
+///
+/// # Examples
+///
+/// ```
+/// use messpack_serde::decode::read_u32_loosely;
+///
+/// assert_eq!(5, read_u32_loosely(&mut &[0xd0, 0x05][..]).unwrap());
+/// assert_eq!(300, read_u32_loosely(&mut &[0xcd, 0x01, 0x2c][..]).unwrap());
+/// assert!(read_u32_loosely(&mut &[0xff][..]).is_err());
+/// ```
+pub fn read_u32_loosely<R: Read>(rd: &mut R) -> Result<u32, ValueReadError> {
+    let marker = decode::read_marker(rd)?;
+    let val = read_int_data(rd, marker)?;
+    u32::try_from(val).map_err(|_| ValueReadError::TypeMismatch(marker))
+}
+
+/// Reads an array of integers, appending them to `out` as `u32`s.
+///
+/// This skips the per-element overhead of going through serde, which matters for large numeric
+/// payloads. Elements are read with [`read_u32_loosely`], and `out` reserves space for the
+/// declared length up front, bounded so that a bogus header can't exhaust memory. Clear `out`
+/// first to reuse its allocation across calls.
+///
+/// # Errors
+///
+/// Returns `ValueReadError::TypeMismatch` if the value isn't an array or an element isn't an
+/// integer fitting a `u32`. Elements read before the failing one stay in `out`.
+///
+/// # Examples
+///
+/// ```
+/// use messpack_serde::decode::read_u32_array;
+///
+/// // [1, 300, 70000]
+/// let buf = [0x93, 0x01, 0xcd, 0x01, 0x2c, 0xce, 0x00, 0x01, 0x11, 0x70];
+/// let mut out = Vec::new();
+/// read_u32_array(&mut &buf[..], &mut out).unwrap();
+///
+/// assert_eq!([1, 300, 70000], out[..]);
+/// ```
+pub fn read_u32_array<R: Read>(rd: &mut R, out: &mut Vec<u32>) -> Result<(), ValueReadError> {
+    let len = decode::read_array_len(rd)?;
+    out.reserve(len.min(MAX_PREALLOC) as usize);
+    for _ in 0..len {
+        out.push(read_u32_loosely(rd)?);
+    }
+    Ok(())
+}
+
+/// Reads a decimal stored as an ext of type `ext_type`, returning its scale and mantissa.
+///
+/// The ext data starts with the scale as a big-endian `i32`, and the remaining bytes are the
+/// mantissa, left as is for an arbitrary-precision decimal library to interpret.
+///
+/// # Errors
+///
+/// Returns `Error::TypeMismatch` if the value isn't an ext, `Error::Uncategorized` if it has a
+/// different type id, and `Error::LengthMismatch` if the data is too short to hold the scale.
+/// The ext data is consumed in the last two cases.
+///
+/// # Examples
+///
+/// ```
+/// use messpack_serde::decode::read_decimal_ext;
+///
+/// // 3.14 as scale 2, mantissa 314 (0x013a), in an ext of type 7
+/// let buf = [0xc7, 0x06, 0x07, 0x00, 0x00, 0x00, 0x02, 0x01, 0x3a];
+///
+/// assert_eq!((2, vec![0x01, 0x3a]), read_decimal_ext(&mut &buf[..], 7).unwrap());
+/// ```
+pub fn read_decimal_ext<R: Read>(rd: &mut R, ext_type: i8) -> Result<(i32, Vec<u8>), Error> {
+    let meta = decode::read_ext_meta(rd)?;
+    let mut data = Vec::with_capacity(meta.size.min(MAX_PREALLOC) as usize);
+    rd.take(u64::from(meta.size))
+        .read_to_end(&mut data)
+        .map_err(Error::InvalidDataRead)?;
+    if data.len() != meta.size as usize {
+        return Err(Error::InvalidDataRead(io::ErrorKind::UnexpectedEof.into()));
+    }
+
+    if meta.typeid != ext_type {
+        return Err(Error::Uncategorized(format!(
+            "expected ext type {ext_type}, got {}",
+            meta.typeid
+        )));
+    }
+    if data.len() < 4 {
+        return Err(Error::LengthMismatch {
+            expected: 4,
+            actual: data.len() as u32,
+        });
+    }
+    let mantissa = data.split_off(4);
+    let scale = i32::from_be_bytes([data[0], data[1], data[2], data[3]]);
+    Ok((scale, mantissa))
+}
+
+/// Reads an ext of type `expected_type` whose data is exactly `N` bytes, such as a UUID, into an
+/// array on the stack.
+///
+/// # Errors
+///
+/// Returns `ValueReadError::TypeMismatch` with the ext's marker if the value isn't an ext, or if
+/// its type id or size differ from the expected ones. In the latter case only the header has been
+/// consumed and the data is left in the reader.
+///
+/// # Examples
+///
+/// ```
+/// use messpack_serde::decode::read_ext_array;
+///
+/// // fixext 4 of type 3
+/// let buf = [0xd6, 0x03, 0xde, 0xad, 0xbe, 0xef];
+///
+/// assert_eq!([0xde, 0xad, 0xbe, 0xef], read_ext_array::<_, 4>(&mut &buf[..], 3).unwrap());
+/// assert!(read_ext_array::<_, 4>(&mut &buf[..], 4).is_err());
+/// ```
+pub fn read_ext_array<R: Read, const N: usize>(
+    rd: &mut R,
+    expected_type: i8,
+) -> Result<[u8; N], ValueReadError> {
+    let marker = decode::read_marker(rd)?;
+    let size = match marker {
+        Marker::FixExt1 => 1,
+        Marker::FixExt2 => 2,
+        Marker::FixExt4 => 4,
+        Marker::FixExt8 => 8,
+        Marker::FixExt16 => 16,
+        Marker::Ext8 => u32::from(rd.read_data_u8()?),
+        Marker::Ext16 => u32::from(rd.read_data_u16()?),
+        Marker::Ext32 => rd.read_data_u32()?,
+        marker => return Err(ValueReadError::TypeMismatch(marker)),
+    };
+    let typeid = rd.read_data_i8()?;
+    if typeid != expected_type || size as usize != N {
+        return Err(ValueReadError::TypeMismatch(marker));
+    }
+
+    let mut buf = [0; N];
+    rd.read_exact(&mut buf)
+        .map_err(ValueReadError::InvalidDataRead)?;
+    Ok(buf)
+}
+
+/// Reads the `len` data bytes of an ext into the start of `buf`, after its header has been read
+/// with `rmp::decode::read_ext_meta`.
+///
+/// This works for ext of any size, not just the fixext ones, and `buf` can be reused across
+/// calls.
+///
+/// # Errors
+///
+/// Returns `Error::BufferSizeTooSmall` without reading anything if `buf` is shorter than `len`,
+/// and `Error::InvalidDataRead` if the reader ends before `len` bytes.
+///
+/// # Examples
+///
+/// ```
+/// use messpack_serde::decode::read_ext_data;
+///
+/// // ext 8 of type 7 with 3 bytes of data
+/// let buf = [0xc7, 0x03, 0x07, 0x61, 0x62, 0x63];
+/// let mut rd = &buf[..];
+/// let mut out = [0u8; 8];
+///
+/// let meta = rmp::decode::read_ext_meta(&mut rd).unwrap();
+/// read_ext_data(&mut rd, meta.size, &mut out).unwrap();
+/// assert_eq!(b"abc", &out[..3]);
+/// ```
+pub fn read_ext_data<R: Read>(rd: &mut R, len: u32, buf: &mut [u8]) -> Result<(), Error> {
+    let available = buf.len();
+    let buf = buf
+        .get_mut(..len as usize)
+        .ok_or(Error::BufferSizeTooSmall {
+            required: len,
+            available: Some(available),
+        })?;
+    rd.read_exact(buf).map_err(Error::InvalidDataRead)
+}
+
+/// Reads an ext into its type id and a newly allocated `Vec` of its data, refusing data longer
+/// than `max_len`.
+///
+/// # Errors
+///
+/// Returns `Error::LengthLimitExceeded` right after the header if the data is longer than
+/// `max_len`, without reading any of it.
+///
+/// # Examples
+///
+/// ```
+/// use messpack_serde::decode::read_ext_owned;
+///
+/// // ext 8 of type 7 with 3 bytes of data
+/// let buf = [0xc7, 0x03, 0x07, 0x61, 0x62, 0x63];
+///
+/// assert_eq!((7, b"abc".to_vec()), read_ext_owned(&mut &buf[..], 16).unwrap());
+/// assert!(read_ext_owned(&mut &buf[..], 2).is_err());
+/// ```
+pub fn read_ext_owned<R: Read>(rd: &mut R, max_len: u32) -> Result<(i8, Vec<u8>), Error> {
+    let meta = decode::read_ext_meta(rd)?;
+    Ok((meta.typeid, read_payload(rd, meta.size, max_len)?))
+}
+
+/// Skips over a single value of any type, along with everything nested in it, without decoding
+/// it.
+///
+/// Useful for stepping over the values of unwanted map keys. Arrays and maps are handled by
+/// counting the values still to skip rather than by recursing, so deeply nested input can't
+/// exhaust the stack. String, binary and ext payloads are discarded as they are read, never
+/// buffered.
+///
+/// # Errors
+///
+/// Returns `ValueReadError::InvalidMarkerRead` if the input is empty,
+/// `ValueReadError::InvalidDataRead` with `io::ErrorKind::UnexpectedEof` if it ends inside the
+/// value, and `ValueReadError::TypeMismatch` on the reserved marker `0xc1`.
+///
+/// # Examples
+///
+/// ```
+/// use messpack_serde::decode::skip_value;
+///
+/// // {"a": [1, 2]}, then true
+/// let buf = [0x81, 0xa1, 0x61, 0x92, 0x01, 0x02, 0xc3];
+/// let mut rd = &buf[..];
+///
+/// skip_value(&mut rd).unwrap();
+/// assert_eq!([0xc3], rd);
+/// ```
+pub fn skip_value<R: Read>(rd: &mut R) -> Result<(), ValueReadError> {
+    let marker = decode::read_marker(rd)?;
+    skip_rest(rd, marker)
+}
+
+/// Like [`skip_value`], for a value whose marker was already read.
+///
+/// Nested containers are tracked by a count of the values left rather than by recursion, so
+/// arbitrarily deep input can't overflow the call stack.
+pub(crate) fn skip_rest<R: Read>(rd: &mut R, mut marker: Marker) -> Result<(), ValueReadError> {
+    let mut remaining = 0u64;
+    loop {
+        remaining += skip_marker_data(rd, marker)?;
+        if remaining == 0 {
+            return Ok(());
+        }
+        remaining -= 1;
+        marker = decode::read_marker(rd).map_err(|err| ValueReadError::InvalidDataRead(err.0))?;
+    }
+}
+
+/// Splits an encoded array into the encoded bytes of each of its elements, borrowed from `buf`.
+///
+/// The elements are skipped with [`skip_value`] rather than decoded, so each slice can be
+/// forwarded or decoded on its own later. Anything after the array is ignored.
+///
+/// # Errors
+///
+/// Returns `ValueReadError::TypeMismatch` if `buf` doesn't start with an array, and the errors
+/// of [`skip_value`] if an element is malformed or cut short.
+///
+/// # Examples
+///
+/// ```
+/// use messpack_serde::decode::read_array_raw_slices;
+///
+/// // [1, "ab"]
+/// let buf = [0x92, 0x01, 0xa2, 0x61, 0x62];
+///
+/// assert_eq!(vec![&buf[1..2], &buf[2..]], read_array_raw_slices(&buf).unwrap());
+/// ```
+pub fn read_array_raw_slices(buf: &[u8]) -> Result<Vec<&[u8]>, ValueReadError> {
+    let mut rd = buf;
+    let len = decode::read_array_len(&mut rd)?;
+    let mut slices = Vec::with_capacity(len.min(MAX_PREALLOC) as usize);
+    for _ in 0..len {
+        let start = rd;
+        skip_value(&mut rd)?;
+        slices.push(&start[..start.len() - rd.len()]);
+    }
+    Ok(slices)
+}
+
+/// Discards the data following `marker`, returning the number of nested values that follow it.
+fn skip_marker_data<R: Read>(rd: &mut R, marker: Marker) -> Result<u64, ValueReadError> {
+    let len = match marker {
+        Marker::Str8 | Marker::Bin8 => rd.read_data_u8()?.into(),
+        Marker::Str16 | Marker::Bin16 => rd.read_data_u16()?.into(),
+        Marker::Str32 | Marker::Bin32 => rd.read_data_u32()?.into(),
+        // One more for the type id.
+        Marker::Ext8 => u64::from(rd.read_data_u8()?) + 1,
+        Marker::Ext16 => u64::from(rd.read_data_u16()?) + 1,
+        Marker::Ext32 => u64::from(rd.read_data_u32()?) + 1,
+        Marker::FixArray(len) => return Ok(len.into()),
+        Marker::Array16 => return Ok(rd.read_data_u16()?.into()),
+        Marker::Array32 => return Ok(rd.read_data_u32()?.into()),
+        Marker::FixMap(len) => return Ok(u64::from(len) * 2),
+        Marker::Map16 => return Ok(u64::from(rd.read_data_u16()?) * 2),
+        Marker::Map32 => return Ok(u64::from(rd.read_data_u32()?) * 2),
+        marker => match marker.data_len() {
+            Some(len) => len as u64,
+            None => return Err(ValueReadError::TypeMismatch(marker)),
+        },
+    };
+
+    let skipped =
+        io::copy(&mut rd.take(len), &mut io::sink()).map_err(ValueReadError::InvalidDataRead)?;
+    if skipped < len {
+        return Err(ValueReadError::InvalidDataRead(
+            io::ErrorKind::UnexpectedEof.into(),
+        ));
+    }
+    Ok(0)
+}
+
+/// Encoding an integer was read from, see [`read_integer_typed`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum IntWidth {
+    /// Positive fixnum, the value is stored in the marker.
+    FixPos,
+    /// Negative fixnum, the value is stored in the marker.
+    FixNeg,
+    /// `uint 8`
+    U8,
+    /// `uint 16`
+    U16,
+    /// `uint 32`
+    U32,
+    /// `uint 64`
+    U64,
+    /// `int 8`
+    I8,
+    /// `int 16`
+    I16,
+    /// `int 32`
+    I32,
+    /// `int 64`
+    I64,
+}
+
+/// Reads an integer of any encoding, for when its width and sign aren't known up front.
+///
+/// Every integer marker is accepted, from fixnums to `uint 64` and `int 64`. Non-negative values
+/// come back unsigned, whatever their encoding, so `Integer::as_u64` succeeds for them and
+/// `Integer::as_i64` for everything up to `i64::MAX`. Use [`read_integer_typed`] to also learn
+/// the encoding.
+///
+/// # Errors
+///
+/// Returns `ValueReadError::TypeMismatch` if the value isn't an integer.
+///
+/// # Examples
+///
+/// ```
+/// use messpack_serde::decode::read_integer;
+///
+/// // u64::MAX as a uint 64, then -2 as an int 32
+/// let buf = [0xcf, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xd2, 0xff, 0xff, 0xff, 0xfe];
+/// let mut rd = &buf[..];
+///
+/// let val = read_integer(&mut rd).unwrap();
+/// assert_eq!((Some(u64::MAX), None), (val.as_u64(), val.as_i64()));
+/// let val = read_integer(&mut rd).unwrap();
+/// assert_eq!((None, Some(-2)), (val.as_u64(), val.as_i64()));
+/// ```
+pub fn read_integer<R: Read>(rd: &mut R) -> Result<Integer, ValueReadError> {
+    read_integer_typed(rd).map(|(val, _)| val)
+}
+
+/// Reads an integer of any encoding, returning it together with the encoding it came in.
+///
+/// # Errors
+///
+/// Returns `ValueReadError::TypeMismatch` if the value isn't an integer.
+///
+/// # Examples
+///
+/// ```
+/// use messpack_serde::decode::value::Integer;
+/// use messpack_serde::decode::{read_integer_typed, IntWidth};
+///
+/// // 5 as a uint 16, which a canonical encoder would have written as a fixnum
+/// let buf = [0xcd, 0x00, 0x05];
+///
+/// assert_eq!((Integer::from(5), IntWidth::U16), read_integer_typed(&mut &buf[..]).unwrap());
+/// ```
+pub fn read_integer_typed<R: Read>(rd: &mut R) -> Result<(Integer, IntWidth), ValueReadError> {
+    let marker = decode::read_marker(rd)?;
+    let width = match marker {
+        Marker::FixPos(_) => IntWidth::FixPos,
+        Marker::FixNeg(_) => IntWidth::FixNeg,
+        Marker::U8 => IntWidth::U8,
+        Marker::U16 => IntWidth::U16,
+        Marker::U32 => IntWidth::U32,
+        Marker::U64 => IntWidth::U64,
+        Marker::I8 => IntWidth::I8,
+        Marker::I16 => IntWidth::I16,
+        Marker::I32 => IntWidth::I32,
+        Marker::I64 => IntWidth::I64,
+        marker => return Err(ValueReadError::TypeMismatch(marker)),
+    };
+    let val = read_int_data(rd, marker)?;
+    let val = match u64::try_from(val) {
+        Ok(val) => Integer::from(val),
+        // Anything negative was read from at most 64 bits.
+        Err(_) => Integer::from(val as i64),
+    };
+    Ok((val, width))
+}
+
+/// Extension methods for [`rmp::decode::ExtMeta`], as returned by `rmp::decode::read_ext_meta`.
+pub trait ExtMetaExt {
+    /// Creates the metadata of an ext value with the given type id and data size.
+    fn new(typeid: i8, size: u32) -> Self;
+
+    /// Returns `true` for the timestamp extension type, type id `-1`.
+    fn is_timestamp(&self) -> bool;
+
+    /// Returns the number of data bytes following the type id.
+    fn data_len(&self) -> u32;
+}
+
+impl ExtMetaExt for ExtMeta {
+    #[inline]
+    fn new(typeid: i8, size: u32) -> Self {
+        Self { typeid, size }
+    }
+
+    #[inline]
+    fn is_timestamp(&self) -> bool {
+        self.typeid == -1
+    }
+
+    #[inline]
+    fn data_len(&self) -> u32 {
+        self.size
+    }
+}
+
+/// Byte order of a number stored in ext data, see [`ExtReader`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Endian {
+    /// Most significant byte first, as MessagePack itself stores numbers.
+    Big,
+    /// Least significant byte first.
+    Little,
+}
+
+/// Reads fixed-size fields out of the data of an ext, after its header was read with
+/// `rmp::decode::read_ext_meta`.
+///
+/// Ext data is opaque to MessagePack, so its byte order is whatever the application chose, and is
+/// passed to each read as an [`Endian`]. Every read is checked against the size declared in the
+/// header and never reads past the ext.
+///
+/// # Examples
+///
+/// ```
+/// use messpack_serde::decode::{Endian, ExtReader};
+///
+/// // fixext 4 of type 1 holding 1000 as a little-endian u32
+/// let buf = [0xd6, 0x01, 0xe8, 0x03, 0x00, 0x00];
+/// let mut rd = &buf[..];
+/// let meta = rmp::decode::read_ext_meta(&mut rd).unwrap();
+/// let mut ext = ExtReader::new(&mut rd, &meta);
+///
+/// assert_eq!(1000, ext.read_u32(Endian::Little).unwrap());
+/// assert_eq!(0, ext.remaining());
+/// ```
+#[derive(Debug)]
+pub struct ExtReader<'a, R> {
+    rd: &'a mut R,
+    left: u32,
+}
+
+macro_rules! ext_reader_methods {
+    ($($read:ident $ty:ty;)*) => {
+        $(
+            #[doc = concat!("Reads an `", stringify!($ty), "` stored in the given byte order.")]
+            #[inline]
+            pub fn $read(&mut self, endian: Endian) -> Result<$ty, Error> {
+                let buf = self.take()?;
+                Ok(match endian {
+                    Endian::Big => <$ty>::from_be_bytes(buf),
+                    Endian::Little => <$ty>::from_le_bytes(buf),
+                })
+            }
+        )*
+    };
+}
+
+impl<'a, R: Read> ExtReader<'a, R> {
+    /// Creates a reader over the `meta.size` data bytes that follow the header in `rd`.
+    #[inline]
+    pub fn new(rd: &'a mut R, meta: &ExtMeta) -> Self {
+        Self {
+            rd,
+            left: meta.size,
+        }
+    }
+
+    /// Returns the number of data bytes that haven't been read yet.
+    #[inline]
+    pub fn remaining(&self) -> u32 {
+        self.left
+    }
+
+    /// Reads a `u8`.
+    #[inline]
+    pub fn read_u8(&mut self) -> Result<u8, Error> {
+        Ok(u8::from_be_bytes(self.take()?))
+    }
+
+    /// Reads an `i8`.
+    #[inline]
+    pub fn read_i8(&mut self) -> Result<i8, Error> {
+        Ok(i8::from_be_bytes(self.take()?))
+    }
+
+    ext_reader_methods! {
+        read_u16 u16;
+        read_u32 u32;
+        read_u64 u64;
+        read_i16 i16;
+        read_i32 i32;
+        read_i64 i64;
+        read_f32 f32;
+        read_f64 f64;
+    }
+
+    /// Reads the next `N` bytes of the ext data.
+    ///
+    /// Returns `Error::LengthMismatch` with the number of bytes needed and left if the ext is too
+    /// short, without consuming anything.
+    fn take<const N: usize>(&mut self) -> Result<[u8; N], Error> {
+        if (self.left as usize) < N {
+            return Err(Error::LengthMismatch {
+                expected: N,
+                actual: self.left,
+            });
+        }
+        let mut buf = [0; N];
+        self.rd
+            .read_exact(&mut buf)
+            .map_err(Error::InvalidDataRead)?;
+        self.left -= N as u32;
+        Ok(buf)
+    }
+}
+
+/// Decoding into an existing value, overwriting it in place.
+///
+/// Lets hot paths reuse one instance across messages instead of producing a fresh value each
+/// time. Implemented for `bool`, the integer and float types, and tuples of up to eight
+/// `DecodeInto` elements, which are encoded as arrays. Your own structs can follow the tuple
+/// impls: read the array header, then decode each field into place.
+///
+/// # Examples
+///
+/// ```
+/// use messpack_serde::decode::DecodeInto;
+///
+/// let mut point = (0i32, 0i32);
+/// for buf in [[0x92, 0x01, 0x02], [0x92, 0x03, 0xff]] {
+///     DecodeInto::decode_into(&mut &buf[..], &mut point).unwrap();
+/// }
+///
+/// assert_eq!((3, -1), point);
+/// ```
+pub trait DecodeInto {
+    /// Reads a value from `rd` into `out`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ValueReadError::TypeMismatch` with the offending marker if the value has the wrong
+    /// type, if an integer doesn't fit `Self`, or if an array has a different length than the
+    /// tuple. Scalars leave `out` untouched on error, while tuples may have been partly updated.
+    fn decode_into(rd: &mut impl Read, out: &mut Self) -> Result<(), ValueReadError>;
+}
+
+impl DecodeInto for bool {
+    #[inline]
+    fn decode_into(rd: &mut impl Read, out: &mut Self) -> Result<(), ValueReadError> {
+        *out = decode::read_bool(rd)?;
+        Ok(())
+    }
+}
+
+impl DecodeInto for f32 {
+    #[inline]
+    fn decode_into(rd: &mut impl Read, out: &mut Self) -> Result<(), ValueReadError> {
+        *out = decode::read_f32(rd)?;
+        Ok(())
+    }
+}
+
+impl DecodeInto for f64 {
+    #[inline]
+    fn decode_into(rd: &mut impl Read, out: &mut Self) -> Result<(), ValueReadError> {
+        *out = read_f64_loosely(rd)?;
+        Ok(())
+    }
+}
+
+macro_rules! impl_decode_into_int {
+    ($($ty:ty)*) => {
+        $(
+            impl DecodeInto for $ty {
+                #[inline]
+                fn decode_into(rd: &mut impl Read, out: &mut Self) -> Result<(), ValueReadError> {
+                    let marker = decode::read_marker(rd)?;
+                    let val = read_int_data(rd, marker)?;
+                    *out = <$ty>::try_from(val).map_err(|_| ValueReadError::TypeMismatch(marker))?;
+                    Ok(())
+                }
+            }
+        )*
+    };
+}
+
+impl_decode_into_int!(u8 u16 u32 u64 usize i8 i16 i32 i64 isize);
+
+/// Reads the data of an integer of any width whose marker was already read.
+fn read_int_data<R: Read>(rd: &mut R, marker: Marker) -> Result<i128, ValueReadError> {
+    Ok(match marker {
+        Marker::FixPos(val) => val.into(),
+        Marker::FixNeg(val) => val.into(),
+        Marker::U8 => rd.read_data_u8()?.into(),
+        Marker::U16 => rd.read_data_u16()?.into(),
+        Marker::U32 => rd.read_data_u32()?.into(),
+        Marker::U64 => rd.read_data_u64()?.into(),
+        Marker::I8 => rd.read_data_i8()?.into(),
+        Marker::I16 => rd.read_data_i16()?.into(),
+        Marker::I32 => rd.read_data_i32()?.into(),
+        Marker::I64 => rd.read_data_i64()?.into(),
+        marker => return Err(ValueReadError::TypeMismatch(marker)),
+    })
+}
+
+macro_rules! impl_decode_into_tuple {
+    ($len:expr => $($idx:tt $name:ident)+) => {
+        impl<$($name: DecodeInto),+> DecodeInto for ($($name,)+) {
+            fn decode_into(rd: &mut impl Read, out: &mut Self) -> Result<(), ValueReadError> {
+                let marker = decode::read_marker(rd)?;
+                let len = match marker {
+                    Marker::FixArray(len) => len.into(),
+                    Marker::Array16 => rd.read_data_u16()?.into(),
+                    Marker::Array32 => rd.read_data_u32()?,
+                    marker => return Err(ValueReadError::TypeMismatch(marker)),
+                };
+                if len != $len {
+                    return Err(ValueReadError::TypeMismatch(marker));
+                }
+                $($name::decode_into(rd, &mut out.$idx)?;)+
+                Ok(())
+            }
+        }
+    };
+}
+
+impl_decode_into_tuple!(1 => 0 A);
+impl_decode_into_tuple!(2 => 0 A 1 B);
+impl_decode_into_tuple!(3 => 0 A 1 B 2 C);
+impl_decode_into_tuple!(4 => 0 A 1 B 2 C 3 D);
+impl_decode_into_tuple!(5 => 0 A 1 B 2 C 3 D 4 E);
+impl_decode_into_tuple!(6 => 0 A 1 B 2 C 3 D 4 E 5 F);
+impl_decode_into_tuple!(7 => 0 A 1 B 2 C 3 D 4 E 5 F 6 G);
+impl_decode_into_tuple!(8 => 0 A 1 B 2 C 3 D 4 E 5 F 6 G 7 H);
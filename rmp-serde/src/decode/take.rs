@@ -0,0 +1,98 @@
+//! Decoding straight from byte slices, for hand-written parsers that don't need `io::Read`.
+//!
+//! Each `take_*` function decodes one value from the front of the buffer and returns it together
+//! with the rest of the buffer, so calls can be chained. Strings and binaries are borrowed from the
+//! buffer.
+
+use std::io::ErrorKind;
+use std::str;
+
+use rmp::decode::{self, DecodeStringError, NumValueReadError, ValueReadError};
+
+/// Takes a `nil` from the front of the buffer.
+pub fn take_nil(buf: &[u8]) -> Result<&[u8], ValueReadError> {
+    let mut rd = buf;
+    decode::read_nil(&mut rd)?;
+    Ok(rd)
+}
+
+/// Takes a boolean from the front of the buffer.
+pub fn take_bool(buf: &[u8]) -> Result<(bool, &[u8]), ValueReadError> {
+    let mut rd = buf;
+    let val = decode::read_bool(&mut rd)?;
+    Ok((val, rd))
+}
+
+/// Takes an integer of any encoding that fits into a `u64` from the front of the buffer.
+pub fn take_u64(buf: &[u8]) -> Result<(u64, &[u8]), NumValueReadError> {
+    let mut rd = buf;
+    let val = decode::read_int(&mut rd)?;
+    Ok((val, rd))
+}
+
+/// Takes an integer of any encoding that fits into an `i64` from the front of the buffer.
+pub fn take_i64(buf: &[u8]) -> Result<(i64, &[u8]), NumValueReadError> {
+    let mut rd = buf;
+    let val = decode::read_int(&mut rd)?;
+    Ok((val, rd))
+}
+
+/// Takes an `F64` encoded float from the front of the buffer.
+pub fn take_f64(buf: &[u8]) -> Result<(f64, &[u8]), ValueReadError> {
+    let mut rd = buf;
+    let val = decode::read_f64(&mut rd)?;
+    Ok((val, rd))
+}
+
+/// Takes a string from the front of the buffer, borrowing it from the buffer.
+///
+/// # Examples
+///
+/// ```
+/// use messpack_serde::decode::{take_array_len, take_str, take_u64};
+///
+/// // ["id", 42]
+/// let buf = [0x92, 0xa2, 0x69, 0x64, 0x2a];
+///
+/// let (len, rest) = take_array_len(&buf).unwrap();
+/// let (key, rest) = take_str(rest).unwrap();
+/// let (val, rest) = take_u64(rest).unwrap();
+///
+/// assert_eq!((2, "id", 42), (len, key, val));
+/// assert!(rest.is_empty());
+/// ```
+pub fn take_str(buf: &[u8]) -> Result<(&str, &[u8]), DecodeStringError<'_>> {
+    let mut rd = buf;
+    let len = decode::read_str_len(&mut rd)?;
+    let (data, rest) = split(rd, len).ok_or(DecodeStringError::BufferSizeTooSmall(len))?;
+    match str::from_utf8(data) {
+        Ok(val) => Ok((val, rest)),
+        Err(err) => Err(DecodeStringError::InvalidUtf8(data, err)),
+    }
+}
+
+/// Takes a binary from the front of the buffer, borrowing it from the buffer.
+pub fn take_bin(buf: &[u8]) -> Result<(&[u8], &[u8]), ValueReadError> {
+    let mut rd = buf;
+    let len = decode::read_bin_len(&mut rd)?;
+    split(rd, len).ok_or_else(|| ValueReadError::InvalidDataRead(ErrorKind::UnexpectedEof.into()))
+}
+
+/// Takes an array header from the front of the buffer, returning the number of elements.
+pub fn take_array_len(buf: &[u8]) -> Result<(u32, &[u8]), ValueReadError> {
+    let mut rd = buf;
+    let len = decode::read_array_len(&mut rd)?;
+    Ok((len, rd))
+}
+
+/// Takes a map header from the front of the buffer, returning the number of entries.
+pub fn take_map_len(buf: &[u8]) -> Result<(u32, &[u8]), ValueReadError> {
+    let mut rd = buf;
+    let len = decode::read_map_len(&mut rd)?;
+    Ok((len, rd))
+}
+
+fn split(buf: &[u8], len: u32) -> Option<(&[u8], &[u8])> {
+    let len = len as usize;
+    (buf.len() >= len).then(|| buf.split_at(len))
+}
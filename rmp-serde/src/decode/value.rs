@@ -0,0 +1,814 @@
+//! Decoding of schemaless MessagePack values into a [`Value`] tree.
+
+use std::collections::HashSet;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::ops::Range;
+use std::rc::Rc;
+
+use rmp::decode::{self, RmpRead};
+use rmp::Marker;
+
+pub use rmpv::{Integer, Value};
+
+use super::Error;
+use crate::crc32::Crc32;
+
+/// Reads a single value of any type.
+///
+/// Integers of every width come out as an [`Integer`], and maps keep their entries in order as
+/// `(key, value)` pairs, so keys of any type are preserved. Arrays and maps nested deeper than
+/// 1024 levels fail with `Error::DepthLimitExceeded` instead of exhausting the stack.
+///
+/// # Errors
+///
+/// Returns `Error::InvalidMarkerRead` if the input is empty, and `Error::InvalidDataRead` if it
+/// ends inside the value.
+///
+/// # Examples
+///
+/// ```
+/// use messpack_serde::decode::value::{read_value, Value};
+///
+/// // {1: "a"}
+/// let buf = [0x81, 0x01, 0xa1, 0x61];
+///
+/// assert_eq!(
+///     Value::Map(vec![(Value::from(1), Value::from("a"))]),
+///     read_value(&mut &buf[..]).unwrap()
+/// );
+/// ```
+pub fn read_value<R: Read>(rd: &mut R) -> Result<Value, Error> {
+    let marker = decode::read_marker(rd)?;
+    dispatch(marker, rd)
+}
+
+/// Reads the rest of a value whose marker has already been read, for callers that peeked at the
+/// marker to decide how to decode it.
+///
+/// Arrays and maps are read in full, with the same depth limit as [`read_value`].
+///
+/// # Errors
+///
+/// Returns `Error::InvalidDataRead` if the input ends inside the value. Running out of input is
+/// never reported as `Error::InvalidMarkerRead`, since the marker is already known.
+///
+/// # Examples
+///
+/// ```
+/// use messpack_serde::decode::value::{dispatch, Value};
+///
+/// // [true, nil]
+/// let buf = [0x92, 0xc3, 0xc0];
+/// let mut rd = &buf[..];
+/// let marker = rmp::decode::read_marker(&mut rd).unwrap();
+///
+/// assert_eq!(
+///     Value::Array(vec![Value::from(true), Value::Nil]),
+///     dispatch(marker, &mut rd).unwrap()
+/// );
+/// ```
+pub fn dispatch<R: Read>(marker: Marker, rd: &mut R) -> Result<Value, Error> {
+    let marker = [marker.to_u8()];
+    rmpv::decode::read_value(&mut (&marker[..]).chain(rd)).map_err(|err| match err.into() {
+        Error::InvalidMarkerRead(err) => Error::InvalidDataRead(err),
+        err => err,
+    })
+}
+
+/// Reads a `[tag, payload]` record, as used by logs mixing records of different types.
+///
+/// The tag must be an integer, and tells the caller how to interpret the payload, which can be
+/// any value.
+///
+/// # Errors
+///
+/// Returns `Error::TypeMismatch` if the record isn't an array or the tag isn't an integer,
+/// `Error::LengthMismatch` if the array doesn't have two elements, and `Error::OutOfRange` if
+/// the tag doesn't fit in an `i64`.
+///
+/// # Examples
+///
+/// ```
+/// use messpack_serde::decode::value::{read_tagged, Value};
+///
+/// // [3, "up"]
+/// let buf = [0x92, 0x03, 0xa2, 0x75, 0x70];
+///
+/// assert_eq!((3, Value::from("up")), read_tagged(&mut &buf[..]).unwrap());
+/// ```
+pub fn read_tagged<R: Read>(rd: &mut R) -> Result<(i64, Value), Error> {
+    super::read_array_len_exact(rd, 2)?;
+    let tag = decode::read_int(rd)?;
+    Ok((tag, read_value(rd)?))
+}
+
+/// Reads a single value, returning `None` if it is `nil`.
+///
+/// Any other value is decoded in full, which makes this convenient for arrays mixing `nil` with
+/// values of different types.
+///
+/// # Examples
+///
+/// ```
+/// use messpack_serde::decode::read_array_with;
+/// use messpack_serde::decode::value::{read_optional_value, Value};
+///
+/// // [nil, 5, "x"]
+/// let buf = [0x93, 0xc0, 0x05, 0xa1, 0x78];
+/// let vals = read_array_with(&mut &buf[..], read_optional_value).unwrap();
+///
+/// assert_eq!(vec![None, Some(Value::from(5)), Some(Value::from("x"))], vals);
+/// ```
+pub fn read_optional_value<R: Read>(rd: &mut R) -> Result<Option<Value>, Error> {
+    match decode::read_marker(rd)? {
+        Marker::Null => Ok(None),
+        marker => dispatch(marker, rd).map(Some),
+    }
+}
+
+/// Reads a map whose keys must be exactly the strings in `expected_keys`, in any order.
+///
+/// The entries are returned in the order they were encoded. This is handy for enforcing a closed
+/// set of fields on a document without declaring a struct for it.
+///
+/// # Errors
+///
+/// Returns `Error::UnexpectedKey` as soon as a key outside `expected_keys` is read, or one that
+/// was already seen, leaving the reader inside the map. Once the whole map is read, returns
+/// `Error::MissingKey` with the first expected key that didn't appear. Keys that aren't strings
+/// fail with `Error::TypeMismatch`.
+///
+/// # Examples
+///
+/// ```
+/// use messpack_serde::decode::value::{read_map_expecting, Value};
+///
+/// // {"id": 1, "name": "x"}
+/// let buf = [0x82, 0xa2, 0x69, 0x64, 0x01, 0xa4, 0x6e, 0x61, 0x6d, 0x65, 0xa1, 0x78];
+/// let entries = read_map_expecting(&mut &buf[..], &["name", "id"]).unwrap();
+///
+/// assert_eq!(
+///     vec![("id".into(), Value::from(1)), ("name".into(), Value::from("x"))],
+///     entries
+/// );
+/// ```
+pub fn read_map_expecting<R: Read>(
+    rd: &mut R,
+    expected_keys: &[&str],
+) -> Result<Vec<(String, Value)>, Error> {
+    let mut seen = vec![false; expected_keys.len()];
+    let entries = super::read_map_with(
+        rd,
+        |rd| {
+            let (len, _) = super::read_str_len_with_class(rd)?;
+            let mut buf = Vec::new();
+            rd.take(u64::from(len))
+                .read_to_end(&mut buf)
+                .map_err(Error::InvalidDataRead)?;
+            if buf.len() != len as usize {
+                return Err(Error::InvalidDataRead(
+                    std::io::ErrorKind::UnexpectedEof.into(),
+                ));
+            }
+            let key = String::from_utf8(buf).map_err(|err| Error::Utf8Error(err.utf8_error()))?;
+
+            match expected_keys.iter().position(|&k| k == key) {
+                Some(idx) if !seen[idx] => {
+                    seen[idx] = true;
+                    Ok(key)
+                }
+                _ => Err(Error::UnexpectedKey(key)),
+            }
+        },
+        |rd| read_value(rd),
+    )?;
+
+    if let Some(idx) = seen.iter().position(|&seen| !seen) {
+        return Err(Error::MissingKey(expected_keys[idx].to_owned()));
+    }
+    Ok(entries)
+}
+
+/// Reads a value followed by a big-endian CRC-32 of its encoded bytes.
+///
+/// This is the counterpart of [`encode::write_value_checked`](crate::encode::write_value_checked).
+/// The checksum is CRC-32/ISO-HDLC, the variant used by zlib and Ethernet.
+///
+/// # Errors
+///
+/// Returns `Error::ChecksumMismatch` if the trailing checksum doesn't match the value, and
+/// `Error::InvalidDataRead` if the checksum is missing.
+///
+/// # Examples
+///
+/// ```
+/// use messpack_serde::decode::value::{read_value_checked, Value};
+///
+/// // true, followed by its CRC-32
+/// let buf = [0xc3, 0xd0, 0x6f, 0x7c, 0x87];
+///
+/// assert_eq!(Value::Boolean(true), read_value_checked(&mut &buf[..]).unwrap());
+/// ```
+pub fn read_value_checked<R: Read>(rd: &mut R) -> Result<Value, Error> {
+    let mut crc = Crc32::new(&mut *rd);
+    let val = read_value(&mut crc)?;
+    let actual = crc.sum();
+
+    let expected = rd.read_data_u32()?;
+    if expected != actual {
+        return Err(Error::ChecksumMismatch { expected, actual });
+    }
+    Ok(val)
+}
+
+/// Byte ranges covered by a decoded value and, recursively, by its elements.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SpanTree {
+    /// The range of stream positions holding the whole value, including its marker.
+    pub span: Range<u64>,
+    /// Spans of the elements of an array, or the keys and values of a map in alternating order.
+    ///
+    /// Empty for anything else.
+    pub children: Vec<SpanTree>,
+}
+
+/// Maximum nesting of arrays and maps accepted by [`read_value_spans`] and
+/// [`read_value_interned`].
+const MAX_DEPTH: usize = 1024;
+
+/// Reads a single value, recording the byte range of it and every nested value.
+///
+/// Positions are taken from `rd.stream_position()`, so they are absolute offsets in the stream
+/// rather than relative to where decoding started. This is meant for inspection tooling, such as
+/// highlighting which bytes of a document encode which field.
+///
+/// # Errors
+///
+/// Returns `Error::DepthLimitExceeded` if arrays and maps are nested more than 1024 levels deep,
+/// in addition to the usual read errors.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+///
+/// use messpack_serde::decode::value::{read_value_spans, Value};
+///
+/// // ["ab", 1]
+/// let mut rd = Cursor::new([0x92, 0xa2, 0x61, 0x62, 0x01]);
+/// let (val, spans) = read_value_spans(&mut rd).unwrap();
+///
+/// assert_eq!(Value::Array(vec![Value::from("ab"), Value::from(1)]), val);
+/// assert_eq!(0..5, spans.span);
+/// assert_eq!(1..4, spans.children[0].span);
+/// assert_eq!(4..5, spans.children[1].span);
+/// ```
+pub fn read_value_spans<R: Read + Seek>(rd: &mut R) -> Result<(Value, SpanTree), Error> {
+    // Containers are tracked on an explicit stack instead of by recursion, so that the depth
+    // limit rather than the call stack bounds how deep the input may be nested.
+    let mut stack: Vec<SpanFrame> = Vec::new();
+    loop {
+        let start = position(rd)?;
+        let marker = decode::read_marker(rd)?;
+        let (len, is_map) = match marker {
+            Marker::FixArray(len) => (Some(u32::from(len)), false),
+            Marker::Array16 => (Some(u32::from(rd.read_data_u16()?)), false),
+            Marker::Array32 => (Some(rd.read_data_u32()?), false),
+            Marker::FixMap(len) => (Some(u32::from(len)), true),
+            Marker::Map16 => (Some(u32::from(rd.read_data_u16()?)), true),
+            Marker::Map32 => (Some(rd.read_data_u32()?), true),
+            _ => (None, false),
+        };
+
+        let mut done = match len {
+            None => {
+                let val = dispatch(marker, &mut *rd)?;
+                let span = start..position(rd)?;
+                let children = Vec::new();
+                (val, SpanTree { span, children })
+            }
+            Some(len) => {
+                if stack.len() >= MAX_DEPTH {
+                    return Err(Error::DepthLimitExceeded);
+                }
+                let remaining = if is_map {
+                    u64::from(len) * 2
+                } else {
+                    u64::from(len)
+                };
+                let frame = SpanFrame {
+                    start,
+                    is_map,
+                    remaining,
+                    vals: Vec::new(),
+                    children: Vec::new(),
+                };
+                if remaining > 0 {
+                    stack.push(frame);
+                    continue;
+                }
+                frame.finish(position(rd)?)
+            }
+        };
+
+        loop {
+            let Some(frame) = stack.last_mut() else {
+                return Ok(done);
+            };
+            frame.vals.push(done.0);
+            frame.children.push(done.1);
+            frame.remaining -= 1;
+            if frame.remaining > 0 {
+                break;
+            }
+            let frame = stack.pop().expect("frame was just inspected");
+            done = frame.finish(position(rd)?);
+        }
+    }
+}
+
+/// An array or map whose elements are still being read by [`read_value_spans`].
+struct SpanFrame {
+    start: u64,
+    is_map: bool,
+    remaining: u64,
+    vals: Vec<Value>,
+    children: Vec<SpanTree>,
+}
+
+impl SpanFrame {
+    fn finish(self, end: u64) -> (Value, SpanTree) {
+        let val = if self.is_map {
+            let mut vals = self.vals.into_iter();
+            let mut entries = Vec::new();
+            while let (Some(key), Some(val)) = (vals.next(), vals.next()) {
+                entries.push((key, val));
+            }
+            Value::Map(entries)
+        } else {
+            Value::Array(self.vals)
+        };
+        let span = self.start..end;
+        let children = self.children;
+        (val, SpanTree { span, children })
+    }
+}
+
+fn position<R: Seek>(rd: &mut R) -> Result<u64, Error> {
+    rd.stream_position().map_err(Error::InvalidDataRead)
+}
+
+/// Reads every value up to the end of the input, carrying on past corrupt ones.
+///
+/// Each value is first skipped over to find where it ends, and only then decoded, so a value that
+/// is malformed anywhere inside, such as one holding the reserved marker `0xc1`, yields an error
+/// in its place instead of a partial result. If the value was well-formed, reading resumes right
+/// after it. Otherwise the reader is realigned as by
+/// [`Deserializer::recover_to_next`](super::Deserializer::recover_to_next), with the same caveat
+/// that leftovers of the corrupt value may come out as values of their own.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+///
+/// use messpack_serde::decode::value::{read_all_lenient, Value};
+///
+/// // 1, the reserved marker 0xc1, then "a"
+/// let buf = [0x01, 0xc1, 0xa1, 0x61];
+/// let vals = read_all_lenient(&mut Cursor::new(&buf[..]));
+///
+/// assert_eq!(3, vals.len());
+/// assert_eq!(Value::from(1), *vals[0].as_ref().unwrap());
+/// assert!(vals[1].is_err());
+/// assert_eq!(Value::from("a"), *vals[2].as_ref().unwrap());
+/// ```
+pub fn read_all_lenient<R: Read + Seek>(rd: &mut R) -> Vec<Result<Value, Error>> {
+    let mut vals = Vec::new();
+    loop {
+        match read_lenient(rd, &mut vals) {
+            Ok(true) => {}
+            Ok(false) => return vals,
+            Err(err) => {
+                vals.push(Err(err));
+                return vals;
+            }
+        }
+    }
+}
+
+/// Reads the next value of [`read_all_lenient`] into `vals`, returning `false` at the end of the
+/// input.
+///
+/// Errors are those of repositioning `rd`, after which there's no telling where the next value
+/// starts.
+fn read_lenient<R: Read + Seek>(
+    rd: &mut R,
+    vals: &mut Vec<Result<Value, Error>>,
+) -> Result<bool, Error> {
+    let start = position(rd)?;
+    match super::skip_value(&mut *rd).map_err(Error::from) {
+        Ok(()) => {
+            let end = position(rd)?;
+            rd.seek(SeekFrom::Start(start))
+                .map_err(Error::InvalidDataRead)?;
+            vals.push(read_value(rd));
+            // Decoding stops early when it fails, e.g. at the depth limit.
+            rd.seek(SeekFrom::Start(end))
+                .map_err(Error::InvalidDataRead)?;
+            Ok(true)
+        }
+        // Nothing left at all, as opposed to a value cut short.
+        Err(Error::InvalidMarkerRead(_)) if position(rd)? == start => Ok(false),
+        Err(err) => {
+            vals.push(Err(err));
+            rd.seek(SeekFrom::Start(start + 1))
+                .map_err(Error::InvalidDataRead)?;
+            // This only fails if there's no value left, which the next call finds out.
+            let _ = super::Deserializer::new(&mut *rd).recover_to_next();
+            Ok(true)
+        }
+    }
+}
+
+/// Expected shape of a value, checked by [`read_value_validated`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Schema {
+    /// An integer of any width and signedness.
+    Int,
+    /// A string.
+    Str,
+    /// An array whose elements all match the enclosed schema.
+    Array(Box<Schema>),
+    /// A map whose keys match the first schema and whose values match the second.
+    Map(Box<Schema>, Box<Schema>),
+    /// Any value at all.
+    Any,
+    /// Either `nil` or a value matching the enclosed schema.
+    Optional(Box<Schema>),
+}
+
+/// Reads a single value, checking that it matches `schema` as it's decoded.
+///
+/// Decoding stops at the first node that doesn't match, so a mismatching document is rejected
+/// without being read in full.
+///
+/// # Errors
+///
+/// Returns `Error::SchemaMismatch` with the path to the first mismatching node. Paths start with
+/// `$`, followed by `[i]` for array elements, `.key` for map values under a string key, `[key]`
+/// for map values under other keys, and `.<key i>` for the `i`-th key of a map itself.
+///
+/// # Examples
+///
+/// ```
+/// use messpack_serde::decode::value::{read_value_validated, Schema};
+/// use messpack_serde::decode::Error;
+///
+/// let schema = Schema::Map(Box::new(Schema::Str), Box::new(Schema::Int));
+///
+/// // {"a": 1}
+/// assert!(read_value_validated(&mut &[0x81, 0xa1, 0x61, 0x01][..], &schema).is_ok());
+///
+/// // {"a": "x"}
+/// match read_value_validated(&mut &[0x81, 0xa1, 0x61, 0xa1, 0x78][..], &schema) {
+///     Err(Error::SchemaMismatch { path, .. }) => assert_eq!("$.a", path),
+///     other => panic!("unexpected result: {other:?}"),
+/// }
+/// ```
+pub fn read_value_validated<R: Read>(rd: &mut R, schema: &Schema) -> Result<Value, Error> {
+    let mut path = String::from("$");
+    validate(rd, schema, &mut path)
+}
+
+fn validate<R: Read>(rd: &mut R, schema: &Schema, path: &mut String) -> Result<Value, Error> {
+    let marker = decode::read_marker(rd)?;
+    validate_marker(rd, marker, schema, path)
+}
+
+// Checks a value whose marker was already taken, so that `Optional` can pass it on to its
+// inner schema.
+fn validate_marker<R: Read>(
+    mut rd: &mut R,
+    marker: Marker,
+    schema: &Schema,
+    path: &mut String,
+) -> Result<Value, Error> {
+    let mismatch = |path: &str| Error::SchemaMismatch {
+        path: path.to_owned(),
+        found: marker,
+    };
+
+    match (schema, marker) {
+        (Schema::Any, _) => dispatch(marker, rd),
+        (Schema::Optional(_), Marker::Null) => Ok(Value::Nil),
+        (Schema::Optional(inner), _) => validate_marker(rd, marker, inner, path),
+        (
+            Schema::Int,
+            Marker::FixPos(_)
+            | Marker::FixNeg(_)
+            | Marker::U8
+            | Marker::U16
+            | Marker::U32
+            | Marker::U64
+            | Marker::I8
+            | Marker::I16
+            | Marker::I32
+            | Marker::I64,
+        ) => dispatch(marker, rd),
+        (Schema::Str, Marker::FixStr(_) | Marker::Str8 | Marker::Str16 | Marker::Str32) => {
+            dispatch(marker, rd)
+        }
+        (Schema::Array(elem), Marker::FixArray(_) | Marker::Array16 | Marker::Array32) => {
+            let len = match marker {
+                Marker::FixArray(len) => len.into(),
+                Marker::Array16 => (&mut rd).read_data_u16()?.into(),
+                _ => (&mut rd).read_data_u32()?,
+            };
+            let mut vals = Vec::with_capacity(len.min(4096) as usize);
+            for i in 0..len {
+                let mark = path.len();
+                push_fmt(path, format_args!("[{i}]"));
+                vals.push(validate(rd, elem, path)?);
+                path.truncate(mark);
+            }
+            Ok(Value::Array(vals))
+        }
+        (
+            Schema::Map(key_schema, val_schema),
+            Marker::FixMap(_) | Marker::Map16 | Marker::Map32,
+        ) => {
+            let len = match marker {
+                Marker::FixMap(len) => len.into(),
+                Marker::Map16 => (&mut rd).read_data_u16()?.into(),
+                _ => (&mut rd).read_data_u32()?,
+            };
+            let mut entries = Vec::with_capacity(len.min(4096) as usize);
+            for i in 0..len {
+                let mark = path.len();
+                push_fmt(path, format_args!(".<key {i}>"));
+                let key = validate(rd, key_schema, path)?;
+                path.truncate(mark);
+
+                match key.as_str() {
+                    Some(key) => push_fmt(path, format_args!(".{key}")),
+                    None => push_fmt(path, format_args!("[{key}]")),
+                }
+                let val = validate(rd, val_schema, path)?;
+                path.truncate(mark);
+                entries.push((key, val));
+            }
+            Ok(Value::Map(entries))
+        }
+        _ => Err(mismatch(path)),
+    }
+}
+
+fn push_fmt(path: &mut String, args: std::fmt::Arguments<'_>) {
+    use std::fmt::Write;
+
+    let _ = path.write_fmt(args);
+}
+
+/// Deduplicates map keys across the values read by [`read_value_interned`].
+///
+/// Keep one interner around for as long as its keys should be shared, e.g. for a whole stream of
+/// similar documents.
+#[derive(Debug, Default)]
+pub struct Interner {
+    strings: HashSet<Rc<str>>,
+}
+
+impl Interner {
+    /// Creates an empty interner.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of distinct strings stored.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.strings.len()
+    }
+
+    /// Returns `true` if no strings are stored.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.strings.is_empty()
+    }
+
+    fn intern(&mut self, s: &str) -> Rc<str> {
+        if let Some(s) = self.strings.get(s) {
+            return Rc::clone(s);
+        }
+        let s: Rc<str> = s.into();
+        self.strings.insert(Rc::clone(&s));
+        s
+    }
+}
+
+/// A value read by [`read_value_interned`], whose string map keys share storage.
+///
+/// Only arrays and maps are broken out, as those are what can contain map keys. Everything else is
+/// kept as a plain [`Value`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum InternedValue {
+    /// Any value other than an array or a map.
+    Leaf(Value),
+    /// An array.
+    Array(Vec<InternedValue>),
+    /// A map, with its entries in the order they were encoded.
+    Map(Vec<(InternedKey, InternedValue)>),
+}
+
+/// A map key of an [`InternedValue`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum InternedKey {
+    /// A string key, shared with every other occurrence of the same key.
+    Str(Rc<str>),
+    /// A key of any other type, including strings that aren't valid UTF-8.
+    Other(Value),
+}
+
+impl From<InternedValue> for Value {
+    fn from(val: InternedValue) -> Self {
+        match val {
+            InternedValue::Leaf(val) => val,
+            InternedValue::Array(vals) => Self::Array(vals.into_iter().map(Self::from).collect()),
+            InternedValue::Map(entries) => Self::Map(
+                entries
+                    .into_iter()
+                    .map(|(key, val)| {
+                        let key = match key {
+                            InternedKey::Str(key) => Self::from(&*key),
+                            InternedKey::Other(key) => key,
+                        };
+                        (key, Self::from(val))
+                    })
+                    .collect(),
+            ),
+        }
+    }
+}
+
+/// Reads a single value, sharing the storage of identical string map keys through `interner`.
+///
+/// Documents such as arrays of records repeat the same keys over and over. Decoding them into a
+/// [`Value`] allocates a `String` per occurrence, whereas here every occurrence of a key is an
+/// `Rc<str>` pointing at the same allocation.
+///
+/// # Errors
+///
+/// Returns `Error::DepthLimitExceeded` for arrays and maps nested more than 1024 levels deep, on
+/// top of the usual decoding errors.
+///
+/// # Examples
+///
+/// ```
+/// use messpack_serde::decode::value::{read_value_interned, Interner};
+///
+/// // [{"id": 1}, {"id": 2}]
+/// let buf = [0x92, 0x81, 0xa2, 0x69, 0x64, 0x01, 0x81, 0xa2, 0x69, 0x64, 0x02];
+/// let mut interner = Interner::new();
+/// read_value_interned(&mut &buf[..], &mut interner).unwrap();
+///
+/// assert_eq!(1, interner.len());
+/// ```
+pub fn read_value_interned<R: Read>(
+    rd: &mut R,
+    interner: &mut Interner,
+) -> Result<InternedValue, Error> {
+    read_interned(rd, interner, MAX_DEPTH)
+}
+
+fn read_interned<R: Read>(
+    rd: &mut R,
+    interner: &mut Interner,
+    depth: usize,
+) -> Result<InternedValue, Error> {
+    let marker = decode::read_marker(rd)?;
+    let len = match marker {
+        Marker::FixArray(len) | Marker::FixMap(len) => len.into(),
+        Marker::Array16 | Marker::Map16 => rd.read_data_u16()?.into(),
+        Marker::Array32 | Marker::Map32 => rd.read_data_u32()?,
+        marker => return read_leaf(rd, marker).map(InternedValue::Leaf),
+    };
+    let depth = depth.checked_sub(1).ok_or(Error::DepthLimitExceeded)?;
+    let capacity = len.min(4096) as usize;
+
+    if let Marker::FixArray(_) | Marker::Array16 | Marker::Array32 = marker {
+        let mut vals = Vec::with_capacity(capacity);
+        for _ in 0..len {
+            vals.push(read_interned(rd, interner, depth)?);
+        }
+        return Ok(InternedValue::Array(vals));
+    }
+
+    let mut entries = Vec::with_capacity(capacity);
+    for _ in 0..len {
+        let key = match read_interned(rd, interner, depth)? {
+            InternedValue::Leaf(Value::String(key)) => match key.as_str() {
+                Some(key) => InternedKey::Str(interner.intern(key)),
+                None => InternedKey::Other(Value::String(key)),
+            },
+            key => InternedKey::Other(key.into()),
+        };
+        entries.push((key, read_interned(rd, interner, depth)?));
+    }
+    Ok(InternedValue::Map(entries))
+}
+
+// Kept out of line so that the recursion in `read_interned` only pays for small frames.
+// Despite the name, `marker` may also start an array or map, which is then read in full.
+#[inline(never)]
+fn read_leaf<R: Read>(rd: &mut R, marker: Marker) -> Result<Value, Error> {
+    dispatch(marker, rd)
+}
+
+/// Copies a single value from `rd` to `wr`, passing every scalar through `f` on the way.
+///
+/// Strings, binaries, exts, numbers, booleans and `nil` are handed to `f` one at a time, and are
+/// replaced by the value it returns, if any. Arrays and maps are re-emitted as they stream by and
+/// never collected, so memory use is bounded by the largest scalar and the nesting depth. Map
+/// keys are copied as they are.
+///
+/// # Errors
+///
+/// Returns the same errors as reading a value. Failing to write is reported as
+/// `Error::Uncategorized`, after which `wr` holds a partial value.
+///
+/// # Examples
+///
+/// ```
+/// use messpack_serde::decode::value::{transform, Value};
+///
+/// // {"pw": "hunter2", "n": [1, "x"]}
+/// let buf = [
+///     0x82, 0xa2, 0x70, 0x77, 0xa7, 0x68, 0x75, 0x6e, 0x74, 0x65, 0x72, 0x32, 0xa1, 0x6e, 0x92,
+///     0x01, 0xa1, 0x78,
+/// ];
+/// let mut out = Vec::new();
+/// transform(&mut &buf[..], &mut out, |val| val.is_str().then(|| Value::from("***"))).unwrap();
+///
+/// let val = rmpv::decode::read_value(&mut &out[..]).unwrap();
+/// assert_eq!(Value::from("***"), val["pw"]);
+/// assert_eq!(Value::Array(vec![Value::from(1), Value::from("***")]), val["n"]);
+/// ```
+pub fn transform<R, W, F>(rd: &mut R, wr: &mut W, mut f: F) -> Result<(), Error>
+where
+    R: Read,
+    W: Write,
+    F: FnMut(&Value) -> Option<Value>,
+{
+    fn write_err(err: impl std::fmt::Display) -> Error {
+        Error::Uncategorized(format!("failed to write the transformed value: {err}"))
+    }
+
+    // Remaining values of each open container, and whether it's a map. Maps count keys and values
+    // separately, so a key is next whenever an even number is left.
+    let mut open: Vec<(u64, bool)> = Vec::new();
+    loop {
+        let is_key = matches!(open.last(), Some(&(left, true)) if left % 2 == 0);
+        if let Some((left, _)) = open.last_mut() {
+            *left -= 1;
+        }
+
+        let marker = decode::read_marker(rd)?;
+        let children = match marker {
+            _ if is_key => {
+                let key = read_leaf(rd, marker)?;
+                rmpv::encode::write_value(wr, &key).map_err(write_err)?;
+                None
+            }
+            Marker::FixArray(len) => Some((u64::from(len), false)),
+            Marker::Array16 => Some((u64::from(rd.read_data_u16()?), false)),
+            Marker::Array32 => Some((u64::from(rd.read_data_u32()?), false)),
+            Marker::FixMap(len) => Some((u64::from(len), true)),
+            Marker::Map16 => Some((u64::from(rd.read_data_u16()?), true)),
+            Marker::Map32 => Some((u64::from(rd.read_data_u32()?), true)),
+            marker => {
+                let val = read_leaf(rd, marker)?;
+                let val = f(&val).unwrap_or(val);
+                rmpv::encode::write_value(wr, &val).map_err(write_err)?;
+                None
+            }
+        };
+        if let Some((len, is_map)) = children {
+            if is_map {
+                rmp::encode::write_map_len(wr, len as u32).map_err(write_err)?;
+                open.push((len * 2, true));
+            } else {
+                rmp::encode::write_array_len(wr, len as u32).map_err(write_err)?;
+                open.push((len, false));
+            }
+        }
+
+        while let Some(&(0, _)) = open.last() {
+            open.pop();
+        }
+        if open.is_empty() {
+            return Ok(());
+        }
+    }
+}
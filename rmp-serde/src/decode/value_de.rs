@@ -0,0 +1,680 @@
+//! Deserializes Rust data structures from an in-memory [`Value`] tree.
+
+use std::slice;
+
+use serde::de::{self, DeserializeSeed, IntoDeserializer, Unexpected, Visitor};
+use serde::forward_to_deserialize_any;
+
+use super::value::Value;
+use super::Error;
+use crate::MSGPACK_EXT_STRUCT_NAME;
+
+/// A deserializer that reads from a borrowed [`Value`] instead of bytes.
+///
+/// It accepts the same shapes as the byte [`Deserializer`](super::Deserializer) with its default
+/// configuration, and strings and binary data are handed out borrowed from the tree.
+///
+/// # Examples
+///
+/// ```
+/// use messpack_serde::decode::value::Value;
+/// use messpack_serde::decode::ValueDeserializer;
+/// use serde::Deserialize;
+///
+/// let val = Value::Array(vec![Value::from(1), Value::from("a")]);
+/// let (n, s) = <(u8, &str)>::deserialize(ValueDeserializer::new(&val)).unwrap();
+///
+/// assert_eq!((1, "a"), (n, s));
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct ValueDeserializer<'de> {
+    val: &'de Value,
+}
+
+impl<'de> ValueDeserializer<'de> {
+    /// Creates a deserializer reading from `val`.
+    #[inline]
+    #[must_use]
+    pub fn new(val: &'de Value) -> Self {
+        Self { val }
+    }
+
+    #[inline]
+    fn tree(self) -> TreeDeserializer<'de, Borrowed> {
+        TreeDeserializer {
+            val: self.val,
+            lend: Borrowed,
+        }
+    }
+}
+
+impl<'de> de::Deserializer<'de> for ValueDeserializer<'de> {
+    type Error = Error;
+
+    #[inline]
+    fn is_human_readable(&self) -> bool {
+        false
+    }
+
+    #[inline]
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.tree().deserialize_any(visitor)
+    }
+
+    #[inline]
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.tree().deserialize_option(visitor)
+    }
+
+    #[inline]
+    fn deserialize_enum<V>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.tree().deserialize_enum(name, variants, visitor)
+    }
+
+    #[inline]
+    fn deserialize_newtype_struct<V>(
+        self,
+        name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.tree().deserialize_newtype_struct(name, visitor)
+    }
+
+    #[inline]
+    fn deserialize_unit_struct<V>(self, name: &'static str, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.tree().deserialize_unit_struct(name, visitor)
+    }
+
+    #[inline]
+    fn deserialize_i128<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.tree().deserialize_i128(visitor)
+    }
+
+    #[inline]
+    fn deserialize_u128<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.tree().deserialize_u128(visitor)
+    }
+
+    #[inline]
+    fn deserialize_identifier<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.tree().deserialize_identifier(visitor)
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 u8 u16 u32 u64 f32 f64 char str string bytes byte_buf unit
+        seq tuple tuple_struct map struct ignored_any
+    }
+}
+
+/// How the strings and binary data of a tree are handed to visitors.
+trait Lend<'a, 'de>: Copy {
+    fn visit_str<V: Visitor<'de>>(self, v: &'a str, visitor: V) -> Result<V::Value, Error>;
+    fn visit_bytes<V: Visitor<'de>>(self, v: &'a [u8], visitor: V) -> Result<V::Value, Error>;
+}
+
+/// Lends data borrowed from a tree that outlives the deserialized value.
+#[derive(Clone, Copy, Debug)]
+struct Borrowed;
+
+impl<'a: 'de, 'de> Lend<'a, 'de> for Borrowed {
+    #[inline]
+    fn visit_str<V: Visitor<'de>>(self, v: &'a str, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_borrowed_str(v)
+    }
+
+    #[inline]
+    fn visit_bytes<V: Visitor<'de>>(self, v: &'a [u8], visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_borrowed_bytes(v)
+    }
+}
+
+/// Lends transient data, for a tree that is dropped before the deserialized value.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Copied;
+
+impl<'de> Lend<'_, 'de> for Copied {
+    #[inline]
+    fn visit_str<V: Visitor<'de>>(self, v: &str, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_str(v)
+    }
+
+    #[inline]
+    fn visit_bytes<V: Visitor<'de>>(self, v: &[u8], visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_bytes(v)
+    }
+}
+
+/// The deserializer behind [`ValueDeserializer`], generic over how it lends the tree's data.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct TreeDeserializer<'a, M> {
+    val: &'a Value,
+    lend: M,
+}
+
+impl<'a> TreeDeserializer<'a, Copied> {
+    /// Creates a deserializer reading from `val`, copying out its strings and binary data.
+    ///
+    /// This is how a [`Value`] built during deserialization, such as an ext handler's, is read
+    /// into types that would borrow from the input.
+    #[inline]
+    pub(crate) fn copying(val: &'a Value) -> Self {
+        Self { val, lend: Copied }
+    }
+}
+
+/// Describes `val` for serde's type mismatch errors.
+fn unexpected(val: &Value) -> Unexpected<'_> {
+    match *val {
+        Value::Nil => Unexpected::Unit,
+        Value::Boolean(v) => Unexpected::Bool(v),
+        Value::Integer(v) => match v.as_u64() {
+            Some(v) => Unexpected::Unsigned(v),
+            None => Unexpected::Signed(v.as_i64().unwrap_or_default()),
+        },
+        Value::F32(v) => Unexpected::Float(v.into()),
+        Value::F64(v) => Unexpected::Float(v),
+        Value::String(ref v) => match v.as_str() {
+            Some(v) => Unexpected::Str(v),
+            None => Unexpected::Bytes(v.as_bytes()),
+        },
+        Value::Binary(ref v) => Unexpected::Bytes(v),
+        Value::Array(_) => Unexpected::Seq,
+        Value::Map(_) => Unexpected::Map,
+        Value::Ext(..) => Unexpected::NewtypeStruct,
+    }
+}
+
+fn visit_array<'a, 'de, M, V>(vals: &'a [Value], lend: M, visitor: V) -> Result<V::Value, Error>
+where
+    M: Lend<'a, 'de>,
+    V: Visitor<'de>,
+{
+    let mut seq = SeqAccess {
+        iter: vals.iter(),
+        lend,
+    };
+    let res = visitor.visit_seq(&mut seq)?;
+    match seq.iter.len() {
+        0 => Ok(res),
+        excess => Err(Error::LengthMismatch {
+            expected: vals.len() - excess,
+            actual: vals.len() as u32,
+        }),
+    }
+}
+
+fn visit_map<'a, 'de, M, V>(
+    entries: &'a [(Value, Value)],
+    lend: M,
+    visitor: V,
+) -> Result<V::Value, Error>
+where
+    M: Lend<'a, 'de>,
+    V: Visitor<'de>,
+{
+    let mut map = MapAccess {
+        iter: entries.iter(),
+        val: None,
+        lend,
+    };
+    let res = visitor.visit_map(&mut map)?;
+    match map.iter.len() {
+        0 => Ok(res),
+        excess => Err(Error::LengthMismatch {
+            expected: entries.len() - excess,
+            actual: entries.len() as u32,
+        }),
+    }
+}
+
+impl<'a, 'de, M: Lend<'a, 'de>> de::Deserializer<'de> for TreeDeserializer<'a, M> {
+    type Error = Error;
+
+    #[inline]
+    fn is_human_readable(&self) -> bool {
+        false
+    }
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        match *self.val {
+            Value::Nil => visitor.visit_unit(),
+            Value::Boolean(v) => visitor.visit_bool(v),
+            Value::Integer(v) => match (v.as_u64(), v.as_i64()) {
+                (Some(v), _) => visitor.visit_u64(v),
+                (None, Some(v)) => visitor.visit_i64(v),
+                (None, None) => Err(Error::OutOfRange),
+            },
+            Value::F32(v) => visitor.visit_f32(v),
+            Value::F64(v) => visitor.visit_f64(v),
+            Value::String(ref v) => match v.as_str() {
+                Some(s) => self.lend.visit_str(s, visitor),
+                // Allow to unpack invalid UTF-8 bytes into a byte array.
+                None => match self.lend.visit_bytes(v.as_bytes(), visitor) {
+                    Ok(buf) => Ok(buf),
+                    Err(err) => Err(v.as_err().map_or(err, |&err| Error::Utf8Error(err))),
+                },
+            },
+            Value::Binary(ref v) => self.lend.visit_bytes(v, visitor),
+            Value::Array(ref vals) => visit_array(vals, self.lend, visitor),
+            Value::Map(ref entries) => visit_map(entries, self.lend, visitor),
+            Value::Ext(tag, ref data) => visitor.visit_newtype_struct(ExtDeserializer {
+                tag,
+                data,
+                lend: self.lend,
+            }),
+        }
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        match *self.val {
+            Value::Nil => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        _name: &str,
+        _variants: &[&str],
+        visitor: V,
+    ) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        match *self.val {
+            // Enums are either encoded as maps with a single K/V pair
+            // where the K = the variant & V = associated data
+            // or as just the variant
+            Value::Map(ref entries) => match entries[..] {
+                [(ref variant, ref val)] => visitor.visit_enum(EnumAccess {
+                    variant,
+                    val: Some(val),
+                    lend: self.lend,
+                }),
+                _ => Err(Error::LengthMismatch {
+                    expected: 1,
+                    actual: entries.len() as u32,
+                }),
+            },
+            ref variant => visitor.visit_enum(EnumAccess {
+                variant,
+                val: None,
+                lend: self.lend,
+            }),
+        }
+    }
+
+    fn deserialize_newtype_struct<V>(
+        self,
+        name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        if name == MSGPACK_EXT_STRUCT_NAME {
+            return match *self.val {
+                Value::Ext(tag, ref data) => visitor.visit_newtype_struct(ExtDeserializer {
+                    tag,
+                    data,
+                    lend: self.lend,
+                }),
+                ref val => Err(de::Error::invalid_type(unexpected(val), &"ext")),
+            };
+        }
+
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_unit_struct<V>(self, _name: &'static str, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        // Unit structs are serialized as [], which only reads as unit when asked for one.
+        match *self.val {
+            Value::Nil => visitor.visit_unit(),
+            Value::Array(ref vals) if vals.is_empty() => visitor.visit_unit(),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_i128<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_i128(read_i128(self.val)?)
+    }
+
+    fn deserialize_u128<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        let val = read_i128(self.val)?;
+        let val = match *self.val {
+            // The 16 bytes are the `u128` itself, only reinterpreted as signed by `read_i128`.
+            Value::Binary(_) => val as u128,
+            _ => u128::try_from(val).map_err(|_| Error::OutOfRange)?,
+        };
+        visitor.visit_u128(val)
+    }
+
+    fn deserialize_identifier<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        if let Value::Nil = *self.val {
+            return Err(Error::Syntax(
+                "found nil where a field or variant name was expected".into(),
+            ));
+        }
+        self.deserialize_any(visitor)
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 u8 u16 u32 u64 f32 f64 char str string bytes byte_buf unit
+        seq tuple tuple_struct map struct ignored_any
+    }
+}
+
+/// Reads a 128-bit integer, which is written as 16 big-endian bytes when it doesn't fit 64 bits.
+fn read_i128(val: &Value) -> Result<i128, Error> {
+    match *val {
+        Value::Integer(v) => match (v.as_u64(), v.as_i64()) {
+            (Some(v), _) => Ok(v.into()),
+            (None, Some(v)) => Ok(v.into()),
+            (None, None) => Err(Error::OutOfRange),
+        },
+        Value::Binary(ref buf) => match <[u8; 16]>::try_from(&buf[..]) {
+            Ok(buf) => Ok(i128::from_be_bytes(buf)),
+            Err(_) => Err(Error::LengthMismatch {
+                expected: 16,
+                actual: buf.len() as u32,
+            }),
+        },
+        ref val => Err(de::Error::invalid_type(
+            unexpected(val),
+            &"a 128-bit integer",
+        )),
+    }
+}
+
+struct SeqAccess<'a, M> {
+    iter: slice::Iter<'a, Value>,
+    lend: M,
+}
+
+impl<'a, 'de, M: Lend<'a, 'de>> de::SeqAccess<'de> for SeqAccess<'a, M> {
+    type Error = Error;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>, Error>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        match self.iter.next() {
+            Some(val) => seed
+                .deserialize(TreeDeserializer {
+                    val,
+                    lend: self.lend,
+                })
+                .map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.iter.len())
+    }
+}
+
+struct MapAccess<'a, M> {
+    iter: slice::Iter<'a, (Value, Value)>,
+    val: Option<&'a Value>,
+    lend: M,
+}
+
+impl<'a, 'de, M: Lend<'a, 'de>> de::MapAccess<'de> for MapAccess<'a, M> {
+    type Error = Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, Error>
+    where
+        K: DeserializeSeed<'de>,
+    {
+        match self.iter.next() {
+            Some((key, val)) => {
+                self.val = Some(val);
+                seed.deserialize(TreeDeserializer {
+                    val: key,
+                    lend: self.lend,
+                })
+                .map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value, Error>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        match self.val.take() {
+            Some(val) => seed.deserialize(TreeDeserializer {
+                val,
+                lend: self.lend,
+            }),
+            None => Err(Error::Uncategorized(
+                "map value requested before its key".into(),
+            )),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.iter.len())
+    }
+}
+
+/// Gives access to an enum variant, with `val` holding its data if it has any.
+struct EnumAccess<'a, M> {
+    variant: &'a Value,
+    val: Option<&'a Value>,
+    lend: M,
+}
+
+impl<'a, 'de, M: Lend<'a, 'de>> de::EnumAccess<'de> for EnumAccess<'a, M> {
+    type Error = Error;
+    type Variant = Self;
+
+    fn variant_seed<V>(self, seed: V) -> Result<(V::Value, Self), Error>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        let variant = seed.deserialize(TreeDeserializer {
+            val: self.variant,
+            lend: self.lend,
+        })?;
+        Ok((variant, self))
+    }
+}
+
+impl<'a, 'de, M: Lend<'a, 'de>> de::VariantAccess<'de> for EnumAccess<'a, M> {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<(), Error> {
+        match self.val {
+            None | Some(Value::Nil) => Ok(()),
+            Some(val) => Err(de::Error::invalid_type(unexpected(val), &"unit variant")),
+        }
+    }
+
+    fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value, Error>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        match self.val {
+            Some(val) => seed.deserialize(TreeDeserializer {
+                val,
+                lend: self.lend,
+            }),
+            None => Err(de::Error::invalid_type(
+                Unexpected::UnitVariant,
+                &"newtype variant",
+            )),
+        }
+    }
+
+    fn tuple_variant<V>(self, _len: usize, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self.val {
+            Some(val) => de::Deserializer::deserialize_any(
+                TreeDeserializer {
+                    val,
+                    lend: self.lend,
+                },
+                visitor,
+            ),
+            None => Err(de::Error::invalid_type(
+                Unexpected::UnitVariant,
+                &"tuple variant",
+            )),
+        }
+    }
+
+    fn struct_variant<V>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self.val {
+            Some(val) => de::Deserializer::deserialize_any(
+                TreeDeserializer {
+                    val,
+                    lend: self.lend,
+                },
+                visitor,
+            ),
+            None => Err(de::Error::invalid_type(
+                Unexpected::UnitVariant,
+                &"struct variant",
+            )),
+        }
+    }
+}
+
+/// Presents an ext value as the `(tag, data)` tuple that ext newtypes expect.
+struct ExtDeserializer<'a, M> {
+    tag: i8,
+    data: &'a [u8],
+    lend: M,
+}
+
+impl<'a, 'de, M: Lend<'a, 'de>> de::Deserializer<'de> for ExtDeserializer<'a, M> {
+    type Error = Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_seq(ExtAccess {
+            tag: Some(self.tag),
+            data: Some(self.data),
+            lend: self.lend,
+        })
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf
+        option unit unit_struct newtype_struct seq tuple tuple_struct map struct enum identifier
+        ignored_any
+    }
+}
+
+struct ExtAccess<'a, M> {
+    tag: Option<i8>,
+    data: Option<&'a [u8]>,
+    lend: M,
+}
+
+impl<'a, 'de, M: Lend<'a, 'de>> de::SeqAccess<'de> for ExtAccess<'a, M> {
+    type Error = Error;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>, Error>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        if let Some(tag) = self.tag.take() {
+            let tag: de::value::I8Deserializer<Error> = tag.into_deserializer();
+            return seed.deserialize(tag).map(Some);
+        }
+        match self.data.take() {
+            Some(data) => seed
+                .deserialize(ExtDataDeserializer {
+                    data,
+                    lend: self.lend,
+                })
+                .map(Some),
+            None => Ok(None),
+        }
+    }
+}
+
+/// The data of an ext value, lent as bytes.
+struct ExtDataDeserializer<'a, M> {
+    data: &'a [u8],
+    lend: M,
+}
+
+impl<'a, 'de, M: Lend<'a, 'de>> de::Deserializer<'de> for ExtDataDeserializer<'a, M> {
+    type Error = Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.lend.visit_bytes(self.data, visitor)
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf
+        option unit unit_struct newtype_struct seq tuple tuple_struct map struct enum identifier
+        ignored_any
+    }
+}
@@ -1,7 +1,9 @@
 //! Serialize a Rust data structure into MessagePack data.
 
 use crate::bytes::OnlyBytes;
-use crate::config::BytesMode;
+use crate::config::{BytesMode, Signedness};
+use crate::crc32::Crc32;
+use crate::decode::value::Value;
 use std::error;
 use std::fmt::{self, Display};
 use std::io::Write;
@@ -21,7 +23,11 @@ use crate::config::{
     BinaryConfig, DefaultConfig, HumanReadableConfig, RuntimeConfig, SerializerConfig,
     StructMapConfig, StructTupleConfig,
 };
-use crate::MSGPACK_EXT_STRUCT_NAME;
+use crate::{MSGPACK_EXT_STRUCT_NAME, MSGPACK_RAW_VALUE_NAME};
+
+mod value;
+
+pub use self::value::ValueSerializer;
 
 /// This type represents all possible errors that can occur when serializing or
 /// deserializing MessagePack data.
@@ -36,6 +42,8 @@ pub enum Error {
     InvalidDataModel(&'static str),
     /// Depth limit exceeded
     DepthLimitExceeded,
+    /// A NaN or infinite float was serialized while they are rejected.
+    NonFiniteFloat,
     /// Catchall for syntax error messages.
     Syntax(String),
 }
@@ -48,6 +56,7 @@ impl error::Error for Error {
             Self::UnknownLength => None,
             Self::InvalidDataModel(_) => None,
             Self::DepthLimitExceeded => None,
+            Self::NonFiniteFloat => None,
             Self::Syntax(..) => None,
         }
     }
@@ -63,6 +72,7 @@ impl Display for Error {
             }
             Self::InvalidDataModel(r) => write!(f, "serialize data model is invalid: {r}"),
             Self::DepthLimitExceeded => f.write_str("depth limit exceeded"),
+            Self::NonFiniteFloat => f.write_str("non-finite floats are not allowed"),
             Self::Syntax(ref msg) => f.write_str(msg),
         }
     }
@@ -170,6 +180,12 @@ impl<W: Write> Serializer<W, DefaultConfig> {
             _back_compat_config: PhantomData,
         }
     }
+
+    /// Constructs a writer of length-prefixed frames, see [`FramedWriter`].
+    #[inline]
+    pub fn framed(wr: W) -> FramedWriter<W> {
+        FramedWriter { wr }
+    }
 }
 
 impl<'a, W: Write + 'a, C> Serializer<W, C> {
@@ -202,6 +218,34 @@ impl<'a, W: Write + 'a, C: SerializerConfig> Serializer<W, C> {
     }
 }
 
+impl<W: Write, C: SerializerConfig> Serializer<W, C> {
+    fn write_field_name(&mut self, key: &str) -> Result<(), Error> {
+        if self.config.bin_keys {
+            encode::write_bin(&mut self.wr, key.as_bytes())?;
+        } else {
+            encode::write_str(&mut self.wr, key)?;
+        }
+        Ok(())
+    }
+
+    /// Serializes a map key, rewriting it as `bin` if it turns out to be a single string.
+    fn serialize_bin_key<T: ?Sized + Serialize>(&mut self, key: &T) -> Result<(), Error> {
+        let mut buf = UnknownLengthCompound::from(&*self);
+        key.serialize(&mut buf.se)?;
+        let buf = buf.se.into_inner();
+
+        let mut rd = &buf[..];
+        match rmp::decode::read_str_len(&mut rd) {
+            Ok(len) if rd.len() == len as usize => encode::write_bin(&mut self.wr, rd)?,
+            _ => self
+                .wr
+                .write_all(&buf)
+                .map_err(ValueWriteError::InvalidDataWrite)?,
+        }
+        Ok(())
+    }
+}
+
 impl<W: Write, C> Serializer<W, C> {
     /// Consumes this serializer returning the new one, which will serialize structs as a map.
     ///
@@ -307,6 +351,66 @@ impl<W: Write, C> Serializer<W, C> {
         self.config.bytes = mode;
         self
     }
+
+    /// Rejects NaN and infinite floats, making `serialize_f32` and `serialize_f64` return
+    /// [`Error::NonFiniteFloat`] instead of writing their IEEE 754 bits.
+    ///
+    /// This is useful for protocols that must stay JSON-compatible.
+    ///
+    /// ```rust
+    /// use serde::ser::Serialize;
+    /// let mut msgpack_data = Vec::new();
+    /// let mut serializer = messpack_serde::Serializer::new(&mut msgpack_data)
+    ///     .with_reject_non_finite(true);
+    /// assert!(f64::NAN.serialize(&mut serializer).is_err());
+    /// ```
+    #[inline]
+    pub const fn with_reject_non_finite(mut self, reject: bool) -> Self {
+        self.config.reject_non_finite = reject;
+        self
+    }
+
+    /// Writes string map keys, and struct field names in struct map mode, as `bin` instead of
+    /// `str`.
+    ///
+    /// This is **not** standard MessagePack, and only exists for interop with consumers that
+    /// insist on binary keys. Keys that aren't strings, values, and enum variant names are written
+    /// as usual. The `Deserializer` accepts `bin` wherever a string is expected, so such data still
+    /// decodes into `String` keys and struct fields.
+    ///
+    /// ```rust
+    /// use std::collections::BTreeMap;
+    /// use serde::ser::Serialize;
+    /// let mut msgpack_data = Vec::new();
+    /// let mut serializer = messpack_serde::Serializer::new(&mut msgpack_data)
+    ///     .with_bin_keys(true);
+    /// BTreeMap::from([("a", 1)]).serialize(&mut serializer).unwrap();
+    /// assert_eq!([0x81, 0xc4, 0x01, b'a', 0x01], msgpack_data[..]);
+    /// ```
+    #[inline]
+    pub const fn with_bin_keys(mut self, enabled: bool) -> Self {
+        self.config.bin_keys = enabled;
+        self
+    }
+
+    /// Chooses how non-negative signed integers are written, see [`Signedness`].
+    ///
+    /// The default, [`Signedness::PreferUnsigned`], writes `5i64` as a positive fixnum.
+    ///
+    /// ```rust
+    /// use serde::ser::Serialize;
+    /// use messpack_serde::config::Signedness;
+    /// let mut msgpack_data = Vec::new();
+    /// let mut serializer = messpack_serde::Serializer::new(&mut msgpack_data)
+    ///     .with_signed_preference(Signedness::PreserveSigned);
+    /// 5i64.serialize(&mut serializer).unwrap();
+    /// assert_eq!([0xd0, 0x05], msgpack_data[..]);
+    /// ```
+    #[inline]
+    pub const fn with_signed_preference(mut self, signedness: Signedness) -> Self {
+        self.config.signedness = signedness;
+        self
+    }
 }
 
 impl<W: Write, C> UnderlyingWrite for Serializer<W, C> {
@@ -381,6 +485,11 @@ pub struct Compound<'a, W, C> {
     se: &'a mut Serializer<W, C>,
 }
 
+/// Writes the bytes of a [`RawValue`](crate::RawValue) straight to the output.
+struct RawValueSerializer<'a, W> {
+    wr: &'a mut W,
+}
+
 #[derive(Debug)]
 #[allow(missing_docs)]
 pub struct ExtFieldSerializer<'a, W> {
@@ -452,7 +561,7 @@ impl<'a, W: Write + 'a, C: SerializerConfig> SerializeStruct for Compound<'a, W,
         value: &T,
     ) -> Result<(), Self::Error> {
         if self.se.config.is_named {
-            encode::write_str(self.se.get_mut(), key)?;
+            self.se.write_field_name(key)?;
         }
         value.serialize(&mut *self.se)
     }
@@ -488,7 +597,7 @@ impl<'a, W: Write + 'a, C: SerializerConfig> SerializeStructVariant for Compound
         value: &T,
     ) -> Result<(), Self::Error> {
         if self.se.config.is_named {
-            encode::write_str(self.se.get_mut(), key)?;
+            self.se.write_field_name(key)?;
             value.serialize(&mut *self.se)
         } else {
             value.serialize(&mut *self.se)
@@ -577,7 +686,17 @@ impl<'a, W: Write + 'a, C: SerializerConfig> SerializeMap for MaybeUnknownLength
     type Error = Error;
 
     fn serialize_key<T: ?Sized + Serialize>(&mut self, key: &T) -> Result<(), Self::Error> {
-        <Self as SerializeSeq>::serialize_element(self, key)
+        if !self.se.config.bin_keys {
+            return <Self as SerializeSeq>::serialize_element(self, key);
+        }
+        match self.compound.as_mut() {
+            None => self.se.serialize_bin_key(key),
+            Some(buf) => {
+                buf.se.serialize_bin_key(key)?;
+                buf.elem_count += 1;
+                Ok(())
+            }
+        }
     }
 
     fn serialize_value<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Self::Error> {
@@ -635,7 +754,22 @@ where
     }
 
     fn serialize_i64(self, v: i64) -> Result<Self::Ok, Self::Error> {
-        encode::write_sint(&mut self.wr, v)?;
+        match self.config.signedness {
+            Signedness::PreserveSigned if v >= 0 => {
+                if let Ok(v) = i8::try_from(v) {
+                    encode::write_i8(&mut self.wr, v)?;
+                } else if let Ok(v) = i16::try_from(v) {
+                    encode::write_i16(&mut self.wr, v)?;
+                } else if let Ok(v) = i32::try_from(v) {
+                    encode::write_i32(&mut self.wr, v)?;
+                } else {
+                    encode::write_i64(&mut self.wr, v)?;
+                }
+            }
+            _ => {
+                encode::write_sint(&mut self.wr, v)?;
+            }
+        }
         Ok(())
     }
 
@@ -665,11 +799,17 @@ where
     }
 
     fn serialize_f32(self, v: f32) -> Result<Self::Ok, Self::Error> {
+        if self.config.reject_non_finite && !v.is_finite() {
+            return Err(Error::NonFiniteFloat);
+        }
         encode::write_f32(&mut self.wr, v)?;
         Ok(())
     }
 
     fn serialize_f64(self, v: f64) -> Result<Self::Ok, Self::Error> {
+        if self.config.reject_non_finite && !v.is_finite() {
+            return Err(Error::NonFiniteFloat);
+        }
         encode::write_f64(&mut self.wr, v)?;
         Ok(())
     }
@@ -727,6 +867,9 @@ where
 
             return ext_se.end();
         }
+        if name == MSGPACK_RAW_VALUE_NAME {
+            return value.serialize(RawValueSerializer { wr: &mut self.wr });
+        }
 
         // Encode as if it's inner type.
         value.serialize(self)
@@ -833,17 +976,8 @@ where
             _ => None,
         };
 
-        const MAX_ITER_SIZE: usize = std::mem::size_of::<<&[u8] as IntoIterator>::IntoIter>();
-        const ITEM_PTR_SIZE: usize = std::mem::size_of::<&u8>();
-
-        // Estimate whether the input is `&[u8]` or similar (hacky, because Rust lacks proper specialization)
-        let might_be_a_bytes_iter = (std::mem::size_of::<I::Item>() == 1 || std::mem::size_of::<I::Item>() == ITEM_PTR_SIZE)
-            // Complex types like HashSet<u8> don't support reading bytes.
-            // The simplest iterator is ptr+len.
-            && std::mem::size_of::<I::IntoIter>() <= MAX_ITER_SIZE;
-
         let mut iter = iter.peekable();
-        if might_be_a_bytes_iter && self.config.bytes != BytesMode::Normal {
+        if self.might_be_bytes_iter::<I::IntoIter>() {
             if let Some(len) = len {
                 // The `OnlyBytes` serializer emits `Err` for everything except `u8`
                 if iter
@@ -862,6 +996,60 @@ where
 }
 
 impl<W: Write, C: SerializerConfig> Serializer<W, C> {
+    /// Serializes the items of an iterator as a MessagePack array.
+    ///
+    /// The array header is written from `iter.len()`, then each item is serialized as it's
+    /// produced, so the sequence never has to be collected into a `Vec` first. Iterators of bytes
+    /// are written as bin under the same [`BytesMode`] rules as any other sequence.
+    ///
+    /// # Errors
+    ///
+    /// Fails with `Error::InvalidValueWrite` before writing anything if the iterator is longer
+    /// than `u32::MAX` items, and with `Error::InvalidDataModel` if it yields a different number
+    /// of items than `iter.len()` promised, in which case the output is corrupt.
+    pub fn serialize_iter<I, T>(&mut self, iter: I) -> Result<(), Error>
+    where
+        I: ExactSizeIterator<Item = T>,
+        T: Serialize,
+    {
+        let len = u32::try_from(iter.len()).map_err(|_| {
+            ValueWriteError::InvalidDataWrite(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "iterator longer than u32::MAX items",
+            ))
+        })?;
+
+        let mut iter = iter.peekable();
+        if self.might_be_bytes_iter::<I>()
+            && iter
+                .peek()
+                .is_some_and(|item| item.serialize(OnlyBytes).is_ok())
+        {
+            return self.bytes_from_iter(iter, len);
+        }
+
+        encode::write_array_len(&mut self.wr, len)?;
+        let mut count = 0;
+        for item in iter {
+            item.serialize(&mut *self)?;
+            count += 1;
+        }
+        check_iter_len(len, count)
+    }
+
+    /// Estimates whether `I` iterates over `u8`s, such as `&[u8]`, which the bytes modes write as
+    /// bin. This is hacky, because Rust lacks proper specialization.
+    fn might_be_bytes_iter<I: Iterator>(&self) -> bool {
+        const MAX_ITER_SIZE: usize = std::mem::size_of::<<&[u8] as IntoIterator>::IntoIter>();
+        const ITEM_PTR_SIZE: usize = std::mem::size_of::<&u8>();
+
+        self.config.bytes != BytesMode::Normal
+            && (std::mem::size_of::<I::Item>() == 1 || std::mem::size_of::<I::Item>() == ITEM_PTR_SIZE)
+            // Complex types like HashSet<u8> don't support reading bytes.
+            // The simplest iterator is ptr+len.
+            && std::mem::size_of::<I>() <= MAX_ITER_SIZE
+    }
+
     fn bytes_from_iter<I>(
         &mut self,
         mut iter: I,
@@ -872,6 +1060,7 @@ impl<W: Write, C: SerializerConfig> Serializer<W, C> {
         I::Item: Serialize,
     {
         encode::write_bin_len(&mut self.wr, len)?;
+        let mut count = 0;
         iter.try_for_each(|item| {
             self.wr
                 .write(std::slice::from_ref(
@@ -880,8 +1069,184 @@ impl<W: Write, C: SerializerConfig> Serializer<W, C> {
                         .map_err(|_| Error::InvalidDataModel("BytesMode"))?,
                 ))
                 .map_err(ValueWriteError::InvalidDataWrite)?;
-            Ok(())
-        })
+            count += 1;
+            Ok::<_, Error>(())
+        })?;
+        check_iter_len(len, count)
+    }
+}
+
+/// Fails if an iterator yielded a different number of items than the header written for it.
+fn check_iter_len(len: u32, count: usize) -> Result<(), Error> {
+    if count == len as usize {
+        Ok(())
+    } else {
+        Err(Error::InvalidDataModel(
+            "iterator yielded a different number of items than its length",
+        ))
+    }
+}
+
+impl<W: Write> serde::Serializer for RawValueSerializer<'_, W> {
+    type Ok = ();
+    type Error = Error;
+
+    type SerializeSeq = serde::ser::Impossible<(), Error>;
+    type SerializeTuple = serde::ser::Impossible<(), Error>;
+    type SerializeTupleStruct = serde::ser::Impossible<(), Error>;
+    type SerializeTupleVariant = serde::ser::Impossible<(), Error>;
+    type SerializeMap = serde::ser::Impossible<(), Error>;
+    type SerializeStruct = serde::ser::Impossible<(), Error>;
+    type SerializeStructVariant = serde::ser::Impossible<(), Error>;
+
+    #[inline]
+    fn serialize_bytes(self, val: &[u8]) -> Result<Self::Ok, Self::Error> {
+        self.wr
+            .write_all(val)
+            .map_err(|err| Error::InvalidValueWrite(ValueWriteError::InvalidDataWrite(err)))
+    }
+
+    fn serialize_bool(self, _val: bool) -> Result<Self::Ok, Self::Error> {
+        Err(Error::InvalidDataModel("expected bytes"))
+    }
+
+    fn serialize_i8(self, _val: i8) -> Result<Self::Ok, Self::Error> {
+        Err(Error::InvalidDataModel("expected bytes"))
+    }
+
+    fn serialize_i16(self, _val: i16) -> Result<Self::Ok, Self::Error> {
+        Err(Error::InvalidDataModel("expected bytes"))
+    }
+
+    fn serialize_i32(self, _val: i32) -> Result<Self::Ok, Self::Error> {
+        Err(Error::InvalidDataModel("expected bytes"))
+    }
+
+    fn serialize_i64(self, _val: i64) -> Result<Self::Ok, Self::Error> {
+        Err(Error::InvalidDataModel("expected bytes"))
+    }
+
+    fn serialize_u8(self, _val: u8) -> Result<Self::Ok, Self::Error> {
+        Err(Error::InvalidDataModel("expected bytes"))
+    }
+
+    fn serialize_u16(self, _val: u16) -> Result<Self::Ok, Self::Error> {
+        Err(Error::InvalidDataModel("expected bytes"))
+    }
+
+    fn serialize_u32(self, _val: u32) -> Result<Self::Ok, Self::Error> {
+        Err(Error::InvalidDataModel("expected bytes"))
+    }
+
+    fn serialize_u64(self, _val: u64) -> Result<Self::Ok, Self::Error> {
+        Err(Error::InvalidDataModel("expected bytes"))
+    }
+
+    fn serialize_f32(self, _val: f32) -> Result<Self::Ok, Self::Error> {
+        Err(Error::InvalidDataModel("expected bytes"))
+    }
+
+    fn serialize_f64(self, _val: f64) -> Result<Self::Ok, Self::Error> {
+        Err(Error::InvalidDataModel("expected bytes"))
+    }
+
+    fn serialize_char(self, _val: char) -> Result<Self::Ok, Self::Error> {
+        Err(Error::InvalidDataModel("expected bytes"))
+    }
+
+    fn serialize_str(self, _val: &str) -> Result<Self::Ok, Self::Error> {
+        Err(Error::InvalidDataModel("expected bytes"))
+    }
+
+    fn serialize_none(self) -> Result<Self::Ok, Self::Error> {
+        Err(Error::InvalidDataModel("expected bytes"))
+    }
+
+    fn serialize_some<T: ?Sized + Serialize>(self, _value: &T) -> Result<Self::Ok, Self::Error> {
+        Err(Error::InvalidDataModel("expected bytes"))
+    }
+
+    fn serialize_unit(self) -> Result<Self::Ok, Self::Error> {
+        Err(Error::InvalidDataModel("expected bytes"))
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Self::Ok, Self::Error> {
+        Err(Error::InvalidDataModel("expected bytes"))
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _idx: u32,
+        _variant: &'static str,
+    ) -> Result<Self::Ok, Self::Error> {
+        Err(Error::InvalidDataModel("expected bytes"))
+    }
+
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        _value: &T,
+    ) -> Result<Self::Ok, Self::Error> {
+        Err(Error::InvalidDataModel("expected bytes"))
+    }
+
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        _idx: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<Self::Ok, Self::Error> {
+        Err(Error::InvalidDataModel("expected bytes"))
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, Error> {
+        Err(Error::InvalidDataModel("expected bytes"))
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, Error> {
+        Err(Error::InvalidDataModel("expected bytes"))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct, Error> {
+        Err(Error::InvalidDataModel("expected bytes"))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _idx: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant, Error> {
+        Err(Error::InvalidDataModel("expected bytes"))
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, Error> {
+        Err(Error::InvalidDataModel("expected bytes"))
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStruct, Error> {
+        Err(Error::InvalidDataModel("expected bytes"))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _idx: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant, Error> {
+        Err(Error::InvalidDataModel("expected bytes"))
     }
 }
 
@@ -1390,6 +1755,199 @@ where
     Ok(wr.0)
 }
 
+/// Serialize the given data structure as a MessagePack byte vector, allocating it only once.
+/// This method uses compact representation, structs are serialized as arrays
+///
+/// The value is serialized twice: first into a sink that only counts bytes, then into a vector
+/// with exactly that capacity. This trades CPU time for avoiding reallocations, which pays off
+/// for large values. `T`'s implementation of `Serialize` is expected to produce the same output
+/// both times; if it doesn't, the result is still correct but may reallocate.
+///
+/// # Errors
+///
+/// Serialization can fail if `T`'s implementation of `Serialize` decides to fail.
+pub fn to_vec_exact<T>(val: &T) -> Result<Vec<u8>, Error>
+where
+    T: Serialize + ?Sized,
+{
+    let len = serialized_size(val)? as usize;
+
+    let mut wr = FallibleWriter(Vec::new());
+    wr.0.try_reserve_exact(len)
+        .map_err(|_| ValueWriteError::InvalidDataWrite(std::io::ErrorKind::OutOfMemory.into()))?;
+    write(&mut wr, val)?;
+    Ok(wr.0)
+}
+
+/// Converts the given data structure into a [`Value`] tree.
+///
+/// The tree is built in memory by a [`ValueSerializer`]. The result is the same as encoding with
+/// [`to_vec`] and decoding that as a `Value`, so structs become arrays, unit variants become
+/// strings, and so on.
+///
+/// # Errors
+///
+/// Serialization can fail if `T`'s implementation of `Serialize` decides to fail.
+///
+/// # Examples
+///
+/// ```
+/// use messpack_serde::decode::value::Value;
+///
+/// let val = messpack_serde::to_value(&(1, "a")).unwrap();
+///
+/// assert_eq!(Value::Array(vec![Value::from(1), Value::from("a")]), val);
+/// ```
+pub fn to_value<T>(val: &T) -> Result<Value, Error>
+where
+    T: Serialize + ?Sized,
+{
+    val.serialize(ValueSerializer::new())
+}
+
+/// Writes a value followed by a big-endian CRC-32 of its encoded bytes.
+///
+/// The output can be read back with
+/// [`decode::value::read_value_checked`](crate::decode::value::read_value_checked).
+///
+/// # Errors
+///
+/// This function returns an error only if the underlying writer fails.
+pub fn write_value_checked<W: Write + ?Sized>(wr: &mut W, val: &Value) -> Result<(), Error> {
+    let mut crc = Crc32::new(&mut *wr);
+    rmpv::encode::write_value(&mut crc, val)?;
+    let sum = crc.sum();
+    wr.write_all(&sum.to_be_bytes())
+        .map_err(ValueWriteError::InvalidDataWrite)?;
+    Ok(())
+}
+
+/// Computes the length of the MessagePack encoding of the given data structure, without
+/// allocating a buffer for it.
+/// This method uses compact representation, structs are serialized as arrays
+///
+/// # Errors
+///
+/// Serialization can fail if `T`'s implementation of `Serialize` decides to fail.
+pub fn serialized_size<T>(val: &T) -> Result<u64, Error>
+where
+    T: Serialize + ?Sized,
+{
+    let mut counter = ByteCounter(0);
+    write(&mut counter, val)?;
+    Ok(counter.0)
+}
+
+/// Writes each value as a frame: a big-endian `u32` byte length, followed by the value itself.
+///
+/// This suits stream transports such as TCP, where the reader needs to know where a message ends
+/// before decoding it. Values use the compact representation, and are read back with
+/// [`Deserializer::framed`](crate::Deserializer::framed).
+///
+/// The length is computed with [`serialized_size`] and the value is then serialized straight into
+/// the writer, so `T`'s implementation of `Serialize` must produce the same output both times.
+///
+/// # Examples
+///
+/// ```
+/// use messpack_serde::Serializer;
+///
+/// let mut framed = Serializer::framed(Vec::new());
+/// framed.write(&(1, "a")).unwrap();
+///
+/// assert_eq!(vec![0, 0, 0, 4, 0x92, 0x01, 0xa1, 0x61], framed.into_inner());
+/// ```
+#[derive(Debug)]
+pub struct FramedWriter<W> {
+    wr: W,
+}
+
+impl<W: Write> FramedWriter<W> {
+    /// Writes `val` as one frame.
+    ///
+    /// # Errors
+    ///
+    /// Fails if `val` fails to serialize, if its encoding is longer than `u32::MAX` bytes, or with
+    /// `Error::InvalidDataModel` if the second serialization produced a different length than the
+    /// first, in which case the stream is corrupt.
+    pub fn write<T: Serialize + ?Sized>(&mut self, val: &T) -> Result<(), Error> {
+        let len = serialized_size(val)?;
+        let len = u32::try_from(len).map_err(|_| {
+            ValueWriteError::InvalidDataWrite(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "frame longer than u32::MAX bytes",
+            ))
+        })?;
+        self.wr
+            .write_all(&len.to_be_bytes())
+            .map_err(ValueWriteError::InvalidDataWrite)?;
+
+        let mut counted = CountingWriter {
+            inner: &mut self.wr,
+            count: 0,
+        };
+        write(&mut counted, val)?;
+        if counted.count != u64::from(len) {
+            return Err(Error::InvalidDataModel(
+                "value serialized to a different length than its frame",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Gets a reference to the underlying writer.
+    #[inline(always)]
+    pub fn get_ref(&self) -> &W {
+        &self.wr
+    }
+
+    /// Gets a mutable reference to the underlying writer.
+    #[inline(always)]
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.wr
+    }
+
+    /// Unwraps this `FramedWriter`, returning the underlying writer.
+    #[inline]
+    pub fn into_inner(self) -> W {
+        self.wr
+    }
+}
+
+/// A writer that counts the bytes passed on to the inner one.
+struct CountingWriter<W> {
+    inner: W,
+    count: u64,
+}
+
+impl<W: Write> Write for CountingWriter<W> {
+    #[inline]
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.count += n as u64;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// A writer that discards its input, only counting how many bytes were written.
+struct ByteCounter(u64);
+
+impl Write for ByteCounter {
+    #[inline(always)]
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0 += buf.len() as u64;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
 #[repr(transparent)]
 struct FallibleWriter(Vec<u8>);
 
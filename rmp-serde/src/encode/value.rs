@@ -0,0 +1,422 @@
+//! Serializes Rust data structures into an in-memory [`Value`] tree.
+
+use serde::ser::{
+    self, SerializeMap, SerializeSeq, SerializeStruct, SerializeStructVariant, SerializeTuple,
+    SerializeTupleStruct, SerializeTupleVariant,
+};
+use serde::Serialize;
+
+use super::Error;
+use crate::decode::value::Value;
+use crate::{MSGPACK_EXT_STRUCT_NAME, MSGPACK_RAW_VALUE_NAME};
+
+/// A serializer that builds a [`Value`] instead of writing bytes.
+///
+/// It makes the same choices as the default [`Serializer`](super::Serializer): structs become
+/// arrays, enum variants are keyed by name, and 128-bit integers become binary. Call
+/// [`with_struct_map`](Self::with_struct_map) to get maps for structs instead.
+///
+/// # Examples
+///
+/// ```
+/// use messpack_serde::decode::value::Value;
+/// use messpack_serde::encode::ValueSerializer;
+/// use serde::Serialize;
+///
+/// let val = (1, "a").serialize(ValueSerializer::new()).unwrap();
+///
+/// assert_eq!(Value::Array(vec![Value::from(1), Value::from("a")]), val);
+/// ```
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ValueSerializer {
+    is_named: bool,
+}
+
+impl ValueSerializer {
+    /// Creates a serializer that writes structs as arrays of their fields.
+    #[inline]
+    #[must_use]
+    pub fn new() -> Self {
+        Self { is_named: false }
+    }
+
+    /// Writes structs as maps from field names to values.
+    #[inline]
+    #[must_use]
+    pub fn with_struct_map(self) -> Self {
+        Self { is_named: true }
+    }
+}
+
+#[doc(hidden)]
+#[derive(Debug)]
+pub struct SerializeVec {
+    se: ValueSerializer,
+    vals: Vec<Value>,
+}
+
+#[doc(hidden)]
+#[derive(Debug)]
+pub struct SerializeTupleVariantValue {
+    variant: &'static str,
+    inner: SerializeVec,
+}
+
+#[doc(hidden)]
+#[derive(Debug)]
+pub struct SerializeMapValue {
+    se: ValueSerializer,
+    entries: Vec<(Value, Value)>,
+    key: Option<Value>,
+}
+
+#[doc(hidden)]
+#[derive(Debug)]
+pub struct SerializeStructValue {
+    se: ValueSerializer,
+    fields: Vec<(&'static str, Value)>,
+}
+
+#[doc(hidden)]
+#[derive(Debug)]
+pub struct SerializeStructVariantValue {
+    variant: &'static str,
+    inner: SerializeStructValue,
+}
+
+/// Wraps `val` in the single-entry map used for enum variants carrying data.
+fn variant_map(variant: &'static str, val: Value) -> Value {
+    Value::Map(vec![(Value::from(variant), val)])
+}
+
+impl ser::Serializer for ValueSerializer {
+    type Ok = Value;
+    type Error = Error;
+
+    type SerializeSeq = SerializeVec;
+    type SerializeTuple = SerializeVec;
+    type SerializeTupleStruct = SerializeVec;
+    type SerializeTupleVariant = SerializeTupleVariantValue;
+    type SerializeMap = SerializeMapValue;
+    type SerializeStruct = SerializeStructValue;
+    type SerializeStructVariant = SerializeStructVariantValue;
+
+    #[inline]
+    fn is_human_readable(&self) -> bool {
+        false
+    }
+
+    fn serialize_bool(self, v: bool) -> Result<Value, Error> {
+        Ok(Value::Boolean(v))
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<Value, Error> {
+        self.serialize_i64(i64::from(v))
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<Value, Error> {
+        self.serialize_i64(i64::from(v))
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<Value, Error> {
+        self.serialize_i64(i64::from(v))
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<Value, Error> {
+        Ok(Value::from(v))
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<Value, Error> {
+        self.serialize_bytes(&v.to_be_bytes())
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<Value, Error> {
+        self.serialize_u64(u64::from(v))
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<Value, Error> {
+        self.serialize_u64(u64::from(v))
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<Value, Error> {
+        self.serialize_u64(u64::from(v))
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<Value, Error> {
+        Ok(Value::from(v))
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<Value, Error> {
+        self.serialize_bytes(&v.to_be_bytes())
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<Value, Error> {
+        Ok(Value::F32(v))
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<Value, Error> {
+        Ok(Value::F64(v))
+    }
+
+    fn serialize_char(self, v: char) -> Result<Value, Error> {
+        Ok(Value::from(v.to_string()))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Value, Error> {
+        Ok(Value::from(v))
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<Value, Error> {
+        Ok(Value::Binary(v.to_vec()))
+    }
+
+    fn serialize_none(self) -> Result<Value, Error> {
+        self.serialize_unit()
+    }
+
+    fn serialize_some<T: ?Sized + Serialize>(self, value: &T) -> Result<Value, Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Value, Error> {
+        Ok(Value::Nil)
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Value, Error> {
+        Ok(Value::Array(Vec::new()))
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _idx: u32,
+        variant: &'static str,
+    ) -> Result<Value, Error> {
+        self.serialize_str(variant)
+    }
+
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(
+        self,
+        name: &'static str,
+        value: &T,
+    ) -> Result<Value, Error> {
+        if name == MSGPACK_EXT_STRUCT_NAME {
+            return match value.serialize(self)? {
+                Value::Array(fields) => match <[Value; 2]>::try_from(fields) {
+                    Ok([Value::Integer(tag), Value::Binary(data)]) => {
+                        let tag = tag
+                            .as_i64()
+                            .and_then(|tag| i8::try_from(tag).ok())
+                            .ok_or(Error::InvalidDataModel("expected i8 and bytes"))?;
+                        Ok(Value::Ext(tag, data))
+                    }
+                    _ => Err(Error::InvalidDataModel("expected i8 and bytes")),
+                },
+                _ => Err(Error::InvalidDataModel("expected tuple")),
+            };
+        }
+        if name == MSGPACK_RAW_VALUE_NAME {
+            return match value.serialize(self)? {
+                Value::Binary(buf) => crate::decode::value::read_value(&mut &buf[..])
+                    .map_err(|_| Error::InvalidDataModel("malformed raw value")),
+                _ => Err(Error::InvalidDataModel("expected bytes")),
+            };
+        }
+
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        _idx: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<Value, Error> {
+        Ok(variant_map(variant, value.serialize(self)?))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq, Error> {
+        Ok(SerializeVec {
+            se: self,
+            vals: Vec::with_capacity(len.unwrap_or(0)),
+        })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple, Error> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleStruct, Error> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _idx: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleVariant, Error> {
+        Ok(SerializeTupleVariantValue {
+            variant,
+            inner: self.serialize_seq(Some(len))?,
+        })
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> Result<Self::SerializeMap, Error> {
+        Ok(SerializeMapValue {
+            se: self,
+            entries: Vec::with_capacity(len.unwrap_or(0)),
+            key: None,
+        })
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeStruct, Error> {
+        Ok(SerializeStructValue {
+            se: self,
+            fields: Vec::with_capacity(len),
+        })
+    }
+
+    fn serialize_struct_variant(
+        self,
+        name: &'static str,
+        _idx: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeStructVariant, Error> {
+        Ok(SerializeStructVariantValue {
+            variant,
+            inner: self.serialize_struct(name, len)?,
+        })
+    }
+}
+
+impl SerializeSeq for SerializeVec {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Error> {
+        self.vals.push(value.serialize(self.se)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<Value, Error> {
+        Ok(Value::Array(self.vals))
+    }
+}
+
+impl SerializeTuple for SerializeVec {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Error> {
+        SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<Value, Error> {
+        SerializeSeq::end(self)
+    }
+}
+
+impl SerializeTupleStruct for SerializeVec {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Error> {
+        SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<Value, Error> {
+        SerializeSeq::end(self)
+    }
+}
+
+impl SerializeTupleVariant for SerializeTupleVariantValue {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Error> {
+        SerializeSeq::serialize_element(&mut self.inner, value)
+    }
+
+    fn end(self) -> Result<Value, Error> {
+        Ok(variant_map(self.variant, SerializeSeq::end(self.inner)?))
+    }
+}
+
+impl SerializeMap for SerializeMapValue {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_key<T: ?Sized + Serialize>(&mut self, key: &T) -> Result<(), Error> {
+        self.key = Some(key.serialize(self.se)?);
+        Ok(())
+    }
+
+    fn serialize_value<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Error> {
+        let key = self
+            .key
+            .take()
+            .ok_or(Error::InvalidDataModel("map value without a key"))?;
+        self.entries.push((key, value.serialize(self.se)?));
+        Ok(())
+    }
+
+    fn end(self) -> Result<Value, Error> {
+        Ok(Value::Map(self.entries))
+    }
+}
+
+impl SerializeStruct for SerializeStructValue {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        self.fields.push((key, value.serialize(self.se)?));
+        Ok(())
+    }
+
+    fn end(self) -> Result<Value, Error> {
+        Ok(if self.se.is_named {
+            Value::Map(
+                self.fields
+                    .into_iter()
+                    .map(|(key, val)| (Value::from(key), val))
+                    .collect(),
+            )
+        } else {
+            Value::Array(self.fields.into_iter().map(|(_, val)| val).collect())
+        })
+    }
+}
+
+impl SerializeStructVariant for SerializeStructVariantValue {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        SerializeStruct::serialize_field(&mut self.inner, key, value)
+    }
+
+    fn end(self) -> Result<Value, Error> {
+        Ok(variant_map(self.variant, SerializeStruct::end(self.inner)?))
+    }
+}
@@ -0,0 +1,157 @@
+//! Compact encodings of IP addresses, for use with `#[serde(with = "...")]`.
+//!
+//! By default serde writes an `Ipv4Addr` as an array of four integers and an `Ipv6Addr` as an
+//! array of sixteen, which costs up to twice the size of the address itself. With these helpers
+//! an `Ipv4Addr` becomes a 4-byte bin and an `Ipv6Addr` a 16-byte bin. An `IpAddr` becomes a
+//! fixext whose type id tells the two families apart, [`V4_EXT_TYPE`] or [`V6_EXT_TYPE`].
+//!
+//! Human-readable serializers and deserializers use serde's default string form instead.
+//!
+//! # Examples
+//!
+//! ```
+//! use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+//!
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Debug, PartialEq, Serialize, Deserialize)]
+//! struct Peer {
+//!     #[serde(with = "messpack_serde::ip")]
+//!     addr: IpAddr,
+//!     #[serde(with = "messpack_serde::ip::v4")]
+//!     gateway: Ipv4Addr,
+//!     #[serde(with = "messpack_serde::ip::v6")]
+//!     link_local: Ipv6Addr,
+//! }
+//!
+//! let peer = Peer {
+//!     addr: IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2)),
+//!     gateway: Ipv4Addr::new(10, 0, 0, 1),
+//!     link_local: Ipv6Addr::new(0xfe80, 0, 0, 0, 0, 0, 0, 1),
+//! };
+//! let buf = messpack_serde::to_vec(&peer).unwrap();
+//!
+//! // Array header, then a fixext 4, a bin 8 of 4 bytes and a bin 8 of 16 bytes.
+//! assert_eq!(1 + 6 + 6 + 18, buf.len());
+//! assert_eq!(peer, messpack_serde::from_slice(&buf).unwrap());
+//! ```
+
+use std::fmt::{self, Formatter};
+use std::net::IpAddr;
+
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{ByteBuf, Bytes, MSGPACK_EXT_STRUCT_NAME};
+
+/// The extension type id of an [`IpAddr`] holding an IPv4 address.
+pub const V4_EXT_TYPE: i8 = 4;
+/// The extension type id of an [`IpAddr`] holding an IPv6 address.
+pub const V6_EXT_TYPE: i8 = 6;
+
+/// Serializes an [`IpAddr`] as a fixext 4 or fixext 16 of the address bytes.
+pub fn serialize<S: Serializer>(addr: &IpAddr, se: S) -> Result<S::Ok, S::Error> {
+    if se.is_human_readable() {
+        return addr.serialize(se);
+    }
+    match addr {
+        IpAddr::V4(addr) => se.serialize_newtype_struct(
+            MSGPACK_EXT_STRUCT_NAME,
+            &(V4_EXT_TYPE, Bytes(&addr.octets())),
+        ),
+        IpAddr::V6(addr) => se.serialize_newtype_struct(
+            MSGPACK_EXT_STRUCT_NAME,
+            &(V6_EXT_TYPE, Bytes(&addr.octets())),
+        ),
+    }
+}
+
+/// Deserializes an [`IpAddr`] written by [`serialize`].
+pub fn deserialize<'de, D: Deserializer<'de>>(de: D) -> Result<IpAddr, D::Error> {
+    if de.is_human_readable() {
+        return IpAddr::deserialize(de);
+    }
+    de.deserialize_newtype_struct(MSGPACK_EXT_STRUCT_NAME, IpAddrVisitor)
+}
+
+struct IpAddrVisitor;
+
+impl<'de> de::Visitor<'de> for IpAddrVisitor {
+    type Value = IpAddr;
+
+    #[cold]
+    fn expecting(&self, fmt: &mut Formatter<'_>) -> Result<(), fmt::Error> {
+        fmt.write_str("an IP address ext")
+    }
+
+    fn visit_newtype_struct<D>(self, de: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let (tag, data): (i8, ByteBuf) = Deserialize::deserialize(de)?;
+        match tag {
+            V4_EXT_TYPE => octets(&data).map(|octets: [u8; 4]| IpAddr::from(octets)),
+            V6_EXT_TYPE => octets(&data).map(|octets: [u8; 16]| IpAddr::from(octets)),
+            tag => Err(de::Error::invalid_value(
+                de::Unexpected::Signed(tag.into()),
+                &"ext type 4 or 6",
+            )),
+        }
+    }
+}
+
+fn octets<E: de::Error, const N: usize>(data: &[u8]) -> Result<[u8; N], E> {
+    data.try_into()
+        .map_err(|_| de::Error::invalid_length(data.len(), &"4 bytes for IPv4 or 16 for IPv6"))
+}
+
+/// Compact encoding of an [`Ipv4Addr`], as a 4-byte bin.
+pub mod v4 {
+    use std::net::Ipv4Addr;
+
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    use crate::ByteBuf;
+
+    /// Serializes an [`Ipv4Addr`] as a bin of its 4 bytes.
+    pub fn serialize<S: Serializer>(addr: &Ipv4Addr, se: S) -> Result<S::Ok, S::Error> {
+        if se.is_human_readable() {
+            return addr.serialize(se);
+        }
+        se.serialize_bytes(&addr.octets())
+    }
+
+    /// Deserializes an [`Ipv4Addr`] written by [`serialize`].
+    pub fn deserialize<'de, D: Deserializer<'de>>(de: D) -> Result<Ipv4Addr, D::Error> {
+        if de.is_human_readable() {
+            return Ipv4Addr::deserialize(de);
+        }
+        let data = ByteBuf::deserialize(de)?;
+        super::octets(&data).map(|octets: [u8; 4]| Ipv4Addr::from(octets))
+    }
+}
+
+/// Compact encoding of an [`Ipv6Addr`], as a 16-byte bin.
+pub mod v6 {
+    use std::net::Ipv6Addr;
+
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    use crate::ByteBuf;
+
+    /// Serializes an [`Ipv6Addr`] as a bin of its 16 bytes.
+    pub fn serialize<S: Serializer>(addr: &Ipv6Addr, se: S) -> Result<S::Ok, S::Error> {
+        if se.is_human_readable() {
+            return addr.serialize(se);
+        }
+        se.serialize_bytes(&addr.octets())
+    }
+
+    /// Deserializes an [`Ipv6Addr`] written by [`serialize`].
+    pub fn deserialize<'de, D: Deserializer<'de>>(de: D) -> Result<Ipv6Addr, D::Error> {
+        if de.is_human_readable() {
+            return Ipv6Addr::deserialize(de);
+        }
+        let data = ByteBuf::deserialize(de)?;
+        super::octets(&data).map(|octets: [u8; 16]| Ipv6Addr::from(octets))
+    }
+}
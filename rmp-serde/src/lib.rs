@@ -2,22 +2,30 @@
 #![warn(missing_debug_implementations)]
 
 use std::fmt::{self, Display, Formatter};
+use std::ops::Deref;
 use std::str::{self, Utf8Error};
+use std::time::{SystemTime, UNIX_EPOCH};
 
 use serde::de;
 use serde::{Deserialize, Serialize};
 
 #[allow(deprecated)]
 pub use crate::decode::from_read_ref;
-pub use crate::decode::{from_read, Deserializer};
-pub use crate::encode::{to_vec, to_vec_named, Serializer};
+pub use crate::decode::{from_read, from_value, Deserializer};
+pub use crate::encode::{to_value, to_vec, to_vec_exact, to_vec_named, Serializer};
 
 pub use crate::decode::from_slice;
 
 mod bytes;
 pub mod config;
+mod crc32;
 pub mod decode;
 pub mod encode;
+pub mod ip;
+pub mod marker;
+pub mod rpc;
+#[cfg(feature = "test-support")]
+pub mod test_support;
 
 /// Hack used to serialize MessagePack Extension types.
 ///
@@ -40,6 +48,9 @@ pub mod encode;
 /// ```
 pub const MSGPACK_EXT_STRUCT_NAME: &str = "_ExtStruct";
 
+/// Name of the serde newtype struct through which [`RawValue`] reaches the serializer.
+pub(crate) const MSGPACK_RAW_VALUE_NAME: &str = "_RawValue";
+
 /// Helper that allows both to encode and decode strings no matter whether they contain valid or
 /// invalid UTF-8.
 ///
@@ -334,3 +345,408 @@ impl<'de> Deserialize<'de> for RawRef<'de> {
         de.deserialize_any(RawRefVisitor)
     }
 }
+
+/// Owned byte buffer that is always encoded as MessagePack `bin`.
+///
+/// Without specialization serde encodes `Vec<u8>` as an array of integers. Wrapping the buffer in
+/// a `ByteBuf` makes it use the compact binary representation instead, without having to depend
+/// on `serde_bytes`. Decoding accepts `bin`, `str` and arrays of integers.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ByteBuf(pub Vec<u8>);
+
+impl ByteBuf {
+    /// Consumes this object, yielding the underlying byte vector.
+    #[inline]
+    #[must_use]
+    pub fn into_vec(self) -> Vec<u8> {
+        self.0
+    }
+}
+
+impl From<Vec<u8>> for ByteBuf {
+    #[inline]
+    fn from(v: Vec<u8>) -> Self {
+        Self(v)
+    }
+}
+
+impl Deref for ByteBuf {
+    type Target = [u8];
+
+    #[inline]
+    fn deref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl AsRef<[u8]> for ByteBuf {
+    #[inline]
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl Serialize for ByteBuf {
+    #[inline]
+    fn serialize<S>(&self, se: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        se.serialize_bytes(&self.0)
+    }
+}
+
+struct ByteBufVisitor;
+
+impl<'de> de::Visitor<'de> for ByteBufVisitor {
+    type Value = ByteBuf;
+
+    #[cold]
+    fn expecting(&self, fmt: &mut Formatter<'_>) -> Result<(), fmt::Error> {
+        "bytes".fmt(fmt)
+    }
+
+    #[inline]
+    fn visit_bytes<E>(self, v: &[u8]) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(ByteBuf(v.into()))
+    }
+
+    #[inline]
+    fn visit_byte_buf<E>(self, v: Vec<u8>) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(ByteBuf(v))
+    }
+
+    #[inline]
+    fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(ByteBuf(v.as_bytes().into()))
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: de::SeqAccess<'de>,
+    {
+        let mut buf = Vec::with_capacity(seq.size_hint().unwrap_or(0).min(4096));
+        while let Some(byte) = seq.next_element()? {
+            buf.push(byte);
+        }
+        Ok(ByteBuf(buf))
+    }
+}
+
+impl<'de> Deserialize<'de> for ByteBuf {
+    #[inline]
+    fn deserialize<D>(de: D) -> Result<Self, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        de.deserialize_byte_buf(ByteBufVisitor)
+    }
+}
+
+/// Borrowed byte slice that is always encoded as MessagePack `bin`.
+///
+/// This is the borrowed counterpart of [`ByteBuf`]. Decoding only succeeds when the bytes can be
+/// borrowed from the input, e.g. when using [`from_slice`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Bytes<'a>(pub &'a [u8]);
+
+impl<'a> From<&'a [u8]> for Bytes<'a> {
+    #[inline]
+    fn from(v: &'a [u8]) -> Self {
+        Self(v)
+    }
+}
+
+impl Deref for Bytes<'_> {
+    type Target = [u8];
+
+    #[inline]
+    fn deref(&self) -> &[u8] {
+        self.0
+    }
+}
+
+impl AsRef<[u8]> for Bytes<'_> {
+    #[inline]
+    fn as_ref(&self) -> &[u8] {
+        self.0
+    }
+}
+
+impl Serialize for Bytes<'_> {
+    #[inline]
+    fn serialize<S>(&self, se: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        se.serialize_bytes(self.0)
+    }
+}
+
+struct BytesVisitor;
+
+impl<'de> de::Visitor<'de> for BytesVisitor {
+    type Value = Bytes<'de>;
+
+    #[cold]
+    fn expecting(&self, fmt: &mut Formatter<'_>) -> Result<(), fmt::Error> {
+        "borrowed bytes".fmt(fmt)
+    }
+
+    #[inline]
+    fn visit_borrowed_bytes<E>(self, v: &'de [u8]) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(Bytes(v))
+    }
+
+    #[inline]
+    fn visit_borrowed_str<E>(self, v: &'de str) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(Bytes(v.as_bytes()))
+    }
+}
+
+impl<'de: 'a, 'a> Deserialize<'de> for Bytes<'a> {
+    #[inline]
+    fn deserialize<D>(de: D) -> Result<Self, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        de.deserialize_bytes(BytesVisitor)
+    }
+}
+
+/// A pre-encoded MessagePack value, written out verbatim.
+///
+/// When serialized with [`Serializer`], the bytes are spliced into the output as they are rather
+/// than wrapped in a `bin`, so a value encoded once, or received from elsewhere, can be embedded
+/// in a larger message without decoding it. Other serializers see a plain byte string.
+///
+/// # Examples
+///
+/// ```
+/// use messpack_serde::RawValue;
+///
+/// let inner = RawValue::from_vec(vec![0x92, 0x01, 0x02]).unwrap();
+///
+/// assert_eq!(vec![0x91, 0x92, 0x01, 0x02], messpack_serde::to_vec(&[inner]).unwrap());
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct RawValue(Vec<u8>);
+
+impl RawValue {
+    /// Wraps `buf`, checking that it holds exactly one well-formed value.
+    ///
+    /// # Errors
+    ///
+    /// Returns the decoding error if `buf` doesn't start with a complete value, and
+    /// `decode::Error::Uncategorized` if anything follows it.
+    pub fn from_vec(buf: Vec<u8>) -> Result<Self, decode::Error> {
+        let mut rd = &buf[..];
+        decode::value::read_value(&mut rd)?;
+        if !rd.is_empty() {
+            return Err(decode::Error::Uncategorized(format!(
+                "{} trailing bytes after raw value",
+                rd.len()
+            )));
+        }
+        Ok(Self(buf))
+    }
+
+    /// Returns the encoded bytes.
+    #[inline]
+    #[must_use]
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    /// Consumes this object, yielding the encoded bytes.
+    #[inline]
+    #[must_use]
+    pub fn into_vec(self) -> Vec<u8> {
+        self.0
+    }
+}
+
+impl Serialize for RawValue {
+    #[inline]
+    fn serialize<S>(&self, se: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        se.serialize_newtype_struct(MSGPACK_RAW_VALUE_NAME, &Bytes(&self.0))
+    }
+}
+
+/// A point in time, encoded as the MessagePack timestamp extension, type id `-1`.
+///
+/// Serializing picks the smallest of the three forms in the spec: 32 bits for whole seconds
+/// between 1970 and 2106, 64 bits when the seconds fit in 34 bits, and 96 bits otherwise.
+/// Decoding accepts all three.
+///
+/// # Examples
+///
+/// ```
+/// use messpack_serde::Timestamp;
+///
+/// let ts = Timestamp::new(1, 0).unwrap();
+///
+/// assert_eq!(vec![0xd6, 0xff, 0x00, 0x00, 0x00, 0x01], messpack_serde::to_vec(&ts).unwrap());
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Timestamp {
+    secs: i64,
+    nanos: u32,
+}
+
+impl Timestamp {
+    /// The extension type id of timestamps.
+    pub const EXT_TYPE: i8 = -1;
+
+    /// Creates a timestamp `secs` seconds and `nanos` nanoseconds after the Unix epoch.
+    ///
+    /// Returns `None` if `nanos` is a second or more.
+    #[inline]
+    #[must_use]
+    pub fn new(secs: i64, nanos: u32) -> Option<Self> {
+        (nanos < 1_000_000_000).then_some(Self { secs, nanos })
+    }
+
+    /// Returns the whole seconds since the Unix epoch, negative for earlier times.
+    #[inline]
+    #[must_use]
+    pub fn secs(&self) -> i64 {
+        self.secs
+    }
+
+    /// Returns the nanoseconds past [`secs`](Self::secs).
+    #[inline]
+    #[must_use]
+    pub fn nanos(&self) -> u32 {
+        self.nanos
+    }
+
+    /// Encodes the ext data in the smallest form that holds this timestamp.
+    fn to_ext_data(self) -> ([u8; 12], usize) {
+        let mut buf = [0; 12];
+        match u64::try_from(self.secs) {
+            Ok(secs) if self.nanos == 0 && secs <= u64::from(u32::MAX) => {
+                buf[..4].copy_from_slice(&(secs as u32).to_be_bytes());
+                (buf, 4)
+            }
+            Ok(secs) if secs < 1 << 34 => {
+                let packed = u64::from(self.nanos) << 34 | secs;
+                buf[..8].copy_from_slice(&packed.to_be_bytes());
+                (buf, 8)
+            }
+            _ => {
+                buf[..4].copy_from_slice(&self.nanos.to_be_bytes());
+                buf[4..].copy_from_slice(&self.secs.to_be_bytes());
+                (buf, 12)
+            }
+        }
+    }
+
+    fn from_ext_data(data: &[u8]) -> Option<Self> {
+        match data.len() {
+            4 => Self::new(u32::from_be_bytes(data.try_into().ok()?).into(), 0),
+            8 => {
+                let packed = u64::from_be_bytes(data.try_into().ok()?);
+                Self::new((packed & ((1 << 34) - 1)) as i64, (packed >> 34) as u32)
+            }
+            12 => {
+                let (nanos, secs) = data.split_at(4);
+                Self::new(
+                    i64::from_be_bytes(secs.try_into().ok()?),
+                    u32::from_be_bytes(nanos.try_into().ok()?),
+                )
+            }
+            _ => None,
+        }
+    }
+}
+
+impl From<SystemTime> for Timestamp {
+    fn from(time: SystemTime) -> Self {
+        match time.duration_since(UNIX_EPOCH) {
+            Ok(since) => Self {
+                secs: since.as_secs() as i64,
+                nanos: since.subsec_nanos(),
+            },
+            Err(err) => {
+                // Round down to the second before, so that the nanoseconds count forward.
+                let before = err.duration();
+                let secs = -(before.as_secs() as i64);
+                match before.subsec_nanos() {
+                    0 => Self { secs, nanos: 0 },
+                    nanos => Self {
+                        secs: secs - 1,
+                        nanos: 1_000_000_000 - nanos,
+                    },
+                }
+            }
+        }
+    }
+}
+
+impl Serialize for Timestamp {
+    fn serialize<S>(&self, se: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let (buf, len) = self.to_ext_data();
+        se.serialize_newtype_struct(
+            MSGPACK_EXT_STRUCT_NAME,
+            &(Self::EXT_TYPE, Bytes(&buf[..len])),
+        )
+    }
+}
+
+struct TimestampVisitor;
+
+impl<'de> de::Visitor<'de> for TimestampVisitor {
+    type Value = Timestamp;
+
+    #[cold]
+    fn expecting(&self, fmt: &mut Formatter<'_>) -> Result<(), fmt::Error> {
+        "a timestamp ext".fmt(fmt)
+    }
+
+    fn visit_newtype_struct<D>(self, de: D) -> Result<Self::Value, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        let (tag, data): (i8, ByteBuf) = Deserialize::deserialize(de)?;
+        if tag != Timestamp::EXT_TYPE {
+            return Err(de::Error::invalid_value(
+                de::Unexpected::Signed(tag.into()),
+                &"ext type -1",
+            ));
+        }
+        Timestamp::from_ext_data(&data).ok_or_else(|| {
+            de::Error::invalid_value(de::Unexpected::Bytes(&data), &"timestamp data")
+        })
+    }
+}
+
+impl<'de> Deserialize<'de> for Timestamp {
+    fn deserialize<D>(de: D) -> Result<Self, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        de.deserialize_newtype_struct(MSGPACK_EXT_STRUCT_NAME, TimestampVisitor)
+    }
+}
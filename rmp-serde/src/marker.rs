@@ -0,0 +1,175 @@
+//! Extra queries on MessagePack [`Marker`]s.
+//!
+//! Markers are read with `rmp::decode::read_marker`, which consumes the marker byte. The rest of
+//! the value can then be read with [`dispatch`](crate::decode::value::dispatch), or by hand after
+//! branching on the marker's type.
+//!
+//! # Examples
+//!
+//! ```
+//! use messpack_serde::decode::value::dispatch;
+//! use messpack_serde::marker::MarkerExt;
+//!
+//! // "id", then 7
+//! let buf = [0xa2, 0x69, 0x64, 0x07];
+//! let mut rd = &buf[..];
+//!
+//! let marker = rmp::decode::read_marker(&mut rd).unwrap();
+//! assert!(marker.is_str());
+//! let key = dispatch(marker, &mut rd).unwrap();
+//! assert_eq!(Some("id"), key.as_str());
+//!
+//! let marker = rmp::decode::read_marker(&mut rd).unwrap();
+//! assert!(marker.is_int());
+//! assert_eq!(Some(7), dispatch(marker, &mut rd).unwrap().as_u64());
+//! ```
+
+use rmp::Marker;
+
+/// Extension methods for [`rmp::Marker`].
+pub trait MarkerExt {
+    /// Returns the number of data bytes that follow this marker, if it's known from the marker
+    /// alone.
+    ///
+    /// For ext markers the count includes the type id byte. Markers whose payload length has to be
+    /// read from the stream (`Str8`, `Bin16`, `Ext32`, ...) and containers, whose size depends on
+    /// their elements, return `None`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use messpack_serde::marker::MarkerExt;
+    /// use rmp::Marker;
+    ///
+    /// assert_eq!(Some(4), Marker::U32.data_len());
+    /// assert_eq!(Some(5), Marker::FixExt4.data_len());
+    /// assert_eq!(None, Marker::Str8.data_len());
+    /// ```
+    fn data_len(&self) -> Option<usize>;
+
+    /// Returns `true` for `nil`.
+    fn is_nil(&self) -> bool;
+
+    /// Returns `true` for `true` and `false`.
+    fn is_bool(&self) -> bool;
+
+    /// Returns `true` for integers of any width and sign, including fixnums.
+    fn is_int(&self) -> bool;
+
+    /// Returns `true` for `float 32` and `float 64`.
+    fn is_float(&self) -> bool;
+
+    /// Returns `true` for strings of any length.
+    fn is_str(&self) -> bool;
+
+    /// Returns `true` for binaries of any length.
+    fn is_bin(&self) -> bool;
+
+    /// Returns `true` for arrays of any length.
+    fn is_array(&self) -> bool;
+
+    /// Returns `true` for maps of any length.
+    fn is_map(&self) -> bool;
+
+    /// Returns `true` for exts of any length, including fixext.
+    fn is_ext(&self) -> bool;
+}
+
+impl MarkerExt for Marker {
+    fn data_len(&self) -> Option<usize> {
+        let len = match *self {
+            Marker::Null | Marker::True | Marker::False => 0,
+            Marker::FixPos(_) | Marker::FixNeg(_) => 0,
+            Marker::U8 | Marker::I8 => 1,
+            Marker::U16 | Marker::I16 => 2,
+            Marker::U32 | Marker::I32 | Marker::F32 => 4,
+            Marker::U64 | Marker::I64 | Marker::F64 => 8,
+            Marker::FixStr(len) => len as usize,
+            Marker::FixExt1 => 2,
+            Marker::FixExt2 => 3,
+            Marker::FixExt4 => 5,
+            Marker::FixExt8 => 9,
+            Marker::FixExt16 => 17,
+            Marker::Str8
+            | Marker::Str16
+            | Marker::Str32
+            | Marker::Bin8
+            | Marker::Bin16
+            | Marker::Bin32
+            | Marker::Ext8
+            | Marker::Ext16
+            | Marker::Ext32
+            | Marker::FixArray(_)
+            | Marker::Array16
+            | Marker::Array32
+            | Marker::FixMap(_)
+            | Marker::Map16
+            | Marker::Map32
+            | Marker::Reserved => return None,
+        };
+        Some(len)
+    }
+    fn is_nil(&self) -> bool {
+        *self == Marker::Null
+    }
+
+    fn is_bool(&self) -> bool {
+        matches!(*self, Marker::True | Marker::False)
+    }
+
+    fn is_int(&self) -> bool {
+        matches!(
+            *self,
+            Marker::FixPos(_)
+                | Marker::FixNeg(_)
+                | Marker::U8
+                | Marker::U16
+                | Marker::U32
+                | Marker::U64
+                | Marker::I8
+                | Marker::I16
+                | Marker::I32
+                | Marker::I64
+        )
+    }
+
+    fn is_float(&self) -> bool {
+        matches!(*self, Marker::F32 | Marker::F64)
+    }
+
+    fn is_str(&self) -> bool {
+        matches!(
+            *self,
+            Marker::FixStr(_) | Marker::Str8 | Marker::Str16 | Marker::Str32
+        )
+    }
+
+    fn is_bin(&self) -> bool {
+        matches!(*self, Marker::Bin8 | Marker::Bin16 | Marker::Bin32)
+    }
+
+    fn is_array(&self) -> bool {
+        matches!(
+            *self,
+            Marker::FixArray(_) | Marker::Array16 | Marker::Array32
+        )
+    }
+
+    fn is_map(&self) -> bool {
+        matches!(*self, Marker::FixMap(_) | Marker::Map16 | Marker::Map32)
+    }
+
+    fn is_ext(&self) -> bool {
+        matches!(
+            *self,
+            Marker::FixExt1
+                | Marker::FixExt2
+                | Marker::FixExt4
+                | Marker::FixExt8
+                | Marker::FixExt16
+                | Marker::Ext8
+                | Marker::Ext16
+                | Marker::Ext32
+        )
+    }
+}
@@ -0,0 +1,155 @@
+//! Framing for [MessagePack-RPC](https://github.com/msgpack-rpc/msgpack-rpc/blob/master/spec.md)
+//! messages.
+//!
+//! Messages are plain MessagePack arrays whose first element is the message type, followed by
+//! type-specific fields. Parameters and results are kept as schemaless [`Value`]s, so they can be
+//! decoded further with [`from_read`](crate::from_read) or inspected directly.
+
+use std::io::{Read, Write};
+
+use rmp::{decode, encode};
+
+use crate::decode::value::{read_value, Value};
+use crate::decode::{read_str_owned, Error};
+
+/// Message type of a request, `[0, msgid, method, params]`.
+const REQUEST: u8 = 0;
+/// Message type of a response, `[1, msgid, error, result]`.
+const RESPONSE: u8 = 1;
+/// Message type of a notification, `[2, method, params]`.
+const NOTIFICATION: u8 = 2;
+
+/// The longest method name, in bytes, accepted when reading requests and notifications.
+pub const MAX_METHOD_LEN: u32 = 1024;
+
+/// Reads a request message, returning its message id, method name and parameters.
+///
+/// # Errors
+///
+/// Returns `LengthMismatch` if the message isn't a 4-element array, `RpcTypeMismatch` if it isn't a
+/// request and `LengthLimitExceeded` if the method name is longer than [`MAX_METHOD_LEN`].
+///
+/// # Examples
+///
+/// ```
+/// use messpack_serde::decode::value::Value;
+/// use messpack_serde::rpc::read_rpc_request;
+///
+/// // [0, 7, "add", [1, 2]]
+/// let buf = [0x94, 0x00, 0x07, 0xa3, 0x61, 0x64, 0x64, 0x92, 0x01, 0x02];
+/// let (msgid, method, params) = read_rpc_request(&mut &buf[..]).unwrap();
+///
+/// assert_eq!(7, msgid);
+/// assert_eq!("add", method);
+/// assert_eq!(Value::Array(vec![Value::from(1), Value::from(2)]), params);
+/// ```
+pub fn read_rpc_request<R: Read>(rd: &mut R) -> Result<(u32, String, Value), Error> {
+    read_header(rd, 4, REQUEST)?;
+    let msgid = decode::read_int(rd)?;
+    let method = read_str_owned(rd, MAX_METHOD_LEN)?;
+    let params = read_value(rd)?;
+    Ok((msgid, method, params))
+}
+
+/// Reads a response message, returning its message id and either the result or the error.
+///
+/// Per the spec, a response whose error is `nil` succeeded and carries a result; anything else is
+/// returned as `Err`.
+///
+/// # Errors
+///
+/// Returns `LengthMismatch` if the message isn't a 4-element array and `RpcTypeMismatch` if it
+/// isn't a response.
+pub fn read_rpc_response<R: Read>(rd: &mut R) -> Result<(u32, Result<Value, Value>), Error> {
+    read_header(rd, 4, RESPONSE)?;
+    let msgid = decode::read_int(rd)?;
+    let error = read_value(rd)?;
+    let result = read_value(rd)?;
+    let outcome = match error {
+        Value::Nil => Ok(result),
+        error => Err(error),
+    };
+    Ok((msgid, outcome))
+}
+
+/// Writes a response message for the request with the given message id.
+///
+/// A successful `result` is written with a `nil` error; an error is written with a `nil` result.
+///
+/// # Examples
+///
+/// ```
+/// use messpack_serde::decode::value::Value;
+/// use messpack_serde::rpc::{read_rpc_response, write_rpc_response};
+///
+/// let mut buf = Vec::new();
+/// write_rpc_response(&mut buf, 7, Ok(&Value::from(3))).unwrap();
+///
+/// assert_eq!([0x94, 0x01, 0x07, 0xc0, 0x03], buf[..]);
+/// assert_eq!((7, Ok(Value::from(3))), read_rpc_response(&mut &buf[..]).unwrap());
+/// ```
+pub fn write_rpc_response<W: Write>(
+    wr: &mut W,
+    msgid: u32,
+    result: Result<&Value, &Value>,
+) -> Result<(), crate::encode::Error> {
+    write_header(wr, 4, RESPONSE)?;
+    encode::write_uint(wr, u64::from(msgid))?;
+    let (error, result) = match result {
+        Ok(result) => (&Value::Nil, result),
+        Err(error) => (error, &Value::Nil),
+    };
+    rmpv::encode::write_value(wr, error)?;
+    rmpv::encode::write_value(wr, result)?;
+    Ok(())
+}
+
+/// Reads a notification message, returning its method name and parameters.
+///
+/// # Errors
+///
+/// Returns `LengthMismatch` if the message isn't a 3-element array, `RpcTypeMismatch` if it isn't a
+/// notification and `LengthLimitExceeded` if the method name is longer than [`MAX_METHOD_LEN`].
+pub fn read_rpc_notification<R: Read>(rd: &mut R) -> Result<(String, Value), Error> {
+    read_header(rd, 3, NOTIFICATION)?;
+    let method = read_str_owned(rd, MAX_METHOD_LEN)?;
+    let params = read_value(rd)?;
+    Ok((method, params))
+}
+
+/// Writes a notification message.
+pub fn write_rpc_notification<W: Write>(
+    wr: &mut W,
+    method: &str,
+    params: &Value,
+) -> Result<(), crate::encode::Error> {
+    write_header(wr, 3, NOTIFICATION)?;
+    encode::write_str(wr, method)?;
+    rmpv::encode::write_value(wr, params)?;
+    Ok(())
+}
+
+/// Reads the array header and message type shared by every message.
+fn read_header<R: Read>(rd: &mut R, len: u32, ty: u8) -> Result<(), Error> {
+    let actual = decode::read_array_len(rd)?;
+    if actual != len {
+        return Err(Error::LengthMismatch {
+            expected: len as usize,
+            actual,
+        });
+    }
+    let actual: u8 = decode::read_int(rd)?;
+    if actual != ty {
+        return Err(Error::RpcTypeMismatch {
+            expected: ty,
+            actual,
+        });
+    }
+    Ok(())
+}
+
+fn write_header<W: Write>(wr: &mut W, len: u32, ty: u8) -> Result<(), crate::encode::Error> {
+    encode::write_array_len(wr, len)?;
+    encode::write_uint(wr, u64::from(ty))?;
+    Ok(())
+}
@@ -0,0 +1,309 @@
+//! Helpers for testing code that produces MessagePack.
+//!
+//! Only available with the `test-support` feature.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use rmp::Marker;
+use serde::Serialize;
+
+use crate::decode::value::Value;
+use crate::marker::MarkerExt;
+
+/// Renders `buf` as hex, one MessagePack value per line, each annotated with its marker.
+///
+/// Elements of arrays and maps are indented below their container. Long payloads wrap onto
+/// continuation lines of 16 bytes, and bytes left over by a truncated value are marked as such.
+///
+/// # Examples
+///
+/// ```
+/// use messpack_serde::test_support::hexdump_annotated;
+///
+/// let dump = hexdump_annotated(&[0x92, 0x01, 0xa1, 0x61]);
+/// let lines: Vec<_> = dump.lines().collect();
+///
+/// assert_eq!("0000  92             FixArray(2)", lines[0]);
+/// assert_eq!("0001  01               FixPos(1)", lines[1]);
+/// assert_eq!("0002  a1 61            FixStr(1) \"a\"", lines[2]);
+/// ```
+#[must_use]
+pub fn hexdump_annotated(buf: &[u8]) -> String {
+    let mut out = String::new();
+    // Remaining element counts of the enclosing containers.
+    let mut open: Vec<u64> = Vec::new();
+    let mut pos = 0;
+
+    while pos < buf.len() {
+        let marker = Marker::from_u8(buf[pos]);
+        let (header, data, children) = sizes(marker, &buf[pos..]);
+        let end = (pos + header + data).min(buf.len());
+        let truncated = pos + header + data > buf.len();
+
+        let indent = "  ".repeat(open.len());
+        let mut note = format!("{marker:?}");
+        if let Marker::FixStr(_) | Marker::Str8 | Marker::Str16 | Marker::Str32 = marker {
+            if !truncated {
+                let s = String::from_utf8_lossy(&buf[pos + header..end]);
+                let _ = write!(note, " {s:?}");
+            }
+        }
+        if truncated {
+            note.push_str(" <truncated>");
+        }
+
+        let mut chunks = buf[pos..end].chunks(16);
+        let first = chunks.next().unwrap_or_default();
+        let _ = writeln!(out, "{pos:04x}  {:<14} {indent}{note}", hex(first));
+        let mut offset = pos + first.len();
+        for chunk in chunks {
+            let _ = writeln!(out, "{offset:04x}  {}", hex(chunk));
+            offset += chunk.len();
+        }
+        pos = end;
+
+        if let Some(count) = open.last_mut() {
+            *count -= 1;
+        }
+        if children > 0 {
+            open.push(children);
+        }
+        while open.last() == Some(&0) {
+            open.pop();
+        }
+    }
+    out
+}
+
+/// Serializes `value` with the default [`Serializer`](crate::Serializer) and asserts that it
+/// produces exactly `expected`.
+///
+/// # Panics
+///
+/// On mismatch, panics with annotated hex dumps of both encodings, see [`hexdump_annotated`].
+///
+/// # Examples
+///
+/// ```
+/// use messpack_serde::test_support::assert_encodes_to;
+///
+/// assert_encodes_to((1, "a"), &[0x92, 0x01, 0xa1, 0x61]);
+/// ```
+#[track_caller]
+pub fn assert_encodes_to<T: Serialize>(value: T, expected: &[u8]) {
+    let actual = crate::to_vec(&value).expect("value failed to serialize");
+    if actual != expected {
+        panic!(
+            "encoding mismatch\n--- expected\n{}--- actual\n{}",
+            hexdump_annotated(expected),
+            hexdump_annotated(&actual)
+        );
+    }
+}
+
+/// Counts the markers in `buf` by kind, such as `"FixPos"` or `"Str8"`.
+///
+/// Markers with an embedded value or length are counted under their name alone, so `FixStr(3)`
+/// and `FixStr(7)` both count as `"FixStr"`. Elements of arrays and maps are counted along with
+/// their container. This makes it easy to check that an encoder picks the smallest markers.
+///
+/// # Examples
+///
+/// ```
+/// use messpack_serde::test_support::scan_stats;
+///
+/// let buf = messpack_serde::to_vec(&(1, 300, "a")).unwrap();
+/// let stats = scan_stats(&buf);
+///
+/// assert_eq!(Some(&1), stats.get("FixArray"));
+/// assert_eq!(Some(&1), stats.get("FixPos"));
+/// assert_eq!(Some(&1), stats.get("U16"));
+/// assert_eq!(Some(&1), stats.get("FixStr"));
+/// ```
+#[must_use]
+pub fn scan_stats(buf: &[u8]) -> BTreeMap<String, usize> {
+    let mut stats = BTreeMap::new();
+    let mut pos = 0;
+    while pos < buf.len() {
+        let marker = Marker::from_u8(buf[pos]);
+        let (header, data, _) = sizes(marker, &buf[pos..]);
+        let mut name = format!("{marker:?}");
+        if let Some(paren) = name.find('(') {
+            name.truncate(paren);
+        }
+        *stats.entry(name).or_insert(0) += 1;
+        pos += header + data;
+    }
+    stats
+}
+
+/// Generates a pseudo-random [`Value`] from `seed`, for round-trip and fuzz-style tests.
+///
+/// Arrays and maps are nested at most `max_depth` levels deep and hold fewer than `max_len`
+/// elements each, which bounds the size of the tree. Integers, strings, bin and ext values favour
+/// the lengths and magnitudes around marker boundaries, such as 255 and 256 byte payloads, and
+/// floats may be NaN. The same seed always yields the same value.
+///
+/// # Examples
+///
+/// ```
+/// use messpack_serde::decode::value::Value;
+/// use messpack_serde::test_support::generate_value;
+///
+/// let val = generate_value(7, 2, 4);
+/// assert_eq!(val, generate_value(7, 2, 4));
+///
+/// let buf = messpack_serde::to_vec(&val).unwrap();
+/// let _: Value = messpack_serde::from_slice(&buf).unwrap();
+/// ```
+#[must_use]
+pub fn generate_value(seed: u64, max_depth: u32, max_len: u64) -> Value {
+    Gen::new(seed).value(max_depth, max_len)
+}
+
+/// Returns the sizes of the header and payload of the value starting at `buf[0]`, and how many
+/// nested values follow it.
+fn sizes(marker: Marker, buf: &[u8]) -> (usize, usize, u64) {
+    let be = |n: usize| -> Option<usize> {
+        let bytes = buf.get(1..=n)?;
+        Some(bytes.iter().fold(0, |acc, &b| (acc << 8) | usize::from(b)))
+    };
+    match marker {
+        Marker::FixArray(len) => (1, 0, len.into()),
+        Marker::FixMap(len) => (1, 0, u64::from(len) * 2),
+        Marker::Array16 | Marker::Array32 | Marker::Map16 | Marker::Map32 => {
+            let n = if let Marker::Array16 | Marker::Map16 = marker {
+                2
+            } else {
+                4
+            };
+            let len = be(n).unwrap_or(0) as u64;
+            let per = if let Marker::Map16 | Marker::Map32 = marker {
+                2
+            } else {
+                1
+            };
+            (1 + n, 0, len * per)
+        }
+        Marker::Str8 | Marker::Bin8 => (2, be(1).unwrap_or(0), 0),
+        Marker::Str16 | Marker::Bin16 => (3, be(2).unwrap_or(0), 0),
+        Marker::Str32 | Marker::Bin32 => (5, be(4).unwrap_or(0), 0),
+        // The type id is part of the header.
+        Marker::Ext8 => (3, be(1).unwrap_or(0), 0),
+        Marker::Ext16 => (4, be(2).unwrap_or(0), 0),
+        Marker::Ext32 => (6, be(4).unwrap_or(0), 0),
+        Marker::FixExt1
+        | Marker::FixExt2
+        | Marker::FixExt4
+        | Marker::FixExt8
+        | Marker::FixExt16 => (2, marker.data_len().unwrap_or(1) - 1, 0),
+        Marker::FixStr(len) => (1, len.into(), 0),
+        // Scalars are shown whole on one line.
+        _ => (1 + marker.data_len().unwrap_or(0), 0, 0),
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len() * 3);
+    for (i, b) in bytes.iter().enumerate() {
+        if i > 0 {
+            out.push(' ');
+        }
+        let _ = write!(out, "{b:02x}");
+    }
+    out
+}
+
+/// Small, dependency-free xorshift generator behind [`generate_value`].
+struct Gen(u64);
+
+impl Gen {
+    fn new(seed: u64) -> Self {
+        // Scramble the seed with a splitmix64 step first. Xorshift is stuck at a zero state, and
+        // nearby seeds would start out producing similar values.
+        let mut z = seed.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        Self((z ^ (z >> 31)).max(1))
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn below(&mut self, n: u64) -> u64 {
+        self.next() % n
+    }
+
+    fn bytes(&mut self) -> Vec<u8> {
+        // Lengths around the bin8/bin16 boundary are the interesting ones.
+        let len = match self.below(4) {
+            0 => 0,
+            1 => self.below(32),
+            2 => 250 + self.below(10),
+            _ => 65530 + self.below(10),
+        };
+        (0..len).map(|_| self.next() as u8).collect()
+    }
+
+    fn string(&mut self) -> String {
+        let len = match self.below(3) {
+            0 => self.below(32),
+            1 => 28 + self.below(8),
+            _ => 250 + self.below(10),
+        };
+        (0..len)
+            .map(|_| match self.below(3) {
+                0 => char::from(b'a' + self.below(26) as u8),
+                1 => 'é',
+                _ => '€',
+            })
+            .collect()
+    }
+
+    fn int(&mut self) -> Value {
+        match self.below(6) {
+            0 => Value::from(self.below(128)),
+            1 => Value::from(-(self.below(32) as i64) - 1),
+            2 => Value::from(self.next()),
+            3 => Value::from(self.next() as i64),
+            4 => Value::from(self.next() as u32),
+            _ => Value::from(self.next() as i16),
+        }
+    }
+
+    fn value(&mut self, depth: u32, max_len: u64) -> Value {
+        let kinds = if depth == 0 || max_len == 0 { 8 } else { 10 };
+        match self.below(kinds) {
+            0 => Value::Nil,
+            1 => Value::Boolean(self.below(2) == 0),
+            2 => self.int(),
+            3 => Value::F32(f32::from_bits(self.next() as u32)),
+            4 => Value::F64(f64::from_bits(self.next())),
+            5 => Value::from(self.string()),
+            6 => Value::Binary(self.bytes()),
+            7 => Value::Ext(self.next() as i8, self.bytes()),
+            8 => {
+                let len = self.below(max_len);
+                Value::Array((0..len).map(|_| self.value(depth - 1, max_len)).collect())
+            }
+            _ => {
+                let len = self.below(max_len);
+                Value::Map(
+                    (0..len)
+                        .map(|_| {
+                            (
+                                self.value(depth - 1, max_len),
+                                self.value(depth - 1, max_len),
+                            )
+                        })
+                        .collect(),
+                )
+            }
+        }
+    }
+}
@@ -5,6 +5,7 @@ use std::io::Cursor;
 use serde::de;
 use serde::Deserialize;
 
+use messpack_serde::decode::value::Value;
 use messpack_serde::decode::{self, Error};
 use messpack_serde::{Deserializer, Raw, RawRef};
 use rmp::Marker;
@@ -33,8 +34,8 @@ fn pass_bool() {
     let buf = [0xc3, 0xc2];
     let mut de = Deserializer::new(&buf[..]);
 
-    assert_eq!(true, Deserialize::deserialize(&mut de).unwrap());
-    assert_eq!(false, Deserialize::deserialize(&mut de).unwrap());
+    assert!(bool::deserialize(&mut de).unwrap());
+    assert!(!bool::deserialize(&mut de).unwrap());
 }
 
 #[test]
@@ -247,7 +248,6 @@ fn pass_tuple() {
     assert_eq!((42, 100500), actual);
 }
 
-#[ignore]
 #[test]
 fn fail_tuple_len_mismatch() {
     let buf = [0x92, 0x2a, 0xce, 0x0, 0x1, 0x88, 0x94];
@@ -257,7 +257,10 @@ fn fail_tuple_len_mismatch() {
     let actual: Result<(u32,), Error> = Deserialize::deserialize(&mut de);
 
     match actual.err().unwrap() {
-        Error::LengthMismatch(1) => (),
+        Error::LengthMismatch {
+            expected: 1,
+            actual: 2,
+        } => (),
         other => panic!("unexpected result: {other:?}"),
     }
 }
@@ -311,6 +314,595 @@ fn fail_option_u8_from_reserved() {
     }
 }
 
+#[test]
+fn fail_u32_from_deeply_nested_array_skips_it() {
+    let depth = 100_000;
+    let mut buf = vec![0x91; depth];
+    buf.extend_from_slice(&[0x90, 0x2a]);
+
+    let mut de = Deserializer::new(&buf[..]);
+    let actual: Result<u32, Error> = Deserialize::deserialize(&mut de);
+    match actual.err() {
+        Some(Error::TypeMismatch(Marker::FixArray(1))) => (),
+        other => panic!("unexpected result: {other:?}"),
+    }
+
+    // The whole nested value was consumed, leaving the reader at the following byte.
+    assert_eq!(42, u32::deserialize(&mut de).unwrap());
+}
+
+#[test]
+fn pass_with_skip_leading_length_prefix() {
+    // 4-byte big-endian length prefix, then ["a", 1]
+    let buf = [0x00, 0x00, 0x00, 0x04, 0x92, 0xa1, 0x61, 0x01];
+
+    let mut de = Deserializer::new(&buf[..]).with_skip_leading(4);
+    let actual: (String, u8) = Deserialize::deserialize(&mut de).unwrap();
+    assert_eq!(("a".to_owned(), 1), actual);
+
+    let mut de = Deserializer::from_read_ref(&buf[..]).with_skip_leading(4);
+    let actual: (&str, u8) = Deserialize::deserialize(&mut de).unwrap();
+    assert_eq!(("a", 1), actual);
+}
+
+#[test]
+fn pass_with_skip_leading_only_skips_once() {
+    let buf = [0xff, 0x01, 0x02];
+
+    let mut de = Deserializer::new(&buf[..]).with_skip_leading(1);
+    assert_eq!(1, u8::deserialize(&mut de).unwrap());
+    assert_eq!(2, u8::deserialize(&mut de).unwrap());
+}
+
+#[test]
+fn fail_with_skip_leading_past_end() {
+    let buf = [0x00, 0x00];
+
+    let mut de = Deserializer::new(&buf[..]).with_skip_leading(4);
+    match u8::deserialize(&mut de).err() {
+        Some(Error::InvalidMarkerRead(..)) => (),
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn pass_with_legacy_raw_into_string_and_bytes() {
+    // raw16 holding "raw"
+    let buf = [0xda, 0x00, 0x03, 0x72, 0x61, 0x77];
+
+    let mut de = Deserializer::new(&buf[..]).with_legacy_raw(true);
+    assert_eq!("raw", String::deserialize(&mut de).unwrap());
+
+    let mut de = Deserializer::new(&buf[..]).with_legacy_raw(true);
+    assert_eq!(b"raw".to_vec(), Vec::<u8>::deserialize(&mut de).unwrap());
+
+    let mut de = Deserializer::new(&buf[..])
+        .with_legacy_raw(true)
+        .with_legacy_raw(false);
+    assert!(Vec::<u8>::deserialize(&mut de).is_err());
+}
+
+#[test]
+fn pass_with_legacy_raw32_into_bytes() {
+    // raw32 holding bytes that aren't valid UTF-8
+    let buf = [0xdb, 0x00, 0x00, 0x00, 0x02, 0xc3, 0x28];
+
+    let mut de = Deserializer::from_read_ref(&buf[..]).with_legacy_raw(true);
+    assert_eq!(vec![0xc3, 0x28], Vec::<u8>::deserialize(&mut de).unwrap());
+}
+
+#[test]
+fn fail_raw16_into_bytes_without_legacy_raw() {
+    let buf = [0xda, 0x00, 0x03, 0x72, 0x61, 0x77];
+
+    let mut de = Deserializer::new(&buf[..]);
+    assert!(Vec::<u8>::deserialize(&mut de).is_err());
+}
+
+#[test]
+fn pass_with_python_compat_str_encoded_bytes() {
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Record {
+        name: String,
+        payload: Vec<u8>,
+    }
+
+    // {"name": "hi", "payload": b"\x00\xff"} as packed with use_bin_type=False
+    let buf = [
+        0x82, 0xa4, 0x6e, 0x61, 0x6d, 0x65, 0xa2, 0x68, 0x69, 0xa7, 0x70, 0x61, 0x79, 0x6c, 0x6f,
+        0x61, 0x64, 0xa2, 0x00, 0xff,
+    ];
+
+    let mut de = Deserializer::new(&buf[..]).with_python_compat(true);
+    let expected = Record {
+        name: "hi".into(),
+        payload: vec![0x00, 0xff],
+    };
+    assert_eq!(expected, Record::deserialize(&mut de).unwrap());
+
+    let mut de = Deserializer::new(&buf[..]).with_python_compat(false);
+    assert!(Record::deserialize(&mut de).is_err());
+}
+
+#[test]
+fn pass_with_python_compat_bin_into_string() {
+    // bin 8 holding "hi"
+    let buf = [0xc4, 0x02, 0x68, 0x69];
+
+    let mut de = Deserializer::new(&buf[..]).with_python_compat(true);
+    assert_eq!("hi", String::deserialize(&mut de).unwrap());
+
+    let mut de = Deserializer::new(&buf[..]).with_python_compat(true);
+    assert_eq!(Value::from("hi"), Value::deserialize(&mut de).unwrap());
+}
+
+#[test]
+fn pass_with_bin_as_str_into_value() {
+    // bin 8 holding "hi", then bin 8 holding the invalid UTF-8 byte 0xff
+    let buf = [0xc4, 0x02, 0x68, 0x69, 0xc4, 0x01, 0xff];
+
+    let mut de = Deserializer::new(&buf[..]).with_bin_as_str(true);
+    assert_eq!(Value::from("hi"), Value::deserialize(&mut de).unwrap());
+    assert_eq!(
+        Value::Binary(vec![0xff]),
+        Value::deserialize(&mut de).unwrap()
+    );
+
+    let mut de = Deserializer::new(&buf[..]).with_bin_as_str(false);
+    assert_eq!(
+        Value::Binary(b"hi".to_vec()),
+        Value::deserialize(&mut de).unwrap()
+    );
+}
+
+#[test]
+fn fail_with_strict_floats_precision_loss() {
+    #[derive(Debug, Deserialize)]
+    #[allow(dead_code)]
+    struct Reading {
+        value: f64,
+    }
+
+    // [2^53 + 1] as uint 64
+    let buf = [0x91, 0xcf, 0x00, 0x20, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01];
+
+    let mut de = Deserializer::new(&buf[..]);
+    assert_eq!(
+        9007199254740992.0,
+        Reading::deserialize(&mut de).unwrap().value
+    );
+
+    let mut de = Deserializer::new(&buf[..]).with_strict_floats(true);
+    match Reading::deserialize(&mut de) {
+        Err(Error::PrecisionLoss) => (),
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn pass_with_strict_floats_exact_ints() {
+    // 2^53 as uint 64, -3 as int 8, 2^24 as uint 32, then 0.5 as f32
+    let buf = [
+        0xcf, 0x00, 0x20, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xd0, 0xfd, 0xce, 0x01, 0x00, 0x00,
+        0x00, 0xca, 0x3f, 0x00, 0x00, 0x00,
+    ];
+    let mut de = Deserializer::new(&buf[..]).with_strict_floats(true);
+
+    assert_eq!(9007199254740992.0, f64::deserialize(&mut de).unwrap());
+    assert_eq!(-3.0, f64::deserialize(&mut de).unwrap());
+    assert_eq!(16777216.0, f32::deserialize(&mut de).unwrap());
+    assert_eq!(0.5, f32::deserialize(&mut de).unwrap());
+
+    // 2^24 + 1 fits an f64 but not an f32.
+    let buf = [0xce, 0x01, 0x00, 0x00, 0x01];
+    let mut de = Deserializer::new(&buf[..]).with_strict_floats(true);
+    match f32::deserialize(&mut de) {
+        Err(Error::PrecisionLoss) => (),
+        other => panic!("unexpected result: {other:?}"),
+    }
+    assert_eq!(
+        16777217.0,
+        f64::deserialize(&mut Deserializer::new(&buf[..])).unwrap()
+    );
+}
+
+#[test]
+fn fail_with_max_ext_len_huge_header() {
+    // ext 32 of type 1 claiming 0xffffffff bytes, with no data at all
+    let buf = [0xc9, 0xff, 0xff, 0xff, 0xff, 0x01];
+
+    let mut de = Deserializer::new(&buf[..]).with_max_ext_len(1024);
+    match messpack_serde::Timestamp::deserialize(&mut de) {
+        Err(Error::LengthLimitExceeded {
+            len: u32::MAX,
+            max_len: 1024,
+        }) => (),
+        other => panic!("unexpected result: {other:?}"),
+    }
+
+    let mut de = Deserializer::new(&buf[..]).with_max_ext_len(1024);
+    match rmpv::Value::deserialize(&mut de) {
+        Err(Error::LengthLimitExceeded {
+            len: u32::MAX,
+            max_len: 1024,
+        }) => (),
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn pass_with_max_ext_len_at_limit() {
+    // fixext 4 of type 5
+    let buf = [0xd6, 0x05, 0x01, 0x02, 0x03, 0x04];
+
+    let mut de = Deserializer::new(&buf[..]).with_max_ext_len(4);
+    assert_eq!(
+        rmpv::Value::Ext(5, vec![1, 2, 3, 4]),
+        rmpv::Value::deserialize(&mut de).unwrap()
+    );
+
+    let mut de = Deserializer::new(&buf[..]).with_max_ext_len(3);
+    match rmpv::Value::deserialize(&mut de) {
+        Err(Error::LengthLimitExceeded { len: 4, max_len: 3 }) => (),
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn fail_struct_field_data_eof_is_data_read() {
+    #[derive(Debug, Deserialize)]
+    #[allow(dead_code)]
+    struct Record {
+        id: u32,
+        name: String,
+    }
+
+    // [300, "abc"] cut off inside the id, then inside the name
+    for buf in [&[0x92, 0xcd, 0x01][..], &[0x92, 0x01, 0xa3, 0x61][..]] {
+        let mut de = Deserializer::new(buf);
+        match Record::deserialize(&mut de) {
+            Err(Error::InvalidDataRead(err)) => {
+                assert_eq!(std::io::ErrorKind::UnexpectedEof, err.kind())
+            }
+            other => panic!("unexpected result: {other:?}"),
+        }
+    }
+
+    // Cut off before the name's marker.
+    let mut de = Deserializer::new(&[0x92, 0x01][..]);
+    match Record::deserialize(&mut de) {
+        Err(Error::InvalidMarkerRead(err)) => {
+            assert_eq!(std::io::ErrorKind::UnexpectedEof, err.kind())
+        }
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn pass_decode_string_error_keeps_utf8_error() {
+    let buf = [0xa2, 0xc3, 0x28];
+    let err = messpack_serde::decode::take_str(&buf).unwrap_err();
+
+    match Error::from(err) {
+        Error::Utf8Error(err) => assert_eq!(0, err.valid_up_to()),
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn pass_recover_to_next_after_corrupt_value() {
+    // ["a", <reserved>, <reserved>], followed by "ok"
+    let buf = [0x93, 0xa1, 0x61, 0xc1, 0xc1, 0xa2, 0x6f, 0x6b];
+    let mut de = Deserializer::new(Cursor::new(&buf[..]));
+
+    assert!(<(String, u8, u8)>::deserialize(&mut de).is_err());
+    de.recover_to_next().unwrap();
+    assert_eq!("ok", String::deserialize(&mut de).unwrap());
+
+    match de.recover_to_next() {
+        Err(Error::InvalidMarkerRead(..)) => (),
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn pass_recover_to_next_skips_truncated_value() {
+    // <reserved>, a str8 claiming 193 bytes, <reserved>, then [1, 2]
+    let buf = [0xc1, 0xd9, 0xc1, 0x92, 0x01, 0x02];
+    let mut de = Deserializer::new(Cursor::new(&buf[..]));
+
+    assert!(Vec::<u8>::deserialize(&mut de).is_err());
+    de.recover_to_next().unwrap();
+    assert_eq!(3, de.position());
+    assert_eq!(vec![1, 2], Vec::<u8>::deserialize(&mut de).unwrap());
+}
+
+#[derive(Debug, PartialEq, Deserialize)]
+struct ThreeFields {
+    a: u8,
+    b: String,
+    c: bool,
+}
+
+#[test]
+fn pass_with_ignore_extra_struct_fields() {
+    // [1, "x", true, [2, 3], {"d": 4}], followed by nil
+    let buf = [
+        0x95, 0x01, 0xa1, 0x78, 0xc3, 0x92, 0x02, 0x03, 0x81, 0xa1, 0x64, 0x04, 0xc0,
+    ];
+    let mut de = Deserializer::new(&buf[..]).with_ignore_extra_struct_fields(true);
+
+    let expected = ThreeFields {
+        a: 1,
+        b: "x".into(),
+        c: true,
+    };
+    assert_eq!(expected, ThreeFields::deserialize(&mut de).unwrap());
+    assert_eq!((), <()>::deserialize(&mut de).unwrap());
+}
+
+#[test]
+fn fail_extra_struct_fields_by_default() {
+    let buf = [0x95, 0x01, 0xa1, 0x78, 0xc3, 0x02, 0x03];
+    let mut de = Deserializer::new(&buf[..]);
+
+    match ThreeFields::deserialize(&mut de) {
+        Err(Error::LengthMismatch {
+            expected: 3,
+            actual: 5,
+        }) => (),
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn fail_with_ignore_extra_struct_fields_still_needs_all_fields() {
+    let buf = [0x92, 0x01, 0xa1, 0x78];
+    let mut de = Deserializer::new(&buf[..]).with_ignore_extra_struct_fields(true);
+
+    assert!(ThreeFields::deserialize(&mut de).is_err());
+}
+
+#[test]
+fn pass_with_unwrap_singletons() {
+    // [5], 5, ["x"], [7], followed by nil
+    let buf = [0x91, 0x05, 0x05, 0x91, 0xa1, 0x78, 0x91, 0x07, 0xc0];
+    let mut de = Deserializer::new(&buf[..]).with_unwrap_singletons(true);
+
+    assert_eq!(5, i32::deserialize(&mut de).unwrap());
+    assert_eq!(5, i32::deserialize(&mut de).unwrap());
+    assert_eq!("x", String::deserialize(&mut de).unwrap());
+    assert_eq!(vec![7], Vec::<u8>::deserialize(&mut de).unwrap());
+    assert_eq!((), <()>::deserialize(&mut de).unwrap());
+}
+
+#[test]
+fn fail_singleton_into_scalar_by_default() {
+    let buf = [0x91, 0x05];
+    let mut de = Deserializer::new(&buf[..]);
+
+    match i32::deserialize(&mut de) {
+        Err(Error::TypeMismatch(Marker::FixArray(1))) => (),
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn pass_with_byte_arrays() {
+    // "abcd", bin [1, 2, 3, 4], [5, 6, 7, 8]
+    let buf = [
+        0xa4, 0x61, 0x62, 0x63, 0x64, 0xc4, 0x04, 0x01, 0x02, 0x03, 0x04, 0x94, 0x05, 0x06, 0x07,
+        0x08,
+    ];
+    let mut de = Deserializer::new(&buf[..]).with_byte_arrays(true);
+
+    assert_eq!(*b"abcd", <[u8; 4]>::deserialize(&mut de).unwrap());
+    assert_eq!([1, 2, 3, 4], <[u8; 4]>::deserialize(&mut de).unwrap());
+    assert_eq!([5, 6, 7, 8], <[u8; 4]>::deserialize(&mut de).unwrap());
+}
+
+#[test]
+fn fail_with_byte_arrays_wrong_len() {
+    // "abc", followed by nil
+    let buf = [0xa3, 0x61, 0x62, 0x63, 0xc0];
+    let mut de = Deserializer::new(&buf[..]).with_byte_arrays(true);
+
+    match <[u8; 4]>::deserialize(&mut de) {
+        Err(Error::LengthMismatch {
+            expected: 4,
+            actual: 3,
+        }) => (),
+        other => panic!("unexpected result: {other:?}"),
+    }
+    assert_eq!((), <()>::deserialize(&mut de).unwrap());
+}
+
+#[test]
+fn fail_str_into_byte_array_by_default() {
+    let buf = [0xa4, 0x61, 0x62, 0x63, 0x64];
+    let mut de = Deserializer::new(&buf[..]);
+
+    assert!(<[u8; 4]>::deserialize(&mut de).is_err());
+}
+
+#[test]
+fn pass_map_with_nil_key() {
+    use std::collections::HashMap;
+
+    // {nil: 1, "a": 2}
+    let buf = [0x82, 0xc0, 0x01, 0xa1, 0x61, 0x02];
+
+    let map: HashMap<Option<String>, u8> = messpack_serde::from_slice(&buf).unwrap();
+    assert_eq!(2, map.len());
+    assert_eq!(Some(&1), map.get(&None));
+    assert_eq!(Some(&2), map.get(&Some("a".into())));
+}
+
+#[test]
+fn fail_struct_with_nil_key() {
+    #[derive(Debug, Deserialize)]
+    #[allow(dead_code)]
+    struct Struct {
+        a: u8,
+    }
+
+    // {nil: 1, "a": 2}
+    let buf = [0x82, 0xc0, 0x01, 0xa1, 0x61, 0x02];
+
+    match messpack_serde::from_slice::<Struct>(&buf) {
+        Err(Error::Syntax(msg)) => assert!(msg.contains("nil"), "{msg}"),
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn fail_map_size_bomb_from_slice() {
+    use std::collections::HashMap;
+
+    // Map32 declaring 1e9 entries, followed by a single one
+    let buf = [0xdf, 0x3b, 0x9a, 0xca, 0x00, 0x01, 0x02];
+
+    match messpack_serde::from_slice::<HashMap<u8, u8>>(&buf) {
+        Err(Error::LengthLimitExceeded {
+            len: 1_000_000_000,
+            max_len: 1,
+        }) => (),
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn fail_array_size_bomb_from_slice() {
+    // Array32 declaring 1e9 elements, followed by three
+    let buf = [0xdd, 0x3b, 0x9a, 0xca, 0x00, 0x01, 0x02, 0x03];
+
+    match messpack_serde::from_slice::<Vec<serde::de::IgnoredAny>>(&buf) {
+        Err(Error::LengthLimitExceeded {
+            len: 1_000_000_000,
+            max_len: 3,
+        }) => (),
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn pass_seq_of_options() {
+    // [1, nil, 3]
+    let buf = [0x93, 0x01, 0xc0, 0x03];
+
+    let mut de = Deserializer::new(&buf[..]);
+    let actual: Vec<Option<i32>> = Deserialize::deserialize(&mut de).unwrap();
+    assert_eq!(vec![Some(1), None, Some(3)], actual);
+
+    // Elements are decoded one marker at a time, whatever their encoding.
+    // [nil, int 16 -300, nil]
+    let buf = [0x93, 0xc0, 0xd1, 0xfe, 0xd4, 0xc0];
+    let actual: Vec<Option<i32>> = messpack_serde::from_slice(&buf).unwrap();
+    assert_eq!(vec![None, Some(-300), None], actual);
+}
+
+#[test]
+fn pass_with_map_as_pairs_into_vec_of_tuples() {
+    // {"a": 1, "b": -2, "c": 300}
+    let buf = [
+        0x83, 0xa1, 0x61, 0x01, 0xa1, 0x62, 0xfe, 0xa1, 0x63, 0xcd, 0x01, 0x2c,
+    ];
+
+    let mut de = Deserializer::new(&buf[..]).with_map_as_pairs(true);
+    let pairs: Vec<(String, i64)> = Deserialize::deserialize(&mut de).unwrap();
+    assert_eq!(
+        vec![("a".into(), 1), ("b".into(), -2), ("c".into(), 300)],
+        pairs
+    );
+}
+
+#[test]
+fn pass_with_map_as_pairs_keeps_arrays() {
+    let buf = [0x91, 0x92, 0xa1, 0x61, 0x01];
+
+    let mut de = Deserializer::new(&buf[..]).with_map_as_pairs(true);
+    let pairs: Vec<(String, i64)> = Deserialize::deserialize(&mut de).unwrap();
+    assert_eq!(vec![("a".into(), 1)], pairs);
+}
+
+#[test]
+fn fail_map_into_vec_of_tuples_without_map_as_pairs() {
+    let buf = [0x81, 0xa1, 0x61, 0x01];
+
+    let mut de = Deserializer::new(&buf[..]);
+    assert!(Vec::<(String, i64)>::deserialize(&mut de).is_err());
+}
+
+#[test]
+fn pass_with_ext_handler_decodes_uuid() {
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Record {
+        id: String,
+        count: u32,
+    }
+
+    fn uuid(typeid: i8, data: &[u8]) -> Result<decode::value::Value, Error> {
+        if typeid != 2 || data.len() != 16 {
+            return Err(Error::Uncategorized(format!("unexpected ext {typeid}")));
+        }
+        let hex: String = data.iter().map(|b| format!("{b:02x}")).collect();
+        let s = format!(
+            "{}-{}-{}-{}-{}",
+            &hex[..8],
+            &hex[8..12],
+            &hex[12..16],
+            &hex[16..20],
+            &hex[20..]
+        );
+        Ok(s.into())
+    }
+
+    // [fixext16 type 2 <uuid>, 7]
+    let mut buf = vec![0x92, 0xd8, 0x02];
+    buf.extend_from_slice(&[
+        0x12, 0x3e, 0x45, 0x67, 0xe8, 0x9b, 0x12, 0xd3, 0xa4, 0x56, 0x42, 0x66, 0x14, 0x17, 0x40,
+        0x00,
+    ]);
+    buf.push(0x07);
+
+    let mut de = Deserializer::new(&buf[..]).with_ext_handler(Box::new(uuid));
+    let actual = Record::deserialize(&mut de).unwrap();
+
+    assert_eq!(
+        Record {
+            id: "123e4567-e89b-12d3-a456-426614174000".to_owned(),
+            count: 7,
+        },
+        actual
+    );
+}
+
+#[test]
+fn fail_with_ext_handler_error() {
+    let buf = [0xd4, 0x05, 0x00];
+
+    let handler = Box::new(|typeid, _: &[u8]| Err(Error::Uncategorized(format!("ext {typeid}"))));
+    let mut de = Deserializer::new(&buf[..]).with_ext_handler(handler);
+    match String::deserialize(&mut de).err() {
+        Some(Error::Uncategorized(msg)) => assert_eq!("ext 5", msg),
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn fail_with_ext_handler_value_keeps_error_variant() {
+    let buf = [0xd4, 0x05, 0x00];
+
+    // The handler yields a str holding the invalid UTF-8 byte 0xff.
+    let handler =
+        Box::new(|_, _: &[u8]| Ok(rmpv::decode::read_value(&mut &[0xa1, 0xff][..]).unwrap()));
+    let mut de = Deserializer::new(&buf[..]).with_ext_handler(handler);
+    match String::deserialize(&mut de) {
+        Err(Error::Utf8Error(_)) => (),
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
 #[test]
 fn pass_vector() {
     let buf = [0x92, 0x00, 0xcc, 0x80];
@@ -343,6 +935,56 @@ fn pass_map() {
     assert_eq!(expected, actual);
 }
 
+#[test]
+fn pass_map_with_integer_keys() {
+    let buf = [
+        0x83, // 3 (size)
+        0x01, 0xa3, 0x6f, 0x6e, 0x65, // 1: 'one'
+        0xcd, 0x01, 0x2c, 0xa1, 0x78, // 300: 'x'
+        0xce, 0x00, 0x01, 0x00, 0x00, 0xa0, // 65536: ''
+    ];
+
+    let actual: HashMap<u32, String> =
+        Deserialize::deserialize(&mut Deserializer::new(&buf[..])).unwrap();
+    let expected = HashMap::from([
+        (1, "one".to_string()),
+        (300, "x".to_string()),
+        (65536, String::new()),
+    ]);
+
+    assert_eq!(expected, actual);
+}
+
+#[test]
+fn pass_btree_map_with_string_keys() {
+    use std::collections::BTreeMap;
+
+    let buf = [
+        0x82, // 2 (size)
+        0xa1, 0x62, 0xd0, 0x80, // 'b': -128
+        0xa1, 0x61, 0xcf, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, // 'a': 1 << 32
+    ];
+
+    let actual: BTreeMap<String, i64> =
+        Deserialize::deserialize(&mut Deserializer::new(&buf[..])).unwrap();
+    let expected = BTreeMap::from([("a".to_string(), 1 << 32), ("b".to_string(), -128)]);
+
+    assert_eq!(expected, actual);
+}
+
+#[test]
+fn fail_map_with_mismatched_key_type() {
+    // {'a': 1}
+    let buf = [0x81, 0xa1, 0x61, 0x01];
+
+    let res: Result<HashMap<u32, u32>, Error> =
+        Deserialize::deserialize(&mut Deserializer::new(&buf[..]));
+    match res.err() {
+        Some(Error::TypeMismatch(Marker::FixStr(1))) => (),
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
 // TODO: Merge three of them.
 #[test]
 fn pass_bin8_into_bytebuf() {
@@ -500,7 +1142,7 @@ fn pass_from() {
 
 #[test]
 fn pass_raw_valid_utf8() {
-    let buf = vec![0xa3, 0x6b, 0x65, 0x79];
+    let buf = [0xa3, 0x6b, 0x65, 0x79];
     let raw: Raw = messpack_serde::from_slice(&buf[..]).unwrap();
 
     assert!(raw.is_str());
@@ -512,7 +1154,7 @@ fn pass_raw_valid_utf8() {
 fn pass_raw_invalid_utf8() {
     // >>> msgpack.dumps(msgpack.dumps([200, []]))
     // '\xa4\x92\xcc\xc8\x90'
-    let buf = vec![0xa4, 0x92, 0xcc, 0xc8, 0x90];
+    let buf = [0xa4, 0x92, 0xcc, 0xc8, 0x90];
     let raw: Raw = messpack_serde::from_slice(&buf[..]).unwrap();
 
     assert!(raw.is_err());
@@ -522,7 +1164,7 @@ fn pass_raw_invalid_utf8() {
 
 #[test]
 fn pass_raw_ref_valid_utf8() {
-    let buf = vec![0xa3, 0x6b, 0x65, 0x79];
+    let buf = [0xa3, 0x6b, 0x65, 0x79];
     let raw: RawRef<'_> = messpack_serde::from_slice(&buf[..]).unwrap();
 
     assert!(raw.is_str());
@@ -534,7 +1176,7 @@ fn pass_raw_ref_valid_utf8() {
 fn pass_raw_ref_invalid_utf8() {
     // >>> msgpack.dumps(msgpack.dumps([200, []]))
     // '\xa4\x92\xcc\xc8\x90'
-    let buf = vec![0xa4, 0x92, 0xcc, 0xc8, 0x90];
+    let buf = [0xa4, 0x92, 0xcc, 0xc8, 0x90];
     let raw: RawRef<'_> = messpack_serde::from_slice(&buf[..]).unwrap();
 
     assert!(raw.is_err());
@@ -544,7 +1186,7 @@ fn pass_raw_ref_invalid_utf8() {
 
 #[test]
 fn fail_str_invalid_utf8() {
-    let buf = vec![0xa4, 0x92, 0xcc, 0xc8, 0x90];
+    let buf = [0xa4, 0x92, 0xcc, 0xc8, 0x90];
     let err: Result<String, decode::Error> = messpack_serde::from_slice(&buf[..]);
 
     assert!(err.is_err());
@@ -571,10 +1213,7 @@ fn fail_depth_limit() {
             Ok(Nested { sub: nested })
         }
     }
-    let mut data = Vec::new();
-    for _ in 0..100 {
-        data.push(0x91u8);
-    }
+    let data = vec![0x91u8; 100];
     let mut reader = messpack_serde::Deserializer::new(Cursor::new(data));
     reader.set_max_depth(100);
     let res = Nested::deserialize(&mut reader);
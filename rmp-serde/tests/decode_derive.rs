@@ -104,6 +104,41 @@ fn pass_struct_from_map() {
     assert_eq!(expected, actual);
 }
 
+#[test]
+fn pass_struct_from_map_with_renamed_field() {
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Struct {
+        #[serde(rename = "n")]
+        count: u8,
+        name: String,
+    }
+
+    let buf = [
+        0x82, // 2 (size)
+        0xa1, 0x6e, // "n"
+        0x05, // 5
+        0xa4, 0x6e, 0x61, 0x6d, 0x65, // "name"
+        0xa1, 0x78, // "x"
+    ];
+
+    let actual: Struct = messpack_serde::from_slice(&buf).unwrap();
+    let expected = Struct {
+        count: 5,
+        name: "x".into(),
+    };
+    assert_eq!(expected, actual);
+
+    // The Rust name of the field is not a key it answers to.
+    let buf = [
+        0x82, // 2 (size)
+        0xa5, 0x63, 0x6f, 0x75, 0x6e, 0x74, // "count"
+        0x05, // 5
+        0xa4, 0x6e, 0x61, 0x6d, 0x65, // "name"
+        0xa1, 0x78, // "x"
+    ];
+    assert!(messpack_serde::from_slice::<Struct>(&buf).is_err());
+}
+
 #[test]
 fn pass_unit_variant() {
     // We expect enums to be encoded as a map {variant_idx => nil}
@@ -126,6 +161,38 @@ fn pass_unit_variant() {
     assert_eq!(6, de.get_ref().position());
 }
 
+#[test]
+fn pass_unit_variant_from_bare_name() {
+    // "Red", as written by the serializer for unit variants
+    let buf = [0xa3, 0x52, 0x65, 0x64];
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    enum Color {
+        Red,
+        Green,
+    }
+
+    let color: Color = messpack_serde::from_slice(&buf).unwrap();
+    assert_eq!(Color::Red, color);
+}
+
+#[test]
+fn fail_unit_variant_from_unknown_name() {
+    // "Blue"
+    let buf = [0xa4, 0x42, 0x6c, 0x75, 0x65];
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    enum Color {
+        Red,
+        Green,
+    }
+
+    match messpack_serde::from_slice::<Color>(&buf) {
+        Err(Error::Syntax(msg)) => assert!(msg.contains("Blue"), "{msg}"),
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
 #[test]
 fn pass_tuple_enum_with_arg() {
     // The encoded byte-array is: {1 => 42}.
@@ -176,7 +243,10 @@ fn fail_enum_map_mismatch() {
     let err: Result<Enum, _> = messpack_serde::from_slice(&buf);
 
     match err.unwrap_err() {
-        Error::LengthMismatch(2) => (),
+        Error::LengthMismatch {
+            expected: 1,
+            actual: 2,
+        } => (),
         other => panic!("unexpected result: {other:?}"),
     }
 }
@@ -509,3 +579,39 @@ fn pass_from_ref() {
         messpack_serde::from_read_ref(&buf).unwrap()
     );
 }
+
+#[test]
+fn pass_enum_variant_by_index_or_name() {
+    #[derive(Debug, PartialEq, Deserialize)]
+    enum E {
+        A,
+        B(u32),
+        C { x: u8 },
+    }
+
+    let cases: [(&[u8], E); 6] = [
+        // 0 and "A"
+        (&[0x00], E::A),
+        (&[0xa1, 0x41], E::A),
+        // {1: 5} and {"B": 5}
+        (&[0x81, 0x01, 0x05], E::B(5)),
+        (&[0x81, 0xa1, 0x42, 0x05], E::B(5)),
+        // {2: [3]} and {"C": {"x": 3}}
+        (&[0x81, 0x02, 0x91, 0x03], E::C { x: 3 }),
+        (&[0x81, 0xa1, 0x43, 0x81, 0xa1, 0x78, 0x03], E::C { x: 3 }),
+    ];
+
+    for (buf, expected) in cases {
+        assert_eq!(
+            expected,
+            messpack_serde::from_slice::<E>(buf).unwrap(),
+            "{buf:02x?}"
+        );
+    }
+
+    // {3: nil} names no variant.
+    match messpack_serde::from_slice::<E>(&[0x81, 0x03, 0xc0]) {
+        Err(Error::Syntax(..)) => (),
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
@@ -0,0 +1,86 @@
+use rmp::decode::ValueReadError;
+use rmp::Marker;
+
+use messpack_serde::decode::DecodeInto;
+
+#[path = "common/counting_alloc.rs"]
+mod counting_alloc;
+
+use counting_alloc::{allocations, CountingAlloc};
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+#[derive(Debug, Default, PartialEq)]
+struct Reading {
+    sensor: u16,
+    value: f64,
+    ok: bool,
+}
+
+impl DecodeInto for Reading {
+    fn decode_into(rd: &mut impl std::io::Read, out: &mut Self) -> Result<(), ValueReadError> {
+        let mut fields = (0, 0.0, false);
+        DecodeInto::decode_into(rd, &mut fields)?;
+        (out.sensor, out.value, out.ok) = fields;
+        Ok(())
+    }
+}
+
+#[test]
+fn pass_decode_into_reused_struct_without_allocating() {
+    let bufs: Vec<Vec<u8>> = (0..1000u16)
+        .map(|i| {
+            let mut buf = Vec::new();
+            messpack_serde::encode::write(&mut buf, &(i, f64::from(i) / 2.0, i % 2 == 0)).unwrap();
+            buf
+        })
+        .collect();
+    let mut reading = Reading::default();
+
+    let ((), count) = allocations(|| {
+        for buf in &bufs {
+            Reading::decode_into(&mut &buf[..], &mut reading).unwrap();
+        }
+    });
+
+    assert_eq!(0, count);
+    assert_eq!(
+        Reading {
+            sensor: 999,
+            value: 499.5,
+            ok: false
+        },
+        reading
+    );
+}
+
+#[test]
+fn pass_decode_into_int_widths() {
+    let mut out = 0i64;
+    i64::decode_into(&mut &[0xcd, 0x01, 0x00][..], &mut out).unwrap();
+    assert_eq!(256, out);
+    i64::decode_into(&mut &[0xe0][..], &mut out).unwrap();
+    assert_eq!(-32, out);
+}
+
+#[test]
+fn fail_decode_into_int_out_of_range() {
+    let mut out = 7u8;
+
+    match u8::decode_into(&mut &[0xcd, 0x01, 0x00][..], &mut out) {
+        Err(ValueReadError::TypeMismatch(Marker::U16)) => (),
+        other => panic!("unexpected result: {other:?}"),
+    }
+    assert_eq!(7, out);
+}
+
+#[test]
+fn fail_decode_into_tuple_wrong_len() {
+    let mut out = (0u8, 0u8);
+
+    match DecodeInto::decode_into(&mut &[0x93, 0x01, 0x02, 0x03][..], &mut out) {
+        Err(ValueReadError::TypeMismatch(Marker::FixArray(3))) => (),
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
@@ -0,0 +1,1333 @@
+use std::borrow::Cow;
+use std::io::{self, Read};
+
+use rmp::decode::{self, ExtMeta, NumValueReadError, ValueReadError};
+use rmp::Marker;
+
+use messpack_serde::read_fixed_array;
+
+use messpack_serde::decode::{
+    read_array_iter, read_array_len_exact, read_array_raw_slices, read_array_with, read_bin,
+    read_bin_cow, read_bin_owned, read_decimal_ext, read_ext_array, read_ext_data, read_ext_owned,
+    read_f64_loosely, read_integer, read_integer_typed, read_map_iter, read_map_with,
+    read_seq_len_loose, read_str, read_str_len_with_class, read_str_owned, read_str_raw,
+    read_str_ref, read_u32_array, read_u32_loosely, read_with_default, skip_value, Endian, Error,
+    ExtMetaExt, ExtReader, IntWidth, ReadReader, StrClass,
+};
+
+#[test]
+fn pass_read_array_with_u32() {
+    // [1, 300, 65536, 4294967295]
+    let buf = [
+        0x94, 0x01, 0xcd, 0x01, 0x2c, 0xce, 0x00, 0x01, 0x00, 0x00, 0xce, 0xff, 0xff, 0xff, 0xff,
+    ];
+    let mut rd = &buf[..];
+
+    let vals: Vec<u32> = read_array_with(&mut rd, decode::read_int).unwrap();
+
+    assert_eq!(vec![1, 300, 65536, u32::MAX], vals);
+    assert!(rd.is_empty());
+}
+
+#[test]
+fn pass_read_array_with_empty() {
+    let buf = [0x90];
+
+    let vals: Vec<u32> = read_array_with(&mut &buf[..], decode::read_int).unwrap();
+
+    assert!(vals.is_empty());
+}
+
+#[test]
+fn fail_read_array_with_stops_at_first_error() {
+    // [1, "a", 3]
+    let buf = [0x93, 0x01, 0xa1, 0x61, 0x03];
+    let mut calls = 0;
+
+    let res: Result<Vec<u32>, NumValueReadError> = read_array_with(&mut &buf[..], |rd| {
+        calls += 1;
+        decode::read_int(rd)
+    });
+
+    match res {
+        Err(NumValueReadError::TypeMismatch(Marker::FixStr(1))) => (),
+        other => panic!("unexpected result: {other:?}"),
+    }
+    assert_eq!(2, calls);
+}
+
+#[test]
+fn fail_read_array_with_from_map() {
+    let buf = [0x80];
+
+    let res: Result<Vec<u32>, NumValueReadError> = read_array_with(&mut &buf[..], decode::read_int);
+
+    match res {
+        Err(NumValueReadError::TypeMismatch(Marker::FixMap(0))) => (),
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn fail_read_array_with_truncated() {
+    let buf = [0x92, 0xc3];
+
+    let res: Result<Vec<bool>, ValueReadError> = read_array_with(&mut &buf[..], decode::read_bool);
+
+    match res {
+        Err(ValueReadError::InvalidMarkerRead(..)) => (),
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+fn read_string<R: Read>(rd: &mut R) -> Result<String, Error> {
+    let mut buf = [0u8; 64];
+    Ok(read_str(rd, &mut buf)?.to_owned())
+}
+
+#[test]
+fn pass_read_fixed_array_heterogeneous() {
+    // [-300, "abc", true]
+    let buf = [0x93, 0xd1, 0xfe, 0xd4, 0xa3, 0x61, 0x62, 0x63, 0xc3, 0x01];
+    let mut rd = &buf[..];
+
+    let (num, s, flag): (i32, String, bool) =
+        read_fixed_array!(&mut rd, decode::read_int, read_string, decode::read_bool).unwrap();
+
+    assert_eq!((-300, "abc".to_owned(), true), (num, s, flag));
+    assert_eq!([0x01], rd);
+}
+
+#[test]
+fn fail_read_fixed_array_wrong_length() {
+    // [1, "a"]
+    let buf = [0x92, 0x01, 0xa1, 0x61];
+
+    let res: Result<(u8, String, bool), Error> = read_fixed_array!(
+        &mut &buf[..],
+        decode::read_int,
+        read_string,
+        decode::read_bool
+    );
+    match res {
+        Err(Error::LengthMismatch {
+            expected: 3,
+            actual: 2,
+        }) => (),
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn fail_read_fixed_array_element_type() {
+    // [1, 2]
+    let buf = [0x92, 0x01, 0x02];
+
+    let res: Result<(u8, bool), Error> =
+        read_fixed_array!(&mut &buf[..], decode::read_int, decode::read_bool);
+    match res {
+        Err(Error::TypeMismatch(Marker::FixPos(2))) => (),
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn pass_read_array_iter_remaining() {
+    let buf = [0x93, 0x01, 0xcd, 0x01, 0x2c, 0x03, 0xc0];
+    let mut rd = &buf[..];
+
+    let mut iter = read_array_iter(&mut rd, decode::read_int::<u32, _>).unwrap();
+    assert_eq!(3, iter.remaining());
+    assert_eq!(1, iter.next().unwrap().unwrap());
+    assert_eq!(2, iter.remaining());
+    assert_eq!(300, iter.next().unwrap().unwrap());
+    assert_eq!(1, iter.remaining());
+    assert_eq!(3, iter.next().unwrap().unwrap());
+    assert_eq!(0, iter.remaining());
+    assert!(iter.next().is_none());
+
+    assert_eq!([0xc0], rd);
+}
+
+#[test]
+fn fail_read_array_iter_ends_after_error() {
+    let buf = [0x93, 0x01, 0xc3, 0x03];
+    let mut rd = &buf[..];
+
+    let mut iter = read_array_iter(&mut rd, decode::read_int::<u32, _>).unwrap();
+    assert_eq!(1, iter.next().unwrap().unwrap());
+    match iter.next() {
+        Some(Err(NumValueReadError::TypeMismatch(Marker::True))) => (),
+        other => panic!("unexpected result: {other:?}"),
+    }
+    assert_eq!(0, iter.remaining());
+    assert!(iter.next().is_none());
+}
+
+#[test]
+fn pass_read_map_iter_remaining() {
+    // {"a": 1, "b": 2}
+    let buf = [0x82, 0xa1, 0x61, 0x01, 0xa1, 0x62, 0x02];
+    let mut rd = &buf[..];
+
+    let mut iter = read_map_iter(&mut rd, read_string, |rd| {
+        Ok(decode::read_int::<i64, _>(rd)?)
+    })
+    .unwrap();
+    assert_eq!(2, iter.remaining());
+    assert_eq!(("a".to_string(), 1), iter.next().unwrap().unwrap());
+    assert_eq!(1, iter.remaining());
+
+    let rest: Vec<_> = iter.collect::<Result<_, _>>().unwrap();
+    assert_eq!(vec![("b".to_string(), 2)], rest);
+    assert!(rd.is_empty());
+}
+
+#[test]
+fn pass_read_with_default_nil() {
+    let buf = [0xc0, 0x2a];
+    let mut rd = &buf[..];
+
+    let val: u32 = read_with_default(&mut rd, decode::read_int).unwrap();
+    assert_eq!(0, val);
+    assert_eq!([0x2a], rd);
+}
+
+#[test]
+fn pass_read_with_default_value() {
+    let buf = [0xcd, 0x01, 0x2c];
+    let mut rd = &buf[..];
+
+    let val: u32 = read_with_default(&mut rd, decode::read_int).unwrap();
+    assert_eq!(300, val);
+    assert!(rd.is_empty());
+}
+
+#[test]
+fn pass_read_with_default_buf_reader() {
+    let buf = [0x92, 0xc0, 0xa1, 0x78];
+    let mut rd = io::BufReader::new(&buf[..]);
+
+    let vals = read_array_with(&mut rd, |rd| read_with_default(rd, read_string)).unwrap();
+    assert_eq!(vec![String::new(), "x".to_string()], vals);
+}
+
+#[test]
+fn fail_read_with_default_empty() {
+    let buf = [];
+
+    match read_with_default::<u32, _, _, _>(&mut &buf[..], decode::read_int) {
+        Err(NumValueReadError::InvalidMarkerRead(..)) => (),
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn fail_read_array_len_exact() {
+    assert!(read_array_len_exact(&mut &[0x92][..], 2).is_ok());
+    match read_array_len_exact(&mut &[0x91][..], 2) {
+        Err(Error::LengthMismatch {
+            expected: 2,
+            actual: 1,
+        }) => (),
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn pass_read_map_with_str_to_i64() {
+    // {"a": 1, "bb": -300, "ccc": 4294967296}
+    let buf = [
+        0x83, 0xa1, 0x61, 0x01, 0xa2, 0x62, 0x62, 0xd1, 0xfe, 0xd4, 0xa3, 0x63, 0x63, 0x63, 0xcf,
+        0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00,
+    ];
+    let mut rd = &buf[..];
+
+    let entries: Vec<(String, i64)> = read_map_with(
+        &mut rd,
+        |rd| messpack_serde::from_read(rd),
+        |rd| Ok(decode::read_int(rd)?),
+    )
+    .unwrap();
+
+    assert_eq!(
+        vec![
+            ("a".to_string(), 1),
+            ("bb".to_string(), -300),
+            ("ccc".to_string(), 4294967296)
+        ],
+        entries
+    );
+    assert!(rd.is_empty());
+}
+
+#[test]
+fn fail_read_map_with_bad_value() {
+    // {"a": 1, "b": nil}
+    let buf = [0x82, 0xa1, 0x61, 0x01, 0xa1, 0x62, 0xc0];
+
+    let res: Result<Vec<(String, i64)>, Error> = read_map_with(
+        &mut &buf[..],
+        |rd| messpack_serde::from_read(rd),
+        |rd| Ok(decode::read_int(rd)?),
+    );
+
+    match res {
+        Err(Error::TypeMismatch(Marker::Null)) => (),
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn fail_read_map_with_from_array() {
+    let buf = [0x90];
+
+    let res: Result<Vec<(u8, u8)>, NumValueReadError> =
+        read_map_with(&mut &buf[..], decode::read_int, decode::read_int);
+
+    match res {
+        Err(NumValueReadError::TypeMismatch(Marker::FixArray(0))) => (),
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn pass_read_seq_len_loose_array() {
+    assert_eq!(3, read_seq_len_loose(&mut &[0x93][..], false).unwrap());
+    assert_eq!(3, read_seq_len_loose(&mut &[0x93][..], true).unwrap());
+    assert_eq!(
+        0x1234,
+        read_seq_len_loose(&mut &[0xdc, 0x12, 0x34][..], false).unwrap()
+    );
+    assert_eq!(
+        0x0001_0000,
+        read_seq_len_loose(&mut &[0xdd, 0x00, 0x01, 0x00, 0x00][..], false).unwrap()
+    );
+}
+
+#[test]
+fn fail_read_seq_len_loose_map_when_strict() {
+    match read_seq_len_loose(&mut &[0x82][..], false) {
+        Err(ValueReadError::TypeMismatch(Marker::FixMap(2))) => (),
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn pass_read_seq_len_loose_map_counts_keys_and_values() {
+    // {1: "a", 2: "b"}
+    let buf = [0x82, 0x01, 0xa1, 0x61, 0x02, 0xa1, 0x62];
+    let mut rd = &buf[..];
+
+    assert_eq!(4, read_seq_len_loose(&mut rd, true).unwrap());
+    assert_eq!([0x01, 0xa1, 0x61, 0x02, 0xa1, 0x62], rd);
+
+    assert_eq!(
+        0x1_ffff_fffe,
+        read_seq_len_loose(&mut &[0xdf, 0xff, 0xff, 0xff, 0xff][..], true).unwrap()
+    );
+}
+
+#[test]
+fn fail_read_seq_len_loose_from_str() {
+    match read_seq_len_loose(&mut &[0xa1, 0x61][..], true) {
+        Err(ValueReadError::TypeMismatch(Marker::FixStr(1))) => (),
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn pass_read_str() {
+    let buf = [0xa5, 0x68, 0x65, 0x6c, 0x6c, 0x6f, 0xc0];
+    let mut rd = &buf[..];
+    let mut out = [0u8; 5];
+
+    assert_eq!("hello", read_str(&mut rd, &mut out).unwrap());
+    assert_eq!([0xc0], rd);
+}
+
+#[test]
+fn fail_read_str_buffer_too_small() {
+    // "0123456789"
+    let buf = [
+        0xaa, 0x30, 0x31, 0x32, 0x33, 0x34, 0x35, 0x36, 0x37, 0x38, 0x39,
+    ];
+    let mut rd = &buf[..];
+    let mut out = [0u8; 5];
+
+    match read_str(&mut rd, &mut out) {
+        Err(Error::BufferSizeTooSmall {
+            required: 10,
+            available: Some(5),
+        }) => (),
+        other => panic!("unexpected result: {other:?}"),
+    }
+
+    // Only the header has been consumed, so the payload can still be read with a bigger buffer.
+    let mut out = [0u8; 10];
+    rd.read_exact(&mut out).unwrap();
+    assert_eq!(b"0123456789", &out);
+}
+
+#[test]
+fn fail_read_str_invalid_utf8() {
+    let buf = [0xa2, 0xc3, 0x28];
+    let mut out = [0u8; 2];
+
+    match read_str(&mut &buf[..], &mut out) {
+        Err(Error::Utf8Error(err)) => assert_eq!(0, err.valid_up_to()),
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn fail_read_str_truncated() {
+    let buf = [0xa5, 0x68, 0x65];
+    let mut out = [0u8; 5];
+
+    match read_str(&mut &buf[..], &mut out) {
+        Err(Error::InvalidDataRead(..)) => (),
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn pass_read_str_raw_skips_utf8_validation() {
+    let buf = [0xa2, 0xc3, 0x28, 0x01];
+    let mut rd = &buf[..];
+    let mut out = [0u8; 4];
+
+    assert_eq!([0xc3, 0x28], read_str_raw(&mut rd, &mut out).unwrap());
+    assert_eq!([0x01], rd);
+}
+
+#[test]
+fn fail_read_str_raw_buffer_too_small() {
+    let buf = [0xa3, 0x61, 0x62, 0x63];
+    let mut out = [0u8; 2];
+
+    match read_str_raw(&mut &buf[..], &mut out) {
+        Err(Error::BufferSizeTooSmall {
+            required: 3,
+            available: Some(2),
+        }) => (),
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn fail_read_str_raw_from_bin() {
+    let buf = [0xc4, 0x01, 0x61];
+    let mut out = [0u8; 2];
+
+    match read_str_raw(&mut &buf[..], &mut out) {
+        Err(Error::TypeMismatch(Marker::Bin8)) => (),
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn pass_read_str_owned() {
+    let buf = [0xa5, 0x68, 0x65, 0x6c, 0x6c, 0x6f, 0xc0];
+    let mut rd = &buf[..];
+
+    assert_eq!("hello", read_str_owned(&mut rd, 5).unwrap());
+    assert_eq!([0xc0], rd);
+}
+
+#[test]
+fn fail_read_str_owned_over_limit_on_unbounded_reader() {
+    // str32 declaring 4 GiB - 1, followed by an endless stream of 'a'
+    let mut rd = [0xdb, 0xff, 0xff, 0xff, 0xff].chain(io::repeat(0x61));
+
+    match read_str_owned(&mut rd, 1024) {
+        Err(Error::LengthLimitExceeded {
+            len: u32::MAX,
+            max_len: 1024,
+        }) => (),
+        other => panic!("unexpected result: {other:?}"),
+    }
+
+    // Only the header was consumed.
+    let mut next = [0; 1];
+    rd.read_exact(&mut next).unwrap();
+    assert_eq!([0x61], next);
+}
+
+#[test]
+fn pass_read_bin_owned() {
+    let buf = [0xc4, 0x03, 0x00, 0xc3, 0x28];
+
+    assert_eq!(
+        vec![0x00, 0xc3, 0x28],
+        read_bin_owned(&mut &buf[..], 3).unwrap()
+    );
+}
+
+#[test]
+fn fail_read_bin_owned_over_limit_on_unbounded_reader() {
+    // bin32 declaring 4 GiB - 1, followed by an endless stream of zeros
+    let mut rd = [0xc6, 0xff, 0xff, 0xff, 0xff].chain(io::repeat(0));
+
+    match read_bin_owned(&mut rd, 1 << 20) {
+        Err(Error::LengthLimitExceeded { len: u32::MAX, .. }) => (),
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn pass_read_bin_cow_borrows_from_slice() {
+    let buf = [0xc4, 0x03, 0x01, 0x02, 0x03, 0xc0];
+    let mut rd = &buf[..];
+
+    match read_bin_cow(&mut rd).unwrap() {
+        Cow::Borrowed(data) => {
+            assert_eq!([0x01, 0x02, 0x03], data);
+            assert!(std::ptr::eq(&buf[2], &data[0]));
+        }
+        Cow::Owned(..) => panic!("expected a borrowed slice"),
+    }
+    assert_eq!([0xc0], rd);
+}
+
+#[test]
+fn pass_read_bin_cow_owns_from_stream() {
+    let buf = [0xc5, 0x00, 0x02, 0xca, 0xfe];
+    let mut rd = ReadReader::new(io::Cursor::new(buf.to_vec()));
+
+    match read_bin_cow(&mut rd).unwrap() {
+        Cow::Owned(data) => assert_eq!(vec![0xca, 0xfe], data),
+        Cow::Borrowed(..) => panic!("expected an owned buffer"),
+    }
+}
+
+#[test]
+fn fail_read_bin_cow_truncated() {
+    let buf = [0xc4, 0x03, 0x01];
+
+    match read_bin_cow(&mut &buf[..]) {
+        Err(ValueReadError::InvalidDataRead(..)) => (),
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn pass_read_f64_loosely_from_f32() {
+    let buf = [0xca, 0x3f, 0xc0, 0x00, 0x00];
+
+    assert_eq!(1.5f64, read_f64_loosely(&mut &buf[..]).unwrap());
+}
+
+#[test]
+fn pass_read_f64_loosely_widens_exactly() {
+    let val = 0.1f32;
+    let mut buf = Vec::new();
+    rmp::encode::write_f32(&mut buf, val).unwrap();
+
+    let widened = read_f64_loosely(&mut &buf[..]).unwrap();
+
+    assert_eq!(f64::from(val), widened);
+    assert_eq!(val, widened as f32);
+}
+
+#[test]
+fn pass_read_f64_loosely_from_f64() {
+    let val = 0.1f64;
+    let mut buf = Vec::new();
+    rmp::encode::write_f64(&mut buf, val).unwrap();
+
+    assert_eq!(
+        val.to_bits(),
+        read_f64_loosely(&mut &buf[..]).unwrap().to_bits()
+    );
+}
+
+#[test]
+fn fail_read_f64_loosely_from_int() {
+    let buf = [0x01];
+
+    match read_f64_loosely(&mut &buf[..]) {
+        Err(ValueReadError::TypeMismatch(Marker::FixPos(1))) => (),
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn pass_read_decimal_ext() {
+    // -12345.678 as scale 3, mantissa -12345678 in two's complement, ext type 1
+    let buf = [
+        0xc7, 0x08, 0x01, 0x00, 0x00, 0x00, 0x03, 0xff, 0x43, 0x9e, 0xb2, 0xc0,
+    ];
+    let mut rd = &buf[..];
+
+    let (scale, mantissa) = read_decimal_ext(&mut rd, 1).unwrap();
+    assert_eq!(3, scale);
+    assert_eq!(-12345678, i32::from_be_bytes(mantissa.try_into().unwrap()));
+    assert_eq!([0xc0], rd);
+}
+
+#[test]
+fn pass_read_decimal_ext_negative_scale_empty_mantissa() {
+    let buf = [0xd6, 0x05, 0xff, 0xff, 0xff, 0xfe];
+
+    assert_eq!((-2, vec![]), read_decimal_ext(&mut &buf[..], 5).unwrap());
+}
+
+#[test]
+fn fail_read_decimal_ext_wrong_type() {
+    let buf = [0xd6, 0x05, 0x00, 0x00, 0x00, 0x02, 0xc0];
+    let mut rd = &buf[..];
+
+    match read_decimal_ext(&mut rd, 6) {
+        Err(Error::Uncategorized(..)) => (),
+        other => panic!("unexpected result: {other:?}"),
+    }
+    assert_eq!([0xc0], rd);
+}
+
+#[test]
+fn fail_read_decimal_ext_short_data() {
+    let buf = [0xd5, 0x05, 0x00, 0x02];
+
+    match read_decimal_ext(&mut &buf[..], 5) {
+        Err(Error::LengthMismatch {
+            expected: 4,
+            actual: 2,
+        }) => (),
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn fail_read_decimal_ext_truncated() {
+    let buf = [0xc7, 0x08, 0x01, 0x00, 0x00];
+
+    match read_decimal_ext(&mut &buf[..], 1) {
+        Err(Error::InvalidDataRead(..)) => (),
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn pass_ext_meta_new() {
+    let meta = ExtMeta::new(5, 16);
+
+    assert_eq!(5, meta.typeid);
+    assert_eq!(16, meta.size);
+    assert_eq!(16, meta.data_len());
+    assert!(!meta.is_timestamp());
+}
+
+#[test]
+fn pass_ext_meta_is_timestamp() {
+    // timestamp 32: fixext4 with type -1
+    let buf = [0xd6, 0xff, 0x00, 0x00, 0x00, 0x01];
+    let meta = decode::read_ext_meta(&mut &buf[..]).unwrap();
+
+    assert!(meta.is_timestamp());
+    assert_eq!(4, meta.data_len());
+    assert_eq!(ExtMeta::new(-1, 4), meta);
+}
+
+#[test]
+fn pass_ext_meta_data_len_ext8() {
+    let buf = [0xc7, 0x03, 0x02, 0x61, 0x62, 0x63];
+    let meta = decode::read_ext_meta(&mut &buf[..]).unwrap();
+
+    assert!(!meta.is_timestamp());
+    assert_eq!(3, meta.data_len());
+}
+
+#[test]
+fn fail_read_fixext4_from_fixext8() {
+    let buf = [0xd7, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00];
+    let mut rd = &buf[..];
+
+    match decode::read_fixext4(&mut rd) {
+        Err(ValueReadError::TypeMismatch(Marker::FixExt8)) => (),
+        other => panic!("unexpected result: {other:?}"),
+    }
+    // Only the marker was consumed.
+    assert_eq!(buf[1..], rd[..]);
+}
+
+#[test]
+fn pass_read_str_len_with_class() {
+    let encodings: [(&[u8], StrClass); 4] = [
+        (&[0xa2, 0x68, 0x69], StrClass::Fix),
+        (&[0xd9, 0x02, 0x68, 0x69], StrClass::Str8),
+        (&[0xda, 0x00, 0x02, 0x68, 0x69], StrClass::Str16),
+        (&[0xdb, 0x00, 0x00, 0x00, 0x02, 0x68, 0x69], StrClass::Str32),
+    ];
+
+    for (buf, class) in encodings {
+        let mut rd = buf;
+        assert_eq!((2, class), read_str_len_with_class(&mut rd).unwrap());
+        assert_eq!(b"hi", rd);
+    }
+}
+
+#[test]
+fn pass_str_class_minimal() {
+    assert_eq!(StrClass::Fix, StrClass::minimal(31));
+    assert_eq!(StrClass::Str8, StrClass::minimal(32));
+    assert_eq!(StrClass::Str16, StrClass::minimal(256));
+    assert_eq!(StrClass::Str32, StrClass::minimal(65536));
+}
+
+#[test]
+fn fail_read_str_len_with_class_not_a_str() {
+    match read_str_len_with_class(&mut &[0xc4, 0x00][..]) {
+        Err(ValueReadError::TypeMismatch(Marker::Bin8)) => (),
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn pass_read_u32_array_mixed_markers() {
+    // [0, i8 5, u16 300, u32 4294967295, i64 7]
+    let buf = [
+        0x95, 0x00, 0xd0, 0x05, 0xcd, 0x01, 0x2c, 0xce, 0xff, 0xff, 0xff, 0xff, 0xd3, 0, 0, 0, 0,
+        0, 0, 0, 0x07,
+    ];
+    let mut out = vec![9];
+
+    read_u32_array(&mut &buf[..], &mut out).unwrap();
+    assert_eq!([9, 0, 5, 300, u32::MAX, 7], out[..]);
+}
+
+#[test]
+fn fail_read_u32_loosely_out_of_range() {
+    match read_u32_loosely(&mut &[0xcf, 0, 0, 0, 0x01, 0, 0, 0, 0][..]) {
+        Err(ValueReadError::TypeMismatch(Marker::U64)) => (),
+        other => panic!("unexpected result: {other:?}"),
+    }
+    match read_u32_loosely(&mut &[0xff][..]) {
+        Err(ValueReadError::TypeMismatch(Marker::FixNeg(-1))) => (),
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn fail_read_u32_array_bogus_len() {
+    // Array32 claiming 4294967295 elements, followed by just one
+    let buf = [0xdd, 0xff, 0xff, 0xff, 0xff, 0x01];
+    let mut out = Vec::new();
+
+    assert!(read_u32_array(&mut &buf[..], &mut out).is_err());
+    assert_eq!([1], out[..]);
+}
+
+#[test]
+fn pass_read_ext_array_uuid() {
+    let uuid = [
+        0x67, 0xe5, 0x50, 0x44, 0x10, 0xb1, 0x42, 0x6f, 0x92, 0x47, 0xbb, 0x68, 0x0e, 0x5f, 0xe0,
+        0xc8,
+    ];
+    let mut buf = vec![0xd8, 0x25];
+    buf.extend_from_slice(&uuid);
+    buf.push(0xc0);
+    let mut rd = &buf[..];
+
+    let out: [u8; 16] = read_ext_array(&mut rd, 0x25).unwrap();
+    assert_eq!(uuid, out);
+    assert_eq!([0xc0], rd);
+}
+
+#[test]
+fn pass_read_ext_array_ext8() {
+    // ext 8 with 3 bytes of type 1
+    let buf = [0xc7, 0x03, 0x01, 0x0a, 0x0b, 0x0c];
+
+    assert_eq!(
+        [0x0a, 0x0b, 0x0c],
+        read_ext_array::<_, 3>(&mut &buf[..], 1).unwrap()
+    );
+}
+
+#[test]
+fn fail_read_ext_array_mismatch() {
+    let buf = [0xd6, 0x25, 0x00, 0x00, 0x00, 0x00];
+
+    match read_ext_array::<_, 16>(&mut &buf[..], 0x25) {
+        Err(ValueReadError::TypeMismatch(Marker::FixExt4)) => (),
+        other => panic!("unexpected result: {other:?}"),
+    }
+    match read_ext_array::<_, 4>(&mut &buf[..], 0x26) {
+        Err(ValueReadError::TypeMismatch(Marker::FixExt4)) => (),
+        other => panic!("unexpected result: {other:?}"),
+    }
+    match read_ext_array::<_, 4>(&mut &[0xc4, 0x00][..], 0x25) {
+        Err(ValueReadError::TypeMismatch(Marker::Bin8)) => (),
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn pass_ext_reader_u32_le() {
+    // fixext 4 of type 9 holding 0x12345678 little-endian
+    let buf = [0xd6, 0x09, 0x78, 0x56, 0x34, 0x12];
+    let mut rd = &buf[..];
+    let meta = decode::read_ext_meta(&mut rd).unwrap();
+    assert_eq!(9, meta.typeid);
+
+    let mut ext = ExtReader::new(&mut rd, &meta);
+    assert_eq!(4, ext.remaining());
+    assert_eq!(0x1234_5678, ext.read_u32(Endian::Little).unwrap());
+    assert_eq!(0, ext.remaining());
+}
+
+#[test]
+fn pass_ext_reader_mixed_order() {
+    // ext 8 of type 2 holding a big-endian u16 and a little-endian i16
+    let buf = [0xc7, 0x04, 0x02, 0x01, 0x02, 0xfe, 0xff];
+    let mut rd = &buf[..];
+    let meta = decode::read_ext_meta(&mut rd).unwrap();
+
+    let mut ext = ExtReader::new(&mut rd, &meta);
+    assert_eq!(0x0102, ext.read_u16(Endian::Big).unwrap());
+    assert_eq!(-2, ext.read_i16(Endian::Little).unwrap());
+}
+
+#[test]
+fn fail_ext_reader_past_declared_size() {
+    // fixext 2 followed by an unrelated value
+    let buf = [0xd5, 0x01, 0xaa, 0xbb, 0x00, 0x00];
+    let mut rd = &buf[..];
+    let meta = decode::read_ext_meta(&mut rd).unwrap();
+
+    let mut ext = ExtReader::new(&mut rd, &meta);
+    assert_eq!(0xaa, ext.read_u8().unwrap());
+    match ext.read_u32(Endian::Big) {
+        Err(Error::LengthMismatch {
+            expected: 4,
+            actual: 1,
+        }) => (),
+        other => panic!("unexpected result: {other:?}"),
+    }
+    assert_eq!(-69, ext.read_i8().unwrap());
+    assert_eq!([0x00, 0x00], rd);
+}
+
+#[test]
+fn pass_ext_reader_u64_either_endian() {
+    // fixext 8 of type 4
+    let buf = [0xd7, 0x04, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08];
+    let meta = decode::read_ext_meta(&mut &buf[..]).unwrap();
+
+    let mut rd = &buf[2..];
+    let mut ext = ExtReader::new(&mut rd, &meta);
+    assert_eq!(0x0102_0304_0506_0708, ext.read_u64(Endian::Big).unwrap());
+
+    let mut rd = &buf[2..];
+    let mut ext = ExtReader::new(&mut rd, &meta);
+    assert_eq!(0x0807_0605_0403_0201, ext.read_u64(Endian::Little).unwrap());
+}
+
+#[test]
+fn pass_read_integer_typed_each_width() {
+    use messpack_serde::decode::value::Integer;
+
+    let cases: &[(&[u8], Integer, IntWidth)] = &[
+        (&[0x07], Integer::from(7), IntWidth::FixPos),
+        (&[0xfb], Integer::from(-5), IntWidth::FixNeg),
+        (&[0xcc, 0x07], Integer::from(7), IntWidth::U8),
+        (&[0xcd, 0x01, 0x00], Integer::from(256), IntWidth::U16),
+        (
+            &[0xce, 0x00, 0x00, 0x00, 0x07],
+            Integer::from(7),
+            IntWidth::U32,
+        ),
+        (
+            &[0xcf, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff],
+            Integer::from(u64::MAX),
+            IntWidth::U64,
+        ),
+        (&[0xd0, 0x07], Integer::from(7), IntWidth::I8),
+        (&[0xd1, 0xff, 0x00], Integer::from(-256), IntWidth::I16),
+        (
+            &[0xd2, 0xff, 0xff, 0xff, 0xff],
+            Integer::from(-1),
+            IntWidth::I32,
+        ),
+        (
+            &[0xd3, 0x80, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00],
+            Integer::from(i64::MIN),
+            IntWidth::I64,
+        ),
+    ];
+    for &(buf, val, width) in cases {
+        let mut rd = buf;
+        assert_eq!((val, width), read_integer_typed(&mut rd).unwrap());
+        assert!(rd.is_empty());
+    }
+}
+
+#[test]
+fn fail_read_integer_typed_not_an_integer() {
+    match read_integer_typed(&mut &[0xca, 0x00, 0x00, 0x00, 0x00][..]) {
+        Err(ValueReadError::TypeMismatch(Marker::F32)) => (),
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn pass_read_integer_any_marker() {
+    // 7, -7, 200 as uint 8, -200 as int 16, 7 as int 64, then u64::MAX
+    let buf = [
+        0x07, 0xf9, 0xcc, 0xc8, 0xd1, 0xff, 0x38, 0xd3, 0, 0, 0, 0, 0, 0, 0, 0x07, 0xcf, 0xff,
+        0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+    ];
+    let mut rd = &buf[..];
+
+    for expected in [7, -7, 200, -200, 7] {
+        assert_eq!(Some(expected), read_integer(&mut rd).unwrap().as_i64());
+    }
+    // Too large for an i64, so it stays unsigned.
+    let val = read_integer(&mut rd).unwrap();
+    assert!(val.is_u64());
+    assert_eq!(Some(u64::MAX), val.as_u64());
+    assert_eq!(None, val.as_i64());
+    assert!(rd.is_empty());
+
+    // Non-negative values are unsigned even when written signed.
+    assert!(read_integer(&mut &[0xd0, 0x05][..]).unwrap().is_u64());
+    assert_eq!(None, read_integer(&mut &[0xff][..]).unwrap().as_u64());
+}
+
+#[test]
+fn fail_read_integer_not_an_integer() {
+    match read_integer(&mut &[0xa1, 0x31][..]) {
+        Err(ValueReadError::TypeMismatch(Marker::FixStr(1))) => (),
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn pass_fixext4_keeps_wire_order() {
+    let mut buf = Vec::new();
+    rmp::encode::write_ext_meta(&mut buf, 4, 0x11).unwrap();
+    buf.extend_from_slice(&[0xde, 0xad, 0xbe, 0xef]);
+    assert_eq!(Marker::FixExt4, Marker::from_u8(buf[0]));
+
+    assert_eq!(
+        (0x11, [0xde, 0xad, 0xbe, 0xef]),
+        decode::read_fixext4(&mut &buf[..]).unwrap()
+    );
+    assert_eq!(
+        [0xde, 0xad, 0xbe, 0xef],
+        read_ext_array::<_, 4>(&mut &buf[..], 0x11).unwrap()
+    );
+}
+
+#[test]
+fn fail_fixext4_wrong_marker() {
+    // fixext 8
+    let buf = [0xd7, 0x11, 0, 0, 0, 0, 0, 0, 0, 0];
+
+    match decode::read_fixext4(&mut &buf[..]) {
+        Err(ValueReadError::TypeMismatch(Marker::FixExt8)) => (),
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn fail_fixext8_and_fixext16_wrong_marker() {
+    // fixext 4
+    let buf = [0xd6, 0x01, 0, 0, 0, 0];
+
+    match decode::read_fixext8(&mut &buf[..]) {
+        Err(ValueReadError::TypeMismatch(Marker::FixExt4)) => (),
+        other => panic!("unexpected result: {other:?}"),
+    }
+    match decode::read_fixext16(&mut &buf[..]) {
+        Err(ValueReadError::TypeMismatch(Marker::FixExt4)) => (),
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn fail_fixext8_and_fixext16_truncated() {
+    // fixext 8 and fixext 16 of type 1, one byte short each
+    let buf8 = [0xd7, 0x01, 1, 2, 3, 4, 5, 6, 7];
+    let buf16 = [
+        0xd8, 0x01, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15,
+    ];
+
+    match decode::read_fixext8(&mut &buf8[..]) {
+        Err(ValueReadError::InvalidDataRead(ref err))
+            if err.kind() == io::ErrorKind::UnexpectedEof => {}
+        other => panic!("unexpected result: {other:?}"),
+    }
+    match decode::read_fixext16(&mut &buf16[..]) {
+        Err(ValueReadError::InvalidDataRead(ref err))
+            if err.kind() == io::ErrorKind::UnexpectedEof => {}
+        other => panic!("unexpected result: {other:?}"),
+    }
+
+    // The complete values still decode.
+    let mut buf = buf16.to_vec();
+    buf.push(16);
+    let (ty, data) = decode::read_fixext16(&mut &buf[..]).unwrap();
+    assert_eq!(1, ty);
+    assert_eq!(16, data[15]);
+}
+
+#[test]
+fn fail_read_ext_meta_non_ext_marker() {
+    // "hi", then u32 1
+    let str_buf = [0xa2, 0x68, 0x69];
+    let u32_buf = [0xce, 0x00, 0x00, 0x00, 0x01];
+
+    match decode::read_ext_meta(&mut &str_buf[..]) {
+        Err(ValueReadError::TypeMismatch(Marker::FixStr(2))) => (),
+        other => panic!("unexpected result: {other:?}"),
+    }
+    match decode::read_ext_meta(&mut &u32_buf[..]) {
+        Err(ValueReadError::TypeMismatch(Marker::U32)) => (),
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn fail_read_ext_meta_missing_typeid() {
+    // fixext 4 and ext 8 of length 3, both cut off before the type id
+    let bufs: [&[u8]; 2] = [&[0xd6], &[0xc7, 0x03]];
+
+    for buf in bufs {
+        match decode::read_ext_meta(&mut &buf[..]) {
+            Err(ValueReadError::InvalidDataRead(ref err))
+                if err.kind() == io::ErrorKind::UnexpectedEof => {}
+            other => panic!("unexpected result: {other:?}"),
+        }
+    }
+}
+
+#[test]
+fn pass_read_ext_data_ext16() {
+    // ext 16 of type 9 with 300 bytes of data
+    let mut buf = vec![0xc8, 0x01, 0x2c, 0x09];
+    buf.extend((0..300).map(|i| i as u8));
+    let mut rd = &buf[..];
+
+    let meta = decode::read_ext_meta(&mut rd).unwrap();
+    assert_eq!(ExtMeta::new(9, 300), meta);
+
+    let mut out = vec![0u8; 512];
+    read_ext_data(&mut rd, meta.size, &mut out).unwrap();
+    assert_eq!(buf[4..], out[..300]);
+    assert!(out[300..].iter().all(|&b| b == 0));
+    assert!(rd.is_empty());
+}
+
+#[test]
+fn fail_read_ext_data_buffer_too_small() {
+    let buf = [0xc7, 0x03, 0x07, 0x61, 0x62, 0x63];
+    let mut rd = &buf[..];
+    let meta = decode::read_ext_meta(&mut rd).unwrap();
+    let mut out = [0u8; 2];
+
+    match read_ext_data(&mut rd, meta.size, &mut out) {
+        Err(Error::BufferSizeTooSmall {
+            required: 3,
+            available: Some(2),
+        }) => (),
+        other => panic!("unexpected result: {other:?}"),
+    }
+    // Nothing past the header was consumed.
+    assert_eq!(buf[3..], rd[..]);
+}
+
+#[test]
+fn fail_read_ext_data_truncated() {
+    // ext 8 declaring 3 bytes but carrying 2
+    let buf = [0xc7, 0x03, 0x07, 0x61, 0x62];
+    let mut rd = &buf[..];
+    let meta = decode::read_ext_meta(&mut rd).unwrap();
+    let mut out = [0u8; 8];
+
+    match read_ext_data(&mut rd, meta.size, &mut out) {
+        Err(Error::InvalidDataRead(ref err)) if err.kind() == io::ErrorKind::UnexpectedEof => {}
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn pass_read_bin_into_buffer() {
+    // bin 8 of 3 bytes, then bin 16 of 300 bytes
+    let mut buf = vec![0xc4, 0x03, 0x01, 0x02, 0x03, 0xc5, 0x01, 0x2c];
+    buf.extend(std::iter::repeat_n(0xab, 300));
+    let mut rd = ReadReader::new(&buf[..]);
+    let mut out = [0u8; 512];
+
+    assert_eq!([1, 2, 3], read_bin(&mut rd, &mut out).unwrap());
+    let data = read_bin(&mut rd, &mut out).unwrap();
+    assert_eq!(300, data.len());
+    assert!(data.iter().all(|&b| b == 0xab));
+}
+
+#[test]
+fn fail_read_bin_buffer_too_small() {
+    let buf = [0xc4, 0x03, 0x01, 0x02, 0x03];
+    let mut rd = &buf[..];
+    let mut out = [0u8; 2];
+
+    match read_bin(&mut rd, &mut out) {
+        Err(Error::BufferSizeTooSmall {
+            required: 3,
+            available: Some(2),
+        }) => (),
+        other => panic!("unexpected result: {other:?}"),
+    }
+    // Only the header was consumed.
+    assert_eq!(buf[2..], rd[..]);
+}
+
+#[test]
+fn fail_read_bin_truncated_or_not_bin() {
+    let mut out = [0u8; 8];
+
+    match read_bin(&mut &[0xc4, 0x03, 0x01][..], &mut out) {
+        Err(Error::InvalidDataRead(ref err)) if err.kind() == io::ErrorKind::UnexpectedEof => {}
+        other => panic!("unexpected result: {other:?}"),
+    }
+    match read_bin(&mut &[0xa1, 0x61][..], &mut out) {
+        Err(Error::TypeMismatch(Marker::FixStr(1))) => (),
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn pass_read_str_ref_leaves_rest() {
+    // str 8 "abc", then nil
+    let buf = [0xd9, 0x03, 0x61, 0x62, 0x63, 0xc0];
+
+    assert_eq!(b"abc", read_str_ref(&buf).unwrap());
+    assert_eq!(b"", read_str_ref(&[0xa0]).unwrap());
+}
+
+#[test]
+fn fail_read_str_ref_bin_marker() {
+    let buf = [0xc4, 0x02, 0x68, 0x69];
+
+    match read_str_ref(&buf) {
+        Err(Error::TypeMismatch(Marker::Bin8)) => (),
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn fail_read_str_ref_short_slice() {
+    // fixstr of 5 bytes with only 3 present
+    let buf = [0xa5, 0x61, 0x62, 0x63];
+
+    match read_str_ref(&buf) {
+        Err(Error::InvalidDataRead(ref err)) if err.kind() == io::ErrorKind::UnexpectedEof => {
+            assert_eq!("string of 5 bytes is missing 2 of them", err.to_string());
+        }
+        other => panic!("unexpected result: {other:?}"),
+    }
+    // A truncated header is reported the same way.
+    match read_str_ref(&[0xda, 0x01]) {
+        Err(Error::InvalidDataRead(ref err)) if err.kind() == io::ErrorKind::UnexpectedEof => {}
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn pass_rmp_errors_box_and_chain_to_io() {
+    fn read_len(buf: &[u8]) -> Result<u32, Box<dyn std::error::Error>> {
+        Ok(decode::read_array_len(&mut &buf[..])?)
+    }
+
+    // array 16 cut off inside its length
+    let err = read_len(&[0xdc, 0x00]).unwrap_err();
+    let source = err.source().unwrap().downcast_ref::<io::Error>().unwrap();
+    assert_eq!(io::ErrorKind::UnexpectedEof, source.kind());
+    assert!(read_len(&[0x90]).is_ok());
+}
+
+#[test]
+fn pass_rmp_errors_through_decode_error_display() {
+    // Marker errors have no `Display` of their own, so they go through `decode::Error`.
+    let err = Error::from(decode::read_marker(&mut &[][..]).unwrap_err());
+    assert!(err.to_string().starts_with("IO error while reading marker"));
+
+    let err = Error::from(decode::read_array_len(&mut &[0xa1][..]).unwrap_err());
+    assert_eq!("wrong msgpack marker FixStr(1)", err.to_string());
+
+    let mut out = [0u8; 4];
+    let err = Error::from(decode::read_str(&mut &[0xa1, 0xff][..], &mut out).unwrap_err());
+    assert!(err
+        .to_string()
+        .starts_with("string found to be invalid utf8: "));
+    let source = std::error::Error::source(&err).unwrap();
+    assert!(source.is::<std::str::Utf8Error>());
+}
+
+#[test]
+fn pass_skip_value_nested() {
+    // [1, ["a", "b"], {"x": 2}], then 7
+    let buf = [
+        0x93, 0x01, 0x92, 0xa1, 0x61, 0xa1, 0x62, 0x81, 0xa1, 0x78, 0x02, 0x07,
+    ];
+    let mut rd = &buf[..];
+
+    skip_value(&mut rd).unwrap();
+    assert_eq!([0x07], rd);
+    skip_value(&mut rd).unwrap();
+    assert!(rd.is_empty());
+}
+
+#[test]
+fn pass_skip_value_every_payload() {
+    // u64, f32, str 8, bin 16, fixext 4, ext 8 and an empty map, then nil
+    let mut buf = vec![0xcf, 0, 0, 0, 0, 0, 0, 0, 1, 0xca, 0x3f, 0x80, 0x00, 0x00];
+    buf.extend([0xd9, 0x02, 0x68, 0x69, 0xc5, 0x01, 0x00]);
+    buf.extend(std::iter::repeat_n(0xab, 256));
+    buf.extend([
+        0xd6, 0x01, 1, 2, 3, 4, 0xc7, 0x02, 0x05, 0xff, 0xff, 0x80, 0xc0,
+    ]);
+    let mut rd = ReadReader::new(&buf[..]);
+
+    for _ in 0..7 {
+        skip_value(&mut rd).unwrap();
+    }
+    assert!(decode::read_nil(&mut rd).is_ok());
+    match skip_value(&mut rd) {
+        Err(ValueReadError::InvalidMarkerRead(..)) => (),
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn fail_skip_value_truncated() {
+    // [1, 2] missing its last element, a str 8 missing a byte, and an ext 8 missing its type id
+    let bufs: [&[u8]; 3] = [&[0x92, 0x01], &[0xd9, 0x02, 0x68], &[0xc7, 0x00]];
+
+    for buf in bufs {
+        match skip_value(&mut &buf[..]) {
+            Err(ValueReadError::InvalidDataRead(ref err))
+                if err.kind() == io::ErrorKind::UnexpectedEof => {}
+            other => panic!("unexpected result: {other:?}"),
+        }
+    }
+}
+
+#[test]
+fn fail_skip_value_reserved() {
+    // [0xc1]
+    match skip_value(&mut &[0x91, 0xc1][..]) {
+        Err(ValueReadError::TypeMismatch(Marker::Reserved)) => (),
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn pass_read_array_raw_slices() {
+    // [300, ["a", nil], {"k": -1}], then a trailing nil
+    let buf = [
+        0x93, 0xcd, 0x01, 0x2c, 0x92, 0xa1, 0x61, 0xc0, 0x81, 0xa1, 0x6b, 0xff, 0xc0,
+    ];
+
+    let slices = read_array_raw_slices(&buf).unwrap();
+    assert_eq!(vec![&buf[1..4], &buf[4..8], &buf[8..12]], slices);
+
+    assert_eq!(
+        300,
+        decode::read_int::<u16, _>(&mut &slices[0][..]).unwrap()
+    );
+    let elem: (String, Option<u8>) = messpack_serde::from_slice(slices[1]).unwrap();
+    assert_eq!(("a".to_string(), None), elem);
+    let elem: std::collections::BTreeMap<String, i8> =
+        messpack_serde::from_slice(slices[2]).unwrap();
+    assert_eq!(Some(&-1), elem.get("k"));
+}
+
+#[test]
+fn fail_read_array_raw_slices() {
+    match read_array_raw_slices(&[0x81, 0x01, 0x02]) {
+        Err(ValueReadError::TypeMismatch(Marker::FixMap(1))) => (),
+        other => panic!("unexpected result: {other:?}"),
+    }
+    // [1, "ab"] with the string cut short
+    match read_array_raw_slices(&[0x92, 0x01, 0xa2, 0x61]) {
+        Err(ValueReadError::InvalidDataRead(ref err))
+            if err.kind() == io::ErrorKind::UnexpectedEof => {}
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn fail_read_ext_owned_over_limit() {
+    // ext 32 of type 1 claiming 0xffffffff bytes, with no data at all
+    let buf = [0xc9, 0xff, 0xff, 0xff, 0xff, 0x01];
+    let mut rd = &buf[..];
+
+    match read_ext_owned(&mut rd, 1 << 20) {
+        Err(Error::LengthLimitExceeded {
+            len: u32::MAX,
+            max_len: 0x0010_0000,
+        }) => (),
+        other => panic!("unexpected result: {other:?}"),
+    }
+    assert!(rd.is_empty());
+
+    let buf = [0xd5, 0x02, 0xab, 0xcd];
+    assert_eq!(
+        (2, vec![0xab, 0xcd]),
+        read_ext_owned(&mut &buf[..], 2).unwrap()
+    );
+}
+
+#[test]
+fn fail_every_ext_marker_missing_typeid() {
+    // Each ext header with a valid size of 1 where needed, cut off before the type id.
+    let bufs: [&[u8]; 8] = [
+        &[0xd4],
+        &[0xd5],
+        &[0xd6],
+        &[0xd7],
+        &[0xd8],
+        &[0xc7, 0x01],
+        &[0xc8, 0x00, 0x01],
+        &[0xc9, 0x00, 0x00, 0x00, 0x01],
+    ];
+
+    for buf in bufs {
+        match decode::read_ext_meta(&mut &buf[..]) {
+            Err(ValueReadError::InvalidDataRead(ref err))
+                if err.kind() == io::ErrorKind::UnexpectedEof => {}
+            other => panic!("unexpected result for {buf:02x?}: {other:?}"),
+        }
+        match read_ext_owned(&mut &buf[..], 16) {
+            Err(Error::InvalidDataRead(ref err)) if err.kind() == io::ErrorKind::UnexpectedEof => {}
+            other => panic!("unexpected result for {buf:02x?}: {other:?}"),
+        }
+        match read_ext_array::<_, 1>(&mut &buf[..], 0) {
+            Err(ValueReadError::InvalidDataRead(ref err))
+                if err.kind() == io::ErrorKind::UnexpectedEof => {}
+            other => panic!("unexpected result for {buf:02x?}: {other:?}"),
+        }
+        match messpack_serde::from_slice::<rmpv::Value>(buf) {
+            Err(Error::InvalidDataRead(ref err)) if err.kind() == io::ErrorKind::UnexpectedEof => {}
+            other => panic!("unexpected result for {buf:02x?}: {other:?}"),
+        }
+    }
+}
+
+#[test]
+fn fail_rmp_read_str_buffer_too_small_converts_structured() {
+    // "hello" into a 2-byte buffer
+    let buf = [0xa5, 0x68, 0x65, 0x6c, 0x6c, 0x6f];
+    let mut out = [0u8; 2];
+
+    let err = decode::read_str(&mut &buf[..], &mut out).unwrap_err();
+    match Error::from(err) {
+        Error::BufferSizeTooSmall {
+            required: 5,
+            available: None,
+        } => (),
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
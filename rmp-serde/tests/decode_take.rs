@@ -0,0 +1,101 @@
+use rmp::decode::{DecodeStringError, NumValueReadError, ValueReadError};
+use rmp::Marker;
+
+use messpack_serde::decode::{
+    take_array_len, take_bin, take_bool, take_f64, take_i64, take_map_len, take_nil, take_str,
+    take_u64,
+};
+
+#[test]
+fn pass_take_chained_across_buffer() {
+    // [{"name": "a", "id": 300}, -7, true, nil, 1.5, bin(0x01 0x02)]
+    let buf = [
+        0x96, 0x82, 0xa4, 0x6e, 0x61, 0x6d, 0x65, 0xa1, 0x61, 0xa2, 0x69, 0x64, 0xcd, 0x01, 0x2c,
+        0xf9, 0xc3, 0xc0, 0xcb, 0x3f, 0xf8, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xc4, 0x02, 0x01,
+        0x02, 0xff,
+    ];
+
+    let (len, rest) = take_array_len(&buf).unwrap();
+    assert_eq!(6, len);
+    let (len, rest) = take_map_len(rest).unwrap();
+    assert_eq!(2, len);
+    let (key, rest) = take_str(rest).unwrap();
+    assert_eq!("name", key);
+    let (val, rest) = take_str(rest).unwrap();
+    assert_eq!("a", val);
+    let (key, rest) = take_str(rest).unwrap();
+    assert_eq!("id", key);
+    let (val, rest) = take_u64(rest).unwrap();
+    assert_eq!(300, val);
+    let (val, rest) = take_i64(rest).unwrap();
+    assert_eq!(-7, val);
+    let (val, rest) = take_bool(rest).unwrap();
+    assert!(val);
+    let rest = take_nil(rest).unwrap();
+    let (val, rest) = take_f64(rest).unwrap();
+    assert_eq!(1.5, val);
+    let (val, rest) = take_bin(rest).unwrap();
+    assert_eq!([0x01, 0x02], val);
+
+    assert_eq!([0xff], rest);
+}
+
+#[test]
+fn pass_take_str_borrows_from_buffer() {
+    let buf = [0xa2, 0x68, 0x69];
+
+    let (val, rest) = take_str(&buf).unwrap();
+
+    assert_eq!(buf[1..].as_ptr(), val.as_ptr());
+    assert!(rest.is_empty());
+}
+
+#[test]
+fn fail_take_str_truncated() {
+    let buf = [0xa5, 0x68, 0x69];
+
+    match take_str(&buf) {
+        Err(DecodeStringError::BufferSizeTooSmall(5)) => (),
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn fail_take_str_invalid_utf8() {
+    let buf = [0xa2, 0xc3, 0x28];
+
+    match take_str(&buf) {
+        Err(DecodeStringError::InvalidUtf8(data, _)) => assert_eq!([0xc3, 0x28], data),
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn fail_take_bin_truncated() {
+    let buf = [0xc4, 0x03, 0x01];
+
+    match take_bin(&buf) {
+        Err(ValueReadError::InvalidDataRead(..)) => (),
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn fail_take_u64_from_negative() {
+    let buf = [0xff];
+
+    match take_u64(&buf) {
+        Err(NumValueReadError::OutOfRange) => (),
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn fail_take_array_len_from_map() {
+    let buf = [0x80];
+
+    match take_array_len(&buf) {
+        Err(ValueReadError::TypeMismatch(Marker::FixMap(0))) => (),
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
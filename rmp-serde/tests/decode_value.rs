@@ -0,0 +1,777 @@
+use messpack_serde::decode::read_array_with;
+use std::io::{Cursor, ErrorKind};
+
+use messpack_serde::decode::value::{
+    dispatch, read_all_lenient, read_map_expecting, read_optional_value, read_tagged, read_value,
+    read_value_checked, read_value_interned, read_value_spans, read_value_validated, transform,
+    InternedKey, InternedValue, Interner, Schema, SpanTree, Value,
+};
+use messpack_serde::decode::Error;
+use rmp::Marker;
+
+#[test]
+fn pass_read_optional_value_nil() {
+    let buf = [0xc0, 0x01];
+    let mut rd = &buf[..];
+
+    assert_eq!(None, read_optional_value(&mut rd).unwrap());
+    assert_eq!([0x01], rd);
+}
+
+#[test]
+fn pass_read_optional_value_nested() {
+    // {"a": [1, nil]}
+    let buf = [0x81, 0xa1, 0x61, 0x92, 0x01, 0xc0];
+    let mut rd = &buf[..];
+
+    let expected = Value::Map(vec![(
+        Value::from("a"),
+        Value::Array(vec![Value::from(1), Value::Nil]),
+    )]);
+    assert_eq!(Some(expected), read_optional_value(&mut rd).unwrap());
+    assert!(rd.is_empty());
+}
+
+#[test]
+fn pass_read_array_of_optional_values() {
+    // [nil, 5, "x"]
+    let buf = [0x93, 0xc0, 0x05, 0xa1, 0x78];
+
+    let vals = read_array_with(&mut &buf[..], read_optional_value).unwrap();
+
+    assert_eq!(
+        vec![None, Some(Value::from(5)), Some(Value::from("x"))],
+        vals
+    );
+}
+
+#[test]
+fn fail_read_optional_value_truncated() {
+    let buf = [0xcd, 0x01];
+
+    match read_optional_value(&mut &buf[..]) {
+        Err(Error::InvalidDataRead(..)) => (),
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn fail_read_optional_value_empty() {
+    let buf = [];
+
+    match read_optional_value(&mut &buf[..]) {
+        Err(Error::InvalidMarkerRead(..)) => (),
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn pass_read_optional_value_keeps_float_width() {
+    let f32_buf = [0xca, 0x3f, 0xc0, 0x00, 0x00];
+    let f64_buf = [0xcb, 0x3f, 0xf8, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00];
+
+    let f32_val = read_optional_value(&mut &f32_buf[..]).unwrap().unwrap();
+    let f64_val = read_optional_value(&mut &f64_buf[..]).unwrap().unwrap();
+    assert_eq!(Value::F32(1.5), f32_val);
+    assert_eq!(Value::F64(1.5), f64_val);
+
+    let mut buf = Vec::new();
+    rmpv::encode::write_value(&mut buf, &f32_val).unwrap();
+    assert_eq!(f32_buf[..], buf);
+
+    let mut buf = Vec::new();
+    rmpv::encode::write_value(&mut buf, &f64_val).unwrap();
+    assert_eq!(f64_buf[..], buf);
+}
+
+#[test]
+fn pass_deserialize_value_keeps_float_width() {
+    let f32_buf = [0xca, 0x3f, 0xc0, 0x00, 0x00];
+    let f64_buf = [0xcb, 0x3f, 0xf8, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00];
+
+    let f32_val: Value = messpack_serde::from_slice(&f32_buf).unwrap();
+    let f64_val: Value = messpack_serde::from_slice(&f64_buf).unwrap();
+    assert_eq!(Value::F32(1.5), f32_val);
+    assert_eq!(Value::F64(1.5), f64_val);
+
+    assert_eq!(5, messpack_serde::to_vec(&f32_val).unwrap().len());
+    assert_eq!(9, messpack_serde::to_vec(&f64_val).unwrap().len());
+}
+
+#[test]
+fn pass_read_value_spans_nested() {
+    // {"a": [1, 300]}
+    let buf = [0x81, 0xa1, 0x61, 0x92, 0x01, 0xcd, 0x01, 0x2c];
+    let mut rd = Cursor::new(&buf[..]);
+
+    let (val, spans) = read_value_spans(&mut rd).unwrap();
+
+    assert_eq!(
+        Value::Map(vec![(
+            Value::from("a"),
+            Value::Array(vec![Value::from(1), Value::from(300)])
+        )]),
+        val
+    );
+    assert_eq!(0..8, spans.span);
+    assert_eq!(2, spans.children.len());
+    assert_eq!(1..3, spans.children[0].span);
+
+    let array = &spans.children[1];
+    assert_eq!(3..8, array.span);
+    assert_eq!(
+        vec![
+            SpanTree {
+                span: 4..5,
+                children: Vec::new()
+            },
+            SpanTree {
+                span: 5..8,
+                children: Vec::new()
+            },
+        ],
+        array.children
+    );
+}
+
+#[test]
+fn pass_read_value_spans_absolute_positions() {
+    let buf = [0xc0, 0x91, 0xc3];
+    let mut rd = Cursor::new(&buf[..]);
+    rd.set_position(1);
+
+    let (val, spans) = read_value_spans(&mut rd).unwrap();
+
+    assert_eq!(Value::Array(vec![Value::Boolean(true)]), val);
+    assert_eq!(1..3, spans.span);
+    assert_eq!(2..3, spans.children[0].span);
+}
+
+#[test]
+fn fail_read_value_spans_too_deep() {
+    let buf = vec![0x91; 2000];
+
+    match read_value_spans(&mut Cursor::new(buf)) {
+        Err(Error::DepthLimitExceeded) => (),
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn pass_read_value_checked_roundtrip() {
+    let val = Value::Map(vec![
+        (Value::from("id"), Value::from(42)),
+        (
+            Value::from("tags"),
+            Value::Array(vec![Value::from("a"), Value::Nil]),
+        ),
+    ]);
+
+    let mut buf = Vec::new();
+    messpack_serde::encode::write_value_checked(&mut buf, &val).unwrap();
+    buf.push(0xc0);
+
+    let mut rd = &buf[..];
+    assert_eq!(val, read_value_checked(&mut rd).unwrap());
+    assert_eq!([0xc0], rd);
+}
+
+#[test]
+fn pass_write_value_checked_matches_zlib_crc32() {
+    let mut buf = Vec::new();
+    messpack_serde::encode::write_value_checked(&mut buf, &Value::from("123456789")).unwrap();
+
+    let mut expected = vec![0xa9];
+    expected.extend_from_slice(b"123456789");
+    // zlib.crc32(b"\xa9123456789")
+    expected.extend_from_slice(&[0x9b, 0x3a, 0x64, 0xe5]);
+    assert_eq!(expected, buf);
+}
+
+#[test]
+fn fail_read_value_checked_corrupted() {
+    let mut buf = Vec::new();
+    messpack_serde::encode::write_value_checked(&mut buf, &Value::from(300)).unwrap();
+    *buf.last_mut().unwrap() ^= 0x01;
+
+    match read_value_checked(&mut &buf[..]) {
+        Err(Error::ChecksumMismatch { expected, actual }) => assert_eq!(expected ^ 0x01, actual),
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn fail_read_value_checked_missing_checksum() {
+    let buf = [0xc3, 0xd0, 0x6f];
+
+    match read_value_checked(&mut &buf[..]) {
+        Err(Error::InvalidDataRead(..)) => (),
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+fn document_schema() -> Schema {
+    // {str: [int | nil]}
+    Schema::Map(
+        Box::new(Schema::Str),
+        Box::new(Schema::Array(Box::new(Schema::Optional(Box::new(
+            Schema::Int,
+        ))))),
+    )
+}
+
+#[test]
+fn pass_read_value_validated() {
+    // {"a": [1, nil], "b": []}
+    let buf = [0x82, 0xa1, 0x61, 0x92, 0x01, 0xc0, 0xa1, 0x62, 0x90, 0x2a];
+    let mut rd = &buf[..];
+
+    let expected = Value::Map(vec![
+        (
+            Value::from("a"),
+            Value::Array(vec![Value::from(1), Value::Nil]),
+        ),
+        (Value::from("b"), Value::Array(vec![])),
+    ]);
+    assert_eq!(
+        expected,
+        read_value_validated(&mut rd, &document_schema()).unwrap()
+    );
+    assert_eq!([0x2a], rd);
+}
+
+#[test]
+fn pass_read_value_validated_any() {
+    // [{"x": 1.5}]
+    let buf = [0x91, 0x81, 0xa1, 0x78, 0xcb, 0x3f, 0xf8, 0, 0, 0, 0, 0, 0];
+    let schema = Schema::Array(Box::new(Schema::Any));
+
+    let val = read_value_validated(&mut &buf[..], &schema).unwrap();
+    assert_eq!(
+        Value::Array(vec![Value::Map(vec![(Value::from("x"), Value::from(1.5))])]),
+        val
+    );
+}
+
+#[test]
+fn fail_read_value_validated_mismatch() {
+    // {"a": [1, nil], "b": [2, "3"]}
+    let buf = [
+        0x82, 0xa1, 0x61, 0x92, 0x01, 0xc0, 0xa1, 0x62, 0x92, 0x02, 0xa1, 0x33,
+    ];
+
+    match read_value_validated(&mut &buf[..], &document_schema()) {
+        Err(Error::SchemaMismatch { path, found }) => {
+            assert_eq!("$.b[1]", path);
+            assert_eq!(rmp::Marker::FixStr(1), found);
+        }
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn fail_read_value_validated_key_mismatch() {
+    // {1: []}
+    let buf = [0x81, 0x01, 0x90];
+
+    match read_value_validated(&mut &buf[..], &document_schema()) {
+        Err(Error::SchemaMismatch { path, .. }) => assert_eq!("$.<key 0>", path),
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn pass_read_map_expecting() {
+    // {"b": nil, "a": [1]}
+    let buf = [0x82, 0xa1, 0x62, 0xc0, 0xa1, 0x61, 0x91, 0x01];
+
+    let entries = read_map_expecting(&mut &buf[..], &["a", "b"]).unwrap();
+    assert_eq!(
+        vec![
+            ("b".to_owned(), Value::Nil),
+            ("a".to_owned(), Value::Array(vec![Value::from(1)])),
+        ],
+        entries
+    );
+}
+
+#[test]
+fn fail_read_map_expecting_extra_key() {
+    // {"a": 1, "c": 2, "b": 3}
+    let buf = [0x83, 0xa1, 0x61, 0x01, 0xa1, 0x63, 0x02, 0xa1, 0x62, 0x03];
+
+    match read_map_expecting(&mut &buf[..], &["a", "b"]) {
+        Err(Error::UnexpectedKey(key)) => assert_eq!("c", key),
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn fail_read_map_expecting_duplicate_key() {
+    // {"a": 1, "a": 2}
+    let buf = [0x82, 0xa1, 0x61, 0x01, 0xa1, 0x61, 0x02];
+
+    match read_map_expecting(&mut &buf[..], &["a", "b"]) {
+        Err(Error::UnexpectedKey(key)) => assert_eq!("a", key),
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn fail_read_map_expecting_missing_key() {
+    // {"b": 1}
+    let buf = [0x81, 0xa1, 0x62, 0x01];
+
+    match read_map_expecting(&mut &buf[..], &["a", "b"]) {
+        Err(Error::MissingKey(key)) => assert_eq!("a", key),
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn roundtrip_value_floats_bit_exact() {
+    let vals = [
+        Value::F64(-0.0),
+        Value::F64(f64::from_bits(1)),
+        Value::F32(-0.0),
+        Value::F32(f32::MIN_POSITIVE / 2.0),
+    ];
+
+    for val in vals {
+        let buf = messpack_serde::to_vec(&val).unwrap();
+        let decoded: Value = messpack_serde::from_slice(&buf).unwrap();
+        let direct = rmpv::decode::read_value(&mut &buf[..]).unwrap();
+
+        for out in [decoded, direct] {
+            match (&val, out) {
+                (Value::F64(expected), Value::F64(actual)) => {
+                    assert_eq!(expected.to_bits(), actual.to_bits());
+                }
+                (Value::F32(expected), Value::F32(actual)) => {
+                    assert_eq!(expected.to_bits(), actual.to_bits());
+                }
+                (_, other) => panic!("unexpected value: {other:?}"),
+            }
+        }
+    }
+}
+
+#[test]
+fn pass_read_value_interned_shares_keys() {
+    #[derive(serde::Serialize)]
+    struct Record {
+        name: String,
+        score: u32,
+    }
+
+    let records: Vec<_> = (0..1000)
+        .map(|i| Record {
+            name: format!("n{i}"),
+            score: i,
+        })
+        .collect();
+    let buf = messpack_serde::to_vec_named(&records).unwrap();
+    let mut interner = Interner::new();
+
+    let val = read_value_interned(&mut &buf[..], &mut interner).unwrap();
+    assert_eq!(2, interner.len());
+
+    let InternedValue::Array(records) = &val else {
+        panic!("unexpected value: {val:?}");
+    };
+    assert_eq!(1000, records.len());
+    let keys: Vec<_> = records
+        .iter()
+        .map(|record| match record {
+            InternedValue::Map(entries) => match &entries[0].0 {
+                InternedKey::Str(key) => key.clone(),
+                other => panic!("unexpected key: {other:?}"),
+            },
+            other => panic!("unexpected value: {other:?}"),
+        })
+        .collect();
+    assert!(keys.iter().all(|key| std::rc::Rc::ptr_eq(key, &keys[0])));
+    assert_eq!("name", &*keys[0]);
+
+    let plain = rmpv::decode::read_value(&mut &buf[..]).unwrap();
+    assert_eq!(plain, Value::from(val));
+}
+
+#[test]
+fn pass_read_value_interned_other_keys() {
+    // {1: "a", nil: ["b"]}
+    let buf = [0x82, 0x01, 0xa1, 0x61, 0xc0, 0x91, 0xa1, 0x62];
+    let mut interner = Interner::new();
+
+    let val = read_value_interned(&mut &buf[..], &mut interner).unwrap();
+    assert!(interner.is_empty());
+    assert_eq!(
+        InternedValue::Map(vec![
+            (
+                InternedKey::Other(Value::from(1)),
+                InternedValue::Leaf(Value::from("a"))
+            ),
+            (
+                InternedKey::Other(Value::Nil),
+                InternedValue::Array(vec![InternedValue::Leaf(Value::from("b"))])
+            ),
+        ]),
+        val
+    );
+}
+
+#[test]
+fn fail_read_value_interned_too_deep() {
+    let buf = vec![0x91; 2000];
+
+    match read_value_interned(&mut &buf[..], &mut Interner::new()) {
+        Err(Error::DepthLimitExceeded) => (),
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn pass_value_deserializer_map_into_struct() {
+    use messpack_serde::decode::ValueDeserializer;
+    use serde::Deserialize;
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    enum Kind {
+        Plain,
+        Scaled(f64),
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Entry<'a> {
+        name: &'a str,
+        id: u16,
+        delta: i64,
+        kinds: Vec<Kind>,
+        note: Option<String>,
+    }
+
+    let val = Value::Map(vec![
+        (Value::from("name"), Value::from("probe")),
+        (Value::from("id"), Value::from(300)),
+        (Value::from("delta"), Value::from(-7)),
+        (
+            Value::from("kinds"),
+            Value::Array(vec![
+                Value::from("Plain"),
+                Value::Map(vec![(Value::from("Scaled"), Value::F64(0.5))]),
+            ]),
+        ),
+        (Value::from("note"), Value::Nil),
+    ]);
+
+    let entry = Entry::deserialize(ValueDeserializer::new(&val)).unwrap();
+    assert_eq!(
+        Entry {
+            name: "probe",
+            id: 300,
+            delta: -7,
+            kinds: vec![Kind::Plain, Kind::Scaled(0.5)],
+            note: None,
+        },
+        entry
+    );
+}
+
+#[test]
+fn pass_value_deserializer_array_into_vec() {
+    use messpack_serde::decode::ValueDeserializer;
+    use serde::Deserialize;
+
+    let val = Value::Array(vec![Value::from(1), Value::from(2), Value::from(3)]);
+    let vals = Vec::<u8>::deserialize(ValueDeserializer::new(&val)).unwrap();
+    assert_eq!(vec![1, 2, 3], vals);
+
+    let val = Value::Array(vec![Value::from(1), Value::from(-1)]);
+    assert!(Vec::<u8>::deserialize(ValueDeserializer::new(&val)).is_err());
+}
+
+#[test]
+fn fail_value_deserializer_tuple_too_long() {
+    use messpack_serde::decode::ValueDeserializer;
+    use serde::Deserialize;
+
+    let val = Value::Array(vec![Value::from(1), Value::from(2), Value::from(3)]);
+    match <(u8, u8)>::deserialize(ValueDeserializer::new(&val)) {
+        Err(Error::LengthMismatch {
+            expected: 2,
+            actual: 3,
+        }) => (),
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn fail_value_deserializer_negative_into_u128() {
+    use messpack_serde::decode::ValueDeserializer;
+    use serde::Deserialize;
+
+    match u128::deserialize(ValueDeserializer::new(&Value::from(-1))) {
+        Err(Error::OutOfRange) => (),
+        other => panic!("unexpected result: {other:?}"),
+    }
+
+    let val = Value::Binary(u128::MAX.to_be_bytes().to_vec());
+    assert_eq!(
+        u128::MAX,
+        u128::deserialize(ValueDeserializer::new(&val)).unwrap()
+    );
+}
+
+#[test]
+fn pass_read_all_lenient_skips_corrupt_value() {
+    // 1, the reserved marker 0xc1 in place of a value, then ["a", true]
+    let buf = [0x01, 0xc1, 0x92, 0xa1, 0x61, 0xc3];
+    let vals = read_all_lenient(&mut Cursor::new(&buf[..]));
+
+    assert_eq!(3, vals.len());
+    assert_eq!(Value::from(1), *vals[0].as_ref().unwrap());
+    assert!(vals[1].is_err());
+    assert_eq!(
+        Value::Array(vec![Value::from("a"), Value::Boolean(true)]),
+        *vals[2].as_ref().unwrap()
+    );
+}
+
+#[test]
+fn pass_read_all_lenient_truncated_tail() {
+    // "x", then a str 8 claiming 32 bytes with only 2 left
+    let buf = [0xa1, 0x78, 0xd9, 0x20, 0xa1, 0x79];
+    let vals = read_all_lenient(&mut Cursor::new(&buf[..]));
+
+    assert_eq!(Value::from("x"), *vals[0].as_ref().unwrap());
+    match vals[1] {
+        Err(Error::InvalidDataRead(ref err)) if err.kind() == ErrorKind::UnexpectedEof => (),
+        ref other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn pass_read_all_lenient_empty() {
+    assert!(read_all_lenient(&mut Cursor::new(&[][..])).is_empty());
+}
+
+#[test]
+fn pass_transform_redacts_nested_strings() {
+    let doc = Value::Map(vec![
+        (Value::from("user"), Value::from("alice")),
+        (
+            Value::from("sessions"),
+            Value::Array(vec![
+                Value::Map(vec![
+                    (Value::from("token"), Value::from("s3cr3t")),
+                    (Value::from("ttl"), Value::from(3600)),
+                ]),
+                Value::Array(vec![]),
+                Value::Binary(vec![1, 2]),
+            ]),
+        ),
+        (Value::from(7), Value::Nil),
+    ]);
+    let mut buf = Vec::new();
+    rmpv::encode::write_value(&mut buf, &doc).unwrap();
+    buf.push(0xc3);
+
+    let mut rd = &buf[..];
+    let mut out = Vec::new();
+    let mut seen = 0;
+    transform(&mut rd, &mut out, |val| {
+        seen += 1;
+        val.is_str().then(|| Value::from("***"))
+    })
+    .unwrap();
+
+    let expected = Value::Map(vec![
+        (Value::from("user"), Value::from("***")),
+        (
+            Value::from("sessions"),
+            Value::Array(vec![
+                Value::Map(vec![
+                    (Value::from("token"), Value::from("***")),
+                    (Value::from("ttl"), Value::from(3600)),
+                ]),
+                Value::Array(vec![]),
+                Value::Binary(vec![1, 2]),
+            ]),
+        ),
+        (Value::from(7), Value::Nil),
+    ]);
+    let mut expected_buf = Vec::new();
+    rmpv::encode::write_value(&mut expected_buf, &expected).unwrap();
+    assert_eq!(expected_buf, out);
+    // Keys are not passed to the callback.
+    assert_eq!(5, seen);
+    // Only the one value was consumed.
+    assert_eq!([0xc3], rd);
+}
+
+#[test]
+fn fail_transform_truncated() {
+    // [1, "ab" cut short
+    let buf = [0x92, 0x01, 0xa2, 0x61];
+    let mut out = Vec::new();
+
+    match transform(&mut &buf[..], &mut out, |_| None) {
+        Err(Error::InvalidDataRead(..)) => (),
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn pass_read_value_every_type() {
+    let doc = Value::Array(vec![
+        Value::Nil,
+        Value::Boolean(true),
+        Value::from(u64::MAX),
+        Value::from(i64::MIN),
+        Value::F32(1.5),
+        Value::F64(-0.25),
+        Value::from("s"),
+        Value::Binary(vec![0, 1]),
+        Value::Map(vec![
+            (Value::from(-1), Value::from("negative key")),
+            (Value::Nil, Value::Array(vec![])),
+        ]),
+        Value::Ext(3, vec![7]),
+    ]);
+    let mut buf = Vec::new();
+    rmpv::encode::write_value(&mut buf, &doc).unwrap();
+
+    assert_eq!(doc, read_value(&mut &buf[..]).unwrap());
+}
+
+#[test]
+fn pass_read_value_integer_widths_collapse() {
+    // 5 as fixnum, uint 16 and int 64
+    for buf in [
+        &[0x05][..],
+        &[0xcd, 0x00, 0x05][..],
+        &[0xd3, 0, 0, 0, 0, 0, 0, 0, 0x05][..],
+    ] {
+        assert_eq!(Value::from(5u64), read_value(&mut &buf[..]).unwrap());
+    }
+}
+
+#[test]
+fn pass_read_value_nested() {
+    // 100 levels of [{"k": ...}] around nil
+    let mut buf = Vec::new();
+    for _ in 0..100 {
+        buf.extend([0x91, 0x81, 0xa1, 0x6b]);
+    }
+    buf.push(0xc0);
+
+    let mut val = read_value(&mut &buf[..]).unwrap();
+    for _ in 0..100 {
+        val = match val {
+            Value::Array(mut vals) => match vals.pop() {
+                Some(Value::Map(mut entries)) => entries.pop().unwrap().1,
+                other => panic!("unexpected element: {other:?}"),
+            },
+            other => panic!("unexpected value: {other:?}"),
+        };
+    }
+    assert_eq!(Value::Nil, val);
+}
+
+#[test]
+fn fail_read_value_truncated() {
+    // [1, 2, 3] cut short before its last element, then inside a str
+    for buf in [&[0x93, 0x01, 0x02][..], &[0xa3, 0x61][..]] {
+        match read_value(&mut &buf[..]) {
+            Err(Error::InvalidDataRead(ref err)) if err.kind() == ErrorKind::UnexpectedEof => (),
+            other => panic!("unexpected result: {other:?}"),
+        }
+    }
+    match read_value(&mut &[][..]) {
+        Err(Error::InvalidMarkerRead(..)) => (),
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn pass_dispatch_each_family() {
+    let cases: [(&[u8], Value); 12] = [
+        (&[0xc0], Value::Nil),
+        (&[0xc2], Value::Boolean(false)),
+        (&[0x7f], Value::from(127)),
+        (&[0xe0], Value::from(-32)),
+        (&[0xcc, 0xff], Value::from(255)),
+        (&[0xd2, 0xff, 0xff, 0xff, 0xfe], Value::from(-2)),
+        (&[0xca, 0x3f, 0xc0, 0x00, 0x00], Value::F32(1.5)),
+        (&[0xcb, 0xbf, 0xd0, 0, 0, 0, 0, 0, 0], Value::F64(-0.25)),
+        (&[0xd9, 0x01, 0x61], Value::from("a")),
+        (&[0xc4, 0x02, 0x00, 0x01], Value::Binary(vec![0, 1])),
+        (
+            &[0x81, 0x01, 0x91, 0xc3],
+            Value::Map(vec![(
+                Value::from(1),
+                Value::Array(vec![Value::Boolean(true)]),
+            )]),
+        ),
+        (&[0xd4, 0x05, 0x09], Value::Ext(5, vec![9])),
+    ];
+
+    for (buf, expected) in cases {
+        let mut rd = buf;
+        let marker = rmp::decode::read_marker(&mut rd).unwrap();
+        assert_eq!(expected, dispatch(marker, &mut rd).unwrap(), "{buf:02x?}");
+        assert!(rd.is_empty());
+    }
+}
+
+#[test]
+fn fail_dispatch_truncated() {
+    // str 8 of length 2 with one byte, and an empty fixarray body for [nil]
+    for (marker, rest) in [
+        (Marker::Str8, &[0x02, 0x61][..]),
+        (Marker::FixArray(1), &[][..]),
+    ] {
+        match dispatch(marker, &mut &rest[..]) {
+            Err(Error::InvalidDataRead(ref err)) if err.kind() == ErrorKind::UnexpectedEof => (),
+            other => panic!("unexpected result: {other:?}"),
+        }
+    }
+}
+
+#[test]
+fn pass_read_tagged_records() {
+    // [1, {"id": 7}] followed by [-2, nil]
+    let buf = [0x92, 0x01, 0x81, 0xa2, 0x69, 0x64, 0x07, 0x92, 0xfe, 0xc0];
+    let mut rd = &buf[..];
+
+    let (tag, payload) = read_tagged(&mut rd).unwrap();
+    assert_eq!(1, tag);
+    assert_eq!(
+        Value::Map(vec![(Value::from("id"), Value::from(7))]),
+        payload
+    );
+    assert_eq!((-2, Value::Nil), read_tagged(&mut rd).unwrap());
+    assert!(rd.is_empty());
+}
+
+#[test]
+fn fail_read_tagged_wrong_shape() {
+    // {"a": 1}
+    match read_tagged(&mut &[0x81, 0xa1, 0x61, 0x01][..]) {
+        Err(Error::TypeMismatch(Marker::FixMap(1))) => (),
+        other => panic!("unexpected result: {other:?}"),
+    }
+    // [1, 2, 3]
+    match read_tagged(&mut &[0x93, 0x01, 0x02, 0x03][..]) {
+        Err(Error::LengthMismatch {
+            expected: 2,
+            actual: 3,
+        }) => (),
+        other => panic!("unexpected result: {other:?}"),
+    }
+    // ["a", 1]
+    match read_tagged(&mut &[0x92, 0xa1, 0x61, 0x01][..]) {
+        Err(Error::TypeMismatch(Marker::FixStr(1))) => (),
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
@@ -1,12 +1,14 @@
 extern crate messpack_serde as rmps;
 
+use std::collections::BTreeMap;
 use std::io::Cursor;
 
-use rmps::config::BytesMode;
+use rmps::config::{BytesMode, Signedness};
 use serde::Serialize;
 
 use messpack_serde::encode::{self, Error};
-use messpack_serde::{Raw, RawRef, Serializer};
+use messpack_serde::test_support::{assert_encodes_to, scan_stats};
+use messpack_serde::{Raw, RawRef, RawValue, Serializer, Timestamp};
 
 #[test]
 fn pass_null() {
@@ -206,6 +208,80 @@ fn pass_f64() {
     assert_eq!([0xcb, 0x40, 0x45, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], buf);
 }
 
+#[test]
+fn pass_f64_nan() {
+    let mut buf = Vec::new();
+
+    f64::NAN.serialize(&mut Serializer::new(&mut buf)).unwrap();
+
+    assert_eq!(0xcb, buf[0]);
+    assert_eq!(f64::NAN.to_bits().to_be_bytes(), buf[1..]);
+}
+
+#[test]
+fn fail_f64_nan_with_reject_non_finite() {
+    let mut buf = Vec::new();
+    let mut se = Serializer::new(&mut buf).with_reject_non_finite(true);
+
+    match f64::NAN.serialize(&mut se) {
+        Err(Error::NonFiniteFloat) => (),
+        other => panic!("unexpected result: {other:?}"),
+    }
+    match f32::NEG_INFINITY.serialize(&mut se) {
+        Err(Error::NonFiniteFloat) => (),
+        other => panic!("unexpected result: {other:?}"),
+    }
+    assert!(buf.is_empty());
+}
+
+#[test]
+fn pass_finite_floats_with_reject_non_finite() {
+    let mut buf = Vec::new();
+    let mut se = Serializer::new(&mut buf)
+        .with_reject_non_finite(true)
+        .with_struct_map();
+
+    (1.5f32, -0.0f64).serialize(&mut se).unwrap();
+
+    assert_eq!(
+        [0x92, 0xca, 0x3f, 0xc0, 0x00, 0x00, 0xcb, 0x80, 0, 0, 0, 0, 0, 0, 0],
+        buf[..]
+    );
+}
+
+#[test]
+fn pass_i64_prefer_unsigned_by_default() {
+    let mut buf = Vec::new();
+    let mut se = Serializer::new(&mut buf);
+    (5i64, 200i64, -5i64).serialize(&mut se).unwrap();
+    assert_eq!([0x93, 0x05, 0xcc, 0xc8, 0xfb], buf[..]);
+
+    let mut explicit = Vec::new();
+    let mut se = Serializer::new(&mut explicit).with_signed_preference(Signedness::PreferUnsigned);
+    (5i64, 200i64, -5i64).serialize(&mut se).unwrap();
+    assert_eq!(buf, explicit);
+}
+
+#[test]
+fn pass_i64_preserve_signed() {
+    let mut buf = Vec::new();
+    let mut se = Serializer::new(&mut buf).with_signed_preference(Signedness::PreserveSigned);
+    (5i64, 200i64, 70000i32, 1i64 << 40, -5i64, 7u8)
+        .serialize(&mut se)
+        .unwrap();
+
+    assert_eq!(
+        [
+            0x96, 0xd0, 0x05, 0xd1, 0x00, 0xc8, 0xd2, 0x00, 0x01, 0x11, 0x70, 0xd3, 0x00, 0x00,
+            0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0xfb, 0x07,
+        ],
+        buf[..]
+    );
+
+    let val: (i64, i64, i32, i64, i64, u8) = rmps::from_slice(&buf).unwrap();
+    assert_eq!((5, 200, 70000, 1 << 40, -5, 7), val);
+}
+
 #[test]
 fn pass_char() {
     let mut buf = [0x00, 0x00];
@@ -220,31 +296,29 @@ fn pass_char() {
 
 #[test]
 fn pass_string() {
-    let mut buf = [
-        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-    ];
-
-    let val = "le message";
-    val.serialize(&mut Serializer::new(&mut &mut buf[..]))
-        .ok()
-        .unwrap();
-
-    assert_eq!(
-        [0xaa, 0x6c, 0x65, 0x20, 0x6d, 0x65, 0x73, 0x73, 0x61, 0x67, 0x65],
-        buf
+    assert_encodes_to(
+        "le message",
+        &[
+            0xaa, 0x6c, 0x65, 0x20, 0x6d, 0x65, 0x73, 0x73, 0x61, 0x67, 0x65,
+        ],
     );
 }
 
 #[test]
 fn pass_tuple() {
-    let mut buf = [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00];
-
-    let val = (42u32, 100500u32);
-    val.serialize(&mut Serializer::new(&mut &mut buf[..]))
-        .ok()
-        .unwrap();
+    assert_encodes_to(
+        (42u32, 100500u32),
+        &[0x92, 0x2a, 0xce, 0x0, 0x1, 0x88, 0x94],
+    );
+}
 
-    assert_eq!([0x92, 0x2a, 0xce, 0x0, 0x1, 0x88, 0x94], buf);
+#[test]
+#[should_panic(expected = "0001  2a               FixPos(42)")]
+fn fail_assert_encodes_to_shows_annotated_dump() {
+    assert_encodes_to(
+        (42u32, 100500u32),
+        &[0x92, 0x2a, 0xce, 0x0, 0x1, 0x88, 0x95],
+    );
 }
 
 #[test]
@@ -398,6 +472,77 @@ fn pass_bin() {
     assert_eq!(vec![0xc4, 0x02, 0xcc, 0x80], buf);
 }
 
+#[test]
+fn pass_bin_picks_smallest_header() {
+    fn header(len: usize) -> Vec<u8> {
+        let mut buf = Vec::new();
+        serde_bytes::Bytes::new(&vec![0; len])
+            .serialize(&mut Serializer::new(&mut buf))
+            .unwrap();
+        buf.truncate(buf.len() - len);
+        buf
+    }
+
+    assert_eq!([0xc4, 0x00], header(0)[..]);
+    assert_eq!([0xc4, 0xff], header(255)[..]);
+    assert_eq!([0xc5, 0x01, 0x00], header(256)[..]);
+    assert_eq!([0xc5, 0xff, 0xff], header(65535)[..]);
+    assert_eq!([0xc6, 0x00, 0x01, 0x00, 0x00], header(65536)[..]);
+}
+
+#[test]
+fn pass_forced_bytes_picks_smallest_header() {
+    fn header(len: usize) -> Vec<u8> {
+        let mut buf = Vec::new();
+        vec![0u8; len]
+            .serialize(&mut Serializer::new(&mut buf).with_bytes(BytesMode::ForceIterables))
+            .unwrap();
+        buf.truncate(buf.len() - len);
+        buf
+    }
+
+    assert_eq!([0xc4, 0xff], header(255)[..]);
+    assert_eq!([0xc5, 0x01, 0x00], header(256)[..]);
+    assert_eq!([0xc5, 0xff, 0xff], header(65535)[..]);
+    assert_eq!([0xc6, 0x00, 0x01, 0x00, 0x00], header(65536)[..]);
+}
+
+#[test]
+fn pass_to_vec_exact() {
+    let val = (
+        "le message",
+        vec![1u64 << 40; 100],
+        Some(std::collections::BTreeMap::from([(1, 2.5f32)])),
+    );
+
+    let buf = messpack_serde::to_vec_exact(&val).unwrap();
+
+    assert_eq!(encode::to_vec(&val).unwrap(), buf);
+    assert_eq!(buf.len(), buf.capacity());
+}
+
+#[test]
+fn pass_serialized_size() {
+    #[derive(Serialize)]
+    struct Record {
+        id: u64,
+        name: String,
+        tags: Vec<&'static str>,
+        score: Option<f64>,
+    }
+
+    let val = Record {
+        id: 1 << 33,
+        name: "x".repeat(300),
+        tags: vec!["a", "bc"],
+        score: None,
+    };
+
+    let expected = encode::to_vec(&val).unwrap().len() as u64;
+    assert_eq!(expected, encode::serialized_size(&val).unwrap());
+    assert_eq!(1, encode::serialized_size(&()).unwrap());
+}
+
 #[test]
 fn pass_to_vec() {
     assert_eq!(vec![0xc0], encode::to_vec(&()).unwrap());
@@ -465,7 +610,288 @@ fn pass_raw_ref_invalid_utf8() {
     assert_eq!(vec![196, 4, 146, 204, 200, 144], buf);
 }
 
+#[test]
+fn pass_raw_value_spliced() {
+    #[derive(Serialize)]
+    struct Envelope {
+        id: u8,
+        payload: RawValue,
+    }
+
+    // {"a": [1, nil]}, encoded elsewhere
+    let payload = [0x81, 0xa1, 0x61, 0x92, 0x01, 0xc0];
+    let val = Envelope {
+        id: 7,
+        payload: RawValue::from_vec(payload.to_vec()).unwrap(),
+    };
+
+    let mut expected = vec![0x92, 0x07];
+    expected.extend_from_slice(&payload);
+    assert_encodes_to(&val, &expected);
+
+    let mut expected = vec![0x82, 0xa2, 0x69, 0x64, 0x07];
+    expected.extend_from_slice(&[0xa7, 0x70, 0x61, 0x79, 0x6c, 0x6f, 0x61, 0x64]);
+    expected.extend_from_slice(&payload);
+    assert_eq!(expected, rmps::to_vec_named(&val).unwrap());
+}
+
+#[test]
+fn fail_raw_value_from_vec_malformed() {
+    assert!(RawValue::from_vec(vec![0x92, 0x01]).is_err());
+    assert!(RawValue::from_vec(vec![0x01, 0x02]).is_err());
+    assert!(RawValue::from_vec(vec![]).is_err());
+}
+
 #[test]
 fn serializer_one_type_arg() {
     let _s: messpack_serde::Serializer<&mut dyn std::io::Write>;
 }
+
+#[test]
+fn pass_value_serializer_nested_struct() {
+    use rmps::decode::value::Value;
+    use rmps::encode::ValueSerializer;
+    use std::collections::BTreeMap;
+
+    #[derive(Serialize)]
+    enum Shape {
+        Dot,
+        Circle(f32),
+        Rect { w: u16, h: u16 },
+    }
+
+    #[derive(Serialize)]
+    struct Layer {
+        name: String,
+        offset: (i8, i64),
+        shapes: Vec<Shape>,
+        tags: BTreeMap<String, Option<bool>>,
+        #[serde(with = "serde_bytes")]
+        blob: Vec<u8>,
+        big: u128,
+    }
+
+    let mut tags = BTreeMap::new();
+    tags.insert("hidden".to_owned(), Some(true));
+    tags.insert("locked".to_owned(), None);
+    let layer = Layer {
+        name: "bg".to_owned(),
+        offset: (-3, 70_000),
+        shapes: vec![Shape::Dot, Shape::Circle(1.5), Shape::Rect { w: 4, h: 2 }],
+        tags,
+        blob: vec![0xde, 0xad],
+        big: 1,
+    };
+
+    let mut big = vec![0; 16];
+    big[15] = 1;
+    let expected = Value::Array(vec![
+        Value::from("bg"),
+        Value::Array(vec![Value::from(-3), Value::from(70_000)]),
+        Value::Array(vec![
+            Value::from("Dot"),
+            Value::Map(vec![(Value::from("Circle"), Value::F32(1.5))]),
+            Value::Map(vec![(
+                Value::from("Rect"),
+                Value::Array(vec![Value::from(4), Value::from(2)]),
+            )]),
+        ]),
+        Value::Map(vec![
+            (Value::from("hidden"), Value::Boolean(true)),
+            (Value::from("locked"), Value::Nil),
+        ]),
+        Value::Binary(vec![0xde, 0xad]),
+        Value::Binary(big),
+    ]);
+
+    let val = layer.serialize(ValueSerializer::new()).unwrap();
+    assert_eq!(expected, val);
+
+    // Matches what the byte serializer produces.
+    let buf = rmps::to_vec(&layer).unwrap();
+    assert_eq!(rmpv::decode::read_value(&mut &buf[..]).unwrap(), val);
+    let buf = rmps::to_vec_named(&layer).unwrap();
+    let named = layer
+        .serialize(ValueSerializer::new().with_struct_map())
+        .unwrap();
+    assert_eq!(rmpv::decode::read_value(&mut &buf[..]).unwrap(), named);
+}
+
+#[test]
+fn pass_value_serializer_ext_and_raw_value() {
+    use rmps::decode::value::Value;
+    use rmps::encode::ValueSerializer;
+
+    #[derive(Serialize)]
+    #[serde(rename = "_ExtStruct")]
+    struct Ext((i8, serde_bytes::ByteBuf));
+
+    let ext = Ext((5, serde_bytes::ByteBuf::from(vec![1, 2, 3])));
+    let val = ext.serialize(ValueSerializer::new()).unwrap();
+    assert_eq!(Value::Ext(5, vec![1, 2, 3]), val);
+
+    let raw = RawValue::from_vec(vec![0x92, 0x01, 0xc0]).unwrap();
+    let val = raw.serialize(ValueSerializer::new()).unwrap();
+    assert_eq!(Value::Array(vec![Value::from(1), Value::Nil]), val);
+}
+
+#[test]
+fn pass_timestamp_32_bit_form() {
+    // 2023-11-14T22:13:20Z
+    let ts = Timestamp::new(1_700_000_000, 0).unwrap();
+    assert_encodes_to(ts, &[0xd6, 0xff, 0x65, 0x53, 0xf1, 0x00]);
+
+    let ts = Timestamp::new(i64::from(u32::MAX), 0).unwrap();
+    assert_encodes_to(ts, &[0xd6, 0xff, 0xff, 0xff, 0xff, 0xff]);
+}
+
+#[test]
+fn pass_timestamp_64_bit_form() {
+    // 2023-11-14T22:13:20.5Z
+    let ts = Timestamp::new(1_700_000_000, 500_000_000).unwrap();
+    assert_encodes_to(
+        ts,
+        &[0xd7, 0xff, 0x77, 0x35, 0x94, 0x00, 0x65, 0x53, 0xf1, 0x00],
+    );
+
+    // Whole seconds past 2106 no longer fit the 32-bit form.
+    let ts = Timestamp::new(1 << 32, 0).unwrap();
+    assert_encodes_to(
+        ts,
+        &[0xd7, 0xff, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00],
+    );
+}
+
+#[test]
+fn pass_timestamp_96_bit_form() {
+    // 2514-05-30T01:53:04Z, the first second past 34 bits
+    let ts = Timestamp::new(1 << 34, 0).unwrap();
+    assert_encodes_to(
+        ts,
+        &[
+            0xc7, 0x0c, 0xff, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x04, 0x00, 0x00, 0x00,
+            0x00,
+        ],
+    );
+
+    // Times before the epoch
+    let ts = Timestamp::new(-1, 1).unwrap();
+    assert_encodes_to(
+        ts,
+        &[
+            0xc7, 0x0c, 0xff, 0x00, 0x00, 0x00, 0x01, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+            0xff,
+        ],
+    );
+}
+
+#[test]
+fn pass_corpus_uses_smallest_markers() {
+    #[derive(Serialize)]
+    struct Event {
+        id: u32,
+        delta: i16,
+        name: String,
+        tags: Vec<String>,
+        ok: bool,
+        note: Option<String>,
+        score: f64,
+    }
+
+    let events: Vec<_> = (0..10u8)
+        .map(|i| Event {
+            id: u32::from(i) * 40,
+            delta: -i16::from(i),
+            name: format!("event-{i}"),
+            tags: if i % 2 == 0 {
+                vec!["x".repeat(40)]
+            } else {
+                vec![]
+            },
+            ok: i % 3 == 0,
+            note: (i == 9).then(|| "n".to_owned()),
+            score: f64::from(i) * 0.5,
+        })
+        .collect();
+
+    let buf = rmps::to_vec(&events).unwrap();
+    let expected = [
+        // The outer vec, the 10 events and their tags
+        ("FixArray", 21),
+        // ids 0 to 120 and delta 0
+        ("FixPos", 5),
+        ("U8", 3),
+        ("U16", 3),
+        ("FixNeg", 9),
+        // The names and the one note
+        ("FixStr", 11),
+        // Only the 40 byte tags outgrow a fixstr
+        ("Str8", 5),
+        ("True", 4),
+        ("False", 6),
+        ("Null", 9),
+        ("F64", 10),
+    ];
+    let expected: BTreeMap<_, _> = expected
+        .iter()
+        .map(|&(name, count)| (name.to_owned(), count))
+        .collect();
+    assert_eq!(expected, scan_stats(&buf));
+}
+
+/// Yields `items` values while claiming to have `len`.
+struct MisreportedLen {
+    items: u32,
+    len: usize,
+}
+
+impl Iterator for MisreportedLen {
+    type Item = u32;
+
+    fn next(&mut self) -> Option<u32> {
+        self.items = self.items.checked_sub(1)?;
+        Some(self.items)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl ExactSizeIterator for MisreportedLen {}
+
+#[test]
+fn fail_serialize_iter_wrong_len() {
+    let mut buf = Vec::new();
+    let iter = MisreportedLen { items: 3, len: 2 };
+    match Serializer::new(&mut buf).serialize_iter(iter) {
+        Err(Error::InvalidDataModel(..)) => (),
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn fail_serialize_iter_too_long() {
+    let mut buf = Vec::new();
+    let iter = MisreportedLen {
+        items: 0,
+        len: u32::MAX as usize + 1,
+    };
+    match Serializer::new(&mut buf).serialize_iter(iter) {
+        Err(Error::InvalidValueWrite(..)) => (),
+        other => panic!("unexpected result: {other:?}"),
+    }
+    assert!(buf.is_empty());
+}
+
+#[test]
+fn pass_serialize_iter_bytes_as_bin() {
+    let data = [1u8, 2, 3];
+    let mut buf = Vec::new();
+    Serializer::new(&mut buf)
+        .with_bytes(BytesMode::ForceIterables)
+        .serialize_iter(data.iter())
+        .unwrap();
+
+    assert_eq!([0xc4, 0x03, 0x01, 0x02, 0x03], buf[..]);
+}
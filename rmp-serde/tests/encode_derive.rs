@@ -380,3 +380,19 @@ fn pass_struct_as_map_using_triple_ext_many_times() {
         se.into_inner()
     );
 }
+
+#[test]
+fn pass_struct_with_f32_field_as_f32() {
+    #[derive(Serialize)]
+    struct Reading {
+        value: f32,
+    }
+
+    let mut buf = Vec::new();
+    Reading { value: 1.5 }
+        .serialize(&mut Serializer::new(&mut buf))
+        .unwrap();
+
+    // Expect: [1.5] with the value kept as a 5-byte F32 rather than promoted to a 9-byte F64.
+    assert_eq!(vec![0x91, 0xca, 0x3f, 0xc0, 0x00, 0x00], buf);
+}
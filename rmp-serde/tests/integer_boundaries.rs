@@ -0,0 +1,153 @@
+//! Round-trips every integer boundary through the serializer and the various integer readers,
+//! checking both the decoded value and the marker chosen by the encoder.
+
+use rmp::decode::{self, NumValueReadError};
+use rmp::Marker;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use messpack_serde::decode::value::Value;
+use messpack_serde::{Deserializer, Serializer};
+
+const UNSIGNED: &[(u64, Marker)] = &[
+    (0, Marker::FixPos(0)),
+    (127, Marker::FixPos(127)),
+    (128, Marker::U8),
+    (u8::MAX as u64, Marker::U8),
+    (u8::MAX as u64 + 1, Marker::U16),
+    (i16::MAX as u64, Marker::U16),
+    (u16::MAX as u64, Marker::U16),
+    (u16::MAX as u64 + 1, Marker::U32),
+    (i32::MAX as u64, Marker::U32),
+    (u32::MAX as u64, Marker::U32),
+    (u32::MAX as u64 + 1, Marker::U64),
+    (i64::MAX as u64, Marker::U64),
+    (u64::MAX, Marker::U64),
+];
+
+const SIGNED: &[(i64, Marker)] = &[
+    (-1, Marker::FixNeg(-1)),
+    (-32, Marker::FixNeg(-32)),
+    (-33, Marker::I8),
+    (i8::MIN as i64, Marker::I8),
+    (i8::MIN as i64 - 1, Marker::I16),
+    (i16::MIN as i64, Marker::I16),
+    (i16::MIN as i64 - 1, Marker::I32),
+    (i32::MIN as i64, Marker::I32),
+    (i32::MIN as i64 - 1, Marker::I64),
+    (i64::MIN, Marker::I64),
+];
+
+fn encode<T: Serialize>(val: T) -> Vec<u8> {
+    let mut buf = Vec::new();
+    val.serialize(&mut Serializer::new(&mut buf)).unwrap();
+    buf
+}
+
+/// Decodes through serde, checking that exactly the whole buffer was consumed.
+fn decode<T: DeserializeOwned>(buf: &[u8]) -> Result<T, messpack_serde::decode::Error> {
+    let mut rd = buf;
+    let val = T::deserialize(&mut Deserializer::new(&mut rd))?;
+    assert!(rd.is_empty(), "trailing bytes in {buf:02x?}");
+    Ok(val)
+}
+
+fn read_u64(buf: &[u8]) -> Result<u64, NumValueReadError> {
+    let mut rd = buf;
+    let val = decode::read_int(&mut rd)?;
+    assert!(rd.is_empty(), "trailing bytes in {buf:02x?}");
+    Ok(val)
+}
+
+fn read_i64(buf: &[u8]) -> Result<i64, NumValueReadError> {
+    let mut rd = buf;
+    let val = decode::read_int(&mut rd)?;
+    assert!(rd.is_empty(), "trailing bytes in {buf:02x?}");
+    Ok(val)
+}
+
+/// Checks that `val` encodes to the same bytes as its `u64` form when it fits into `T`, and that
+/// decoding into `T` succeeds exactly when it fits.
+fn check_narrow<T>(buf: &[u8], val: i128)
+where
+    T: Serialize + DeserializeOwned + TryFrom<i128> + PartialEq + std::fmt::Debug,
+{
+    match T::try_from(val) {
+        Ok(narrow) => {
+            assert_eq!(
+                buf,
+                encode(&narrow),
+                "{val} as {}",
+                std::any::type_name::<T>()
+            );
+            assert_eq!(narrow, decode::<T>(buf).unwrap());
+        }
+        Err(_) => assert!(
+            decode::<T>(buf).is_err(),
+            "{val} decoded into {}",
+            std::any::type_name::<T>()
+        ),
+    }
+}
+
+fn check_all_types(buf: &[u8], val: i128) {
+    check_narrow::<u8>(buf, val);
+    check_narrow::<u16>(buf, val);
+    check_narrow::<u32>(buf, val);
+    check_narrow::<u64>(buf, val);
+    check_narrow::<i8>(buf, val);
+    check_narrow::<i16>(buf, val);
+    check_narrow::<i32>(buf, val);
+    check_narrow::<i64>(buf, val);
+}
+
+#[test]
+fn roundtrip_unsigned_boundaries() {
+    for &(val, marker) in UNSIGNED {
+        let buf = encode(val);
+        assert_eq!(marker, Marker::from_u8(buf[0]), "marker for {val}");
+
+        assert_eq!(val, decode::<u64>(&buf).unwrap());
+        assert_eq!(i128::from(val), decode::<i128>(&buf).unwrap());
+        assert_eq!(Value::from(val), decode::<Value>(&buf).unwrap());
+        assert_eq!(val, read_u64(&buf).unwrap());
+        assert_eq!(i64::try_from(val).ok(), read_i64(&buf).ok());
+
+        check_all_types(&buf, i128::from(val));
+    }
+}
+
+#[test]
+fn roundtrip_signed_boundaries() {
+    for &(val, marker) in SIGNED {
+        let buf = encode(val);
+        assert_eq!(marker, Marker::from_u8(buf[0]), "marker for {val}");
+
+        assert_eq!(val, decode::<i64>(&buf).unwrap());
+        assert_eq!(i128::from(val), decode::<i128>(&buf).unwrap());
+        assert_eq!(Value::from(val), decode::<Value>(&buf).unwrap());
+        assert_eq!(val, read_i64(&buf).unwrap());
+        assert!(read_u64(&buf).is_err());
+
+        check_all_types(&buf, i128::from(val));
+    }
+}
+
+#[test]
+fn roundtrip_one_past_each_boundary() {
+    // Values just inside and outside each boundary, so off-by-one marker choices show up.
+    for &(val, _) in UNSIGNED {
+        for val in [val.saturating_sub(1), val, val.saturating_add(1)] {
+            let buf = encode(val);
+            assert_eq!(val, decode::<u64>(&buf).unwrap());
+            check_all_types(&buf, i128::from(val));
+        }
+    }
+    for &(val, _) in SIGNED {
+        for val in [val.saturating_sub(1), val, val.saturating_add(1)] {
+            let buf = encode(val);
+            assert_eq!(val, decode::<i64>(&buf).unwrap());
+            check_all_types(&buf, i128::from(val));
+        }
+    }
+}
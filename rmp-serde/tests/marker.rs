@@ -0,0 +1,161 @@
+use messpack_serde::marker::MarkerExt;
+use rmp::Marker;
+
+#[test]
+fn pass_data_len_scalars() {
+    assert_eq!(Some(0), Marker::Null.data_len());
+    assert_eq!(Some(0), Marker::True.data_len());
+    assert_eq!(Some(0), Marker::FixPos(7).data_len());
+    assert_eq!(Some(0), Marker::FixNeg(-3).data_len());
+    assert_eq!(Some(1), Marker::U8.data_len());
+    assert_eq!(Some(2), Marker::I16.data_len());
+    assert_eq!(Some(4), Marker::U32.data_len());
+    assert_eq!(Some(4), Marker::F32.data_len());
+    assert_eq!(Some(8), Marker::I64.data_len());
+    assert_eq!(Some(8), Marker::F64.data_len());
+}
+
+#[test]
+fn pass_data_len_fixstr() {
+    assert_eq!(Some(0), Marker::FixStr(0).data_len());
+    assert_eq!(Some(31), Marker::FixStr(31).data_len());
+}
+
+#[test]
+fn pass_data_len_fixext_includes_type_id() {
+    assert_eq!(Some(2), Marker::FixExt1.data_len());
+    assert_eq!(Some(3), Marker::FixExt2.data_len());
+    assert_eq!(Some(5), Marker::FixExt4.data_len());
+    assert_eq!(Some(9), Marker::FixExt8.data_len());
+    assert_eq!(Some(17), Marker::FixExt16.data_len());
+}
+
+#[test]
+fn pass_data_len_variable_length() {
+    assert_eq!(None, Marker::Str8.data_len());
+    assert_eq!(None, Marker::Bin32.data_len());
+    assert_eq!(None, Marker::Ext16.data_len());
+    assert_eq!(None, Marker::FixArray(2).data_len());
+    assert_eq!(None, Marker::Array16.data_len());
+    assert_eq!(None, Marker::FixMap(1).data_len());
+    assert_eq!(None, Marker::Map32.data_len());
+    assert_eq!(None, Marker::Reserved.data_len());
+}
+
+#[test]
+fn pass_data_len_matches_encoded_size() {
+    let mut buf = Vec::new();
+    rmp::encode::write_u32(&mut buf, 7).unwrap();
+    let marker = Marker::from_u8(buf[0]);
+    assert_eq!(Some(buf.len() - 1), marker.data_len());
+
+    let mut buf = Vec::new();
+    rmp::encode::write_ext_meta(&mut buf, 4, 1).unwrap();
+    buf.extend_from_slice(&[0; 4]);
+    let marker = Marker::from_u8(buf[0]);
+    assert_eq!(Some(buf.len() - 1), marker.data_len());
+}
+
+#[test]
+fn pass_fixstr_len_full_range() {
+    for len in 0..=31u8 {
+        let byte = 0xa0 | len;
+        assert_eq!(Marker::FixStr(len), Marker::from_u8(byte));
+        assert_eq!(byte, Marker::FixStr(len).to_u8());
+        assert_eq!(
+            u32::from(len),
+            rmp::decode::read_str_len(&mut &[byte][..]).unwrap()
+        );
+    }
+    // Neighbours of the range
+    assert_eq!(Marker::FixArray(15), Marker::from_u8(0x9f));
+    assert_eq!(Marker::Null, Marker::from_u8(0xc0));
+}
+
+#[test]
+fn pass_fixarray_and_fixmap_len_full_range() {
+    for len in 0..=15u8 {
+        let byte = 0x90 | len;
+        assert_eq!(Marker::FixArray(len), Marker::from_u8(byte));
+        assert_eq!(byte, Marker::FixArray(len).to_u8());
+        assert_eq!(
+            u32::from(len),
+            rmp::decode::read_array_len(&mut &[byte][..]).unwrap()
+        );
+
+        let byte = 0x80 | len;
+        assert_eq!(Marker::FixMap(len), Marker::from_u8(byte));
+        assert_eq!(byte, Marker::FixMap(len).to_u8());
+        assert_eq!(
+            u32::from(len),
+            rmp::decode::read_map_len(&mut &[byte][..]).unwrap()
+        );
+    }
+    // Neighbours of the ranges
+    assert_eq!(Marker::FixPos(0x7f), Marker::from_u8(0x7f));
+    assert_eq!(Marker::FixStr(0), Marker::from_u8(0xa0));
+}
+
+#[test]
+fn pass_negative_fixnum_full_range() {
+    for byte in 0xe0..=0xffu8 {
+        let val = i8::from_be_bytes([byte]);
+        assert!((-32..=-1).contains(&val));
+        assert_eq!(Marker::FixNeg(val), Marker::from_u8(byte));
+        assert_eq!(byte, Marker::FixNeg(val).to_u8());
+        assert_eq!(val, rmp::decode::read_nfix(&mut &[byte][..]).unwrap());
+        assert_eq!(
+            val,
+            rmp::decode::read_int::<i8, _>(&mut &[byte][..]).unwrap()
+        );
+        assert_eq!(
+            i64::from(val),
+            messpack_serde::from_slice::<i64>(&[byte]).unwrap()
+        );
+    }
+    assert_eq!(Marker::FixNeg(-32), Marker::from_u8(0xe0));
+    assert_eq!(Marker::FixNeg(-1), Marker::from_u8(0xff));
+}
+
+#[test]
+fn pass_type_queries_partition_all_markers() {
+    for byte in 0..=255u8 {
+        let marker = Marker::from_u8(byte);
+        let families = [
+            marker.is_nil(),
+            marker.is_bool(),
+            marker.is_int(),
+            marker.is_float(),
+            marker.is_str(),
+            marker.is_bin(),
+            marker.is_array(),
+            marker.is_map(),
+            marker.is_ext(),
+        ];
+        let count = families.iter().filter(|&&is| is).count();
+        // Every marker but the reserved one belongs to exactly one family.
+        assert_eq!(
+            usize::from(marker != Marker::Reserved),
+            count,
+            "{byte:#04x}"
+        );
+    }
+}
+
+#[test]
+fn pass_type_queries_examples() {
+    assert!(Marker::FixPos(0).is_int());
+    assert!(Marker::FixNeg(-1).is_int());
+    assert!(Marker::U64.is_int());
+    assert!(!Marker::F64.is_int());
+    assert!(Marker::F32.is_float());
+    assert!(Marker::FixStr(3).is_str());
+    assert!(!Marker::Bin8.is_str());
+    assert!(Marker::Bin32.is_bin());
+    assert!(Marker::Array16.is_array());
+    assert!(Marker::FixMap(0).is_map());
+    assert!(Marker::FixExt16.is_ext());
+    assert!(Marker::Ext8.is_ext());
+    assert!(Marker::Null.is_nil());
+    assert!(Marker::False.is_bool());
+}
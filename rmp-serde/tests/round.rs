@@ -455,6 +455,71 @@ fn round_trip_struct_with_flattened_struct_field() {
     }
 }
 
+#[test]
+fn round_trip_struct_with_nested_flattened_fields() {
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct Outer {
+        id: u64,
+        #[serde(flatten)]
+        middle: Middle,
+    }
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct Middle {
+        #[serde(flatten)]
+        inner: Inner,
+        tags: Vec<String>,
+        ratio: f32,
+    }
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct Inner {
+        name: Option<String>,
+        kind: Kind,
+        blob: serde_bytes::ByteBuf,
+        pair: (u8, String),
+    }
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    enum Kind {
+        Unit,
+        Newtype(u8),
+        Struct { q: i16 },
+    }
+
+    for kind in [Kind::Unit, Kind::Newtype(3), Kind::Struct { q: -5 }] {
+        let strct = Outer {
+            id: 1,
+            middle: Middle {
+                inner: Inner {
+                    name: Some("x".to_string()),
+                    kind,
+                    blob: serde_bytes::ByteBuf::from(vec![1, 2, 3]),
+                    pair: (7, "y".to_string()),
+                },
+                tags: vec!["a".to_string(), "b".to_string()],
+                ratio: 0.5,
+            },
+        };
+
+        // struct-as-tuple
+        {
+            let serialized: Vec<u8> = messpack_serde::to_vec(&strct).unwrap();
+            let deserialized: Outer = messpack_serde::from_slice(&serialized).unwrap();
+            assert_eq!(deserialized, strct);
+        }
+
+        // struct-as-map, both borrowed and from a reader
+        {
+            let serialized: Vec<u8> = messpack_serde::to_vec_named(&strct).unwrap();
+            let deserialized: Outer = messpack_serde::from_slice(&serialized).unwrap();
+            assert_eq!(deserialized, strct);
+            let deserialized: Outer = messpack_serde::from_read(Cursor::new(&serialized)).unwrap();
+            assert_eq!(deserialized, strct);
+        }
+    }
+}
+
 // Checks whether deserialization and serialization can both work with enum variants as strings
 #[test]
 fn round_variant_string() {
@@ -539,6 +604,49 @@ fn roundtrip_vec() {
     assert_roundtrips(vec![vec![Some(3u16), None, Some(10000)]]);
 }
 
+#[test]
+fn roundtrip_f32_without_promotion() {
+    for val in [0.1f32, -1.0e-38, f32::MAX, f32::MIN_POSITIVE, f32::EPSILON] {
+        let buf = messpack_serde::to_vec(&val).unwrap();
+        assert_eq!(5, buf.len());
+
+        let decoded: f32 = messpack_serde::from_slice(&buf).unwrap();
+        assert_eq!(val.to_bits(), decoded.to_bits());
+    }
+    assert_roundtrips(0.1f32);
+}
+
+#[test]
+fn roundtrip_byte_buf_as_bin() {
+    let val = messpack_serde::ByteBuf(vec![0xab; 1000]);
+    let buf = messpack_serde::to_vec(&val).unwrap();
+
+    // Bin16 header with a big-endian length, then the raw bytes.
+    assert_eq!([0xc5, 0x03, 0xe8], buf[..3]);
+    assert_eq!(1003, buf.len());
+
+    let decoded: messpack_serde::ByteBuf = messpack_serde::from_slice(&buf).unwrap();
+    assert_eq!(val, decoded);
+    assert_roundtrips(val);
+
+    // Arrays of integers written by plain `Vec<u8>` are accepted too.
+    let buf = messpack_serde::to_vec(&vec![1u8, 2, 3]).unwrap();
+    let decoded: messpack_serde::ByteBuf = messpack_serde::from_slice(&buf).unwrap();
+    assert_eq!(vec![1, 2, 3], decoded.into_vec());
+}
+
+#[test]
+fn roundtrip_bytes_as_bin() {
+    let data = [0xcdu8; 1000];
+    let buf = messpack_serde::to_vec(&messpack_serde::Bytes(&data)).unwrap();
+
+    assert_eq!([0xc5, 0x03, 0xe8], buf[..3]);
+
+    let decoded: messpack_serde::Bytes<'_> = messpack_serde::from_slice(&buf).unwrap();
+    assert_eq!(&data[..], &*decoded);
+    assert_eq!(buf[3..].as_ptr(), decoded.as_ptr());
+}
+
 #[test]
 fn roundtrip_hashsets() {
     use std::collections::HashSet;
@@ -599,6 +707,21 @@ fn roundtrip_simple_enum() {
     assert_roundtrips(SimpleEnum::V2("hello".into()));
 }
 
+#[test]
+fn roundtrip_mixed_enum() {
+    #[derive(PartialEq, Debug, Serialize, Deserialize)]
+    enum E {
+        A,
+        B(u32),
+        C { x: u8 },
+    }
+
+    assert_roundtrips(E::A);
+    assert_roundtrips(E::B(70000));
+    assert_roundtrips(E::C { x: 3 });
+    assert_roundtrips(vec![E::C { x: 1 }, E::A, E::B(2)]);
+}
+
 #[test]
 fn roundtrip_some() {
     #[derive(PartialEq, Debug, Serialize, Deserialize)]
@@ -610,6 +733,40 @@ fn roundtrip_some() {
     assert_roundtrips(Some("hi".to_string()));
 }
 
+#[test]
+fn roundtrip_option_bytes() {
+    // `Some` adds nothing around its value, and `None` is a single nil.
+    let buf = messpack_serde::to_vec(&Some(5u8)).unwrap();
+    assert_eq!([0x05], buf[..]);
+    assert_eq!(
+        Some(5),
+        messpack_serde::from_slice::<Option<u8>>(&buf).unwrap()
+    );
+
+    let buf = messpack_serde::to_vec(&None::<u8>).unwrap();
+    assert_eq!([0xc0], buf[..]);
+    assert_eq!(
+        None,
+        messpack_serde::from_slice::<Option<u8>>(&buf).unwrap()
+    );
+
+    let buf = messpack_serde::to_vec(&None::<()>).unwrap();
+    assert_eq!([0xc0], buf[..]);
+    assert_eq!(
+        None,
+        messpack_serde::from_slice::<Option<()>>(&buf).unwrap()
+    );
+
+    // The unit is a nil too, so `Some(())` can't be told apart from `None`, see
+    // `roundtrip_some_failures`.
+    let buf = messpack_serde::to_vec(&Some(())).unwrap();
+    assert_eq!([0xc0], buf[..]);
+    assert_eq!(
+        None,
+        messpack_serde::from_slice::<Option<()>>(&buf).unwrap()
+    );
+}
+
 /// Some types don't fully consume their input SeqAccess, leading to incorrect
 /// deserializes.
 ///
@@ -667,6 +824,360 @@ fn i128_from_integers() {
     assert_eq!(arr, [0, 1i128, -12, 119]);
 }
 
+#[test]
+fn roundtrip_serialize_iter() {
+    let mut buf = Vec::new();
+    let mut se = Serializer::new(&mut buf);
+    se.serialize_iter((0..10000u32).map(|i| (i, i.to_string())))
+        .unwrap();
+
+    // Array32 header for 10000 elements.
+    assert_eq!([0xdc, 0x27, 0x10], buf[..3]);
+
+    let actual: Vec<(u32, String)> = messpack_serde::from_slice(&buf).unwrap();
+    let expected: Vec<_> = (0..10000u32).map(|i| (i, i.to_string())).collect();
+    assert_eq!(expected, actual);
+}
+
+#[test]
+fn roundtrip_tuple_exact_arity() {
+    let val = (1, "a".to_string(), true);
+    let buf = messpack_serde::to_vec(&val).unwrap();
+
+    // fixarray of 3, then the elements
+    assert_eq!([0x93, 0x01, 0xa1, 0x61, 0xc3], buf[..]);
+    assert_roundtrips(val);
+}
+
+#[test]
+fn tuple_rejects_wrong_arity() {
+    use messpack_serde::decode::Error;
+
+    let short = messpack_serde::to_vec(&(1, "a")).unwrap();
+    match messpack_serde::from_slice::<(i32, String, bool)>(&short) {
+        Err(Error::LengthMismatch {
+            expected: 3,
+            actual: 2,
+        }) => (),
+        other => panic!("unexpected result: {other:?}"),
+    }
+
+    let long = messpack_serde::to_vec(&(1, "a", true, 2)).unwrap();
+    match messpack_serde::from_slice::<(i32, String, bool)>(&long) {
+        Err(Error::LengthMismatch {
+            expected: 3,
+            actual: 4,
+        }) => (),
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn tuple_arity_error_reports_both_lengths() {
+    let short = messpack_serde::to_vec(&(1, "a")).unwrap();
+    let err = messpack_serde::from_slice::<(i32, String, bool)>(&short).unwrap_err();
+
+    assert_eq!(
+        "array had incorrect length, expected 3 but found 2",
+        err.to_string()
+    );
+}
+
+#[test]
+fn roundtrip_bin_keys() {
+    use std::collections::BTreeMap;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Doc {
+        name: String,
+        tags: BTreeMap<String, u32>,
+        ids: BTreeMap<u8, String>,
+    }
+
+    let val = Doc {
+        name: "x".into(),
+        tags: BTreeMap::from([("a".into(), 1), ("b".into(), 2)]),
+        ids: BTreeMap::from([(7, "seven".into())]),
+    };
+
+    let mut buf = Vec::new();
+    val.serialize(
+        &mut Serializer::new(&mut buf)
+            .with_struct_map()
+            .with_bin_keys(true),
+    )
+    .unwrap();
+
+    let raw = rmpv::decode::read_value(&mut &buf[..]).unwrap();
+    for (key, _) in raw.as_map().unwrap() {
+        assert!(key.is_bin(), "{key:?}");
+    }
+    let tags = raw.as_map().unwrap()[1].1.as_map().unwrap();
+    assert!(tags.iter().all(|(key, _)| key.is_bin()));
+    // Non-string keys and string values are left alone.
+    let ids = raw.as_map().unwrap()[2].1.as_map().unwrap();
+    assert_eq!(
+        [(rmpv::Value::from(7), rmpv::Value::from("seven"))],
+        ids[..]
+    );
+
+    let mut de = Deserializer::new(&buf[..]);
+    assert_eq!(val, Doc::deserialize(&mut de).unwrap());
+}
+
+#[test]
+fn roundtrip_bin_size_boundaries() {
+    use serde_bytes::ByteBuf;
+
+    for (len, header) in [
+        (0, &[0xc4, 0x00][..]),
+        (1, &[0xc4, 0x01]),
+        (255, &[0xc4, 0xff]),
+        (256, &[0xc5, 0x01, 0x00]),
+    ] {
+        let val = ByteBuf::from(vec![0xab; len]);
+        let buf = messpack_serde::to_vec(&val).unwrap();
+        assert_eq!(header, &buf[..header.len()], "len {len}");
+        assert_eq!(header.len() + len, buf.len());
+
+        let (data, rest) = messpack_serde::decode::take_bin(&buf).unwrap();
+        assert_eq!(val[..], data[..]);
+        assert!(rest.is_empty());
+
+        let borrowed: &serde_bytes::Bytes = messpack_serde::from_slice(&buf).unwrap();
+        assert_eq!(val[..], borrowed[..]);
+        let owned: Vec<u8> = messpack_serde::from_slice(&buf).unwrap();
+        assert_eq!(val[..], owned[..]);
+        assert_roundtrips(val);
+    }
+}
+
+#[test]
+fn roundtrip_map_of_unknown_length() {
+    use std::collections::BTreeMap;
+
+    // Serializes the even keys of a map through an iterator that can't report its length.
+    struct EvenKeys<'a>(&'a BTreeMap<u32, String>);
+
+    impl Serialize for EvenKeys<'_> {
+        fn serialize<S: serde::Serializer>(&self, se: S) -> Result<S::Ok, S::Error> {
+            se.collect_map(self.0.iter().filter(|(k, _)| *k % 2 == 0))
+        }
+    }
+
+    let all: BTreeMap<u32, String> = (0..40).map(|i| (i, i.to_string())).collect();
+    let buf = messpack_serde::to_vec(&EvenKeys(&all)).unwrap();
+
+    // Twenty entries don't fit into a fixmap.
+    assert_eq!([0xde, 0x00, 0x14], buf[..3]);
+    let decoded: BTreeMap<u32, String> = messpack_serde::from_slice(&buf).unwrap();
+    let expected: BTreeMap<u32, String> = all.into_iter().filter(|(k, _)| k % 2 == 0).collect();
+    assert_eq!(expected, decoded);
+}
+
+#[test]
+fn roundtrip_framed_messages() {
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Msg {
+        id: u32,
+        body: String,
+    }
+
+    let first = Msg {
+        id: 1,
+        body: "hello".into(),
+    };
+    let second = Msg {
+        id: 70000,
+        body: "x".repeat(300),
+    };
+
+    let mut framed = Serializer::framed(Cursor::new(Vec::new()));
+    framed.write(&first).unwrap();
+    framed.write(&second).unwrap();
+    let mut buf = framed.into_inner();
+
+    let first_len = messpack_serde::encode::serialized_size(&first).unwrap() as u32;
+    assert_eq!(first_len.to_be_bytes(), buf.get_ref()[..4]);
+
+    buf.set_position(0);
+    let mut framed = Deserializer::framed(buf);
+    assert_eq!(Some(first), framed.read::<Msg>().unwrap());
+    assert_eq!(Some(second), framed.read::<Msg>().unwrap());
+    assert_eq!(None, framed.read::<Msg>().unwrap());
+}
+
+#[test]
+fn framed_rejects_truncated_and_padded_frames() {
+    use messpack_serde::decode::Error;
+
+    let mut framed = Deserializer::framed(&[0, 0, 0, 2, 0x05][..]);
+    match framed.read::<u8>() {
+        Err(Error::InvalidDataRead(..)) => (),
+        other => panic!("unexpected result: {other:?}"),
+    }
+
+    let mut framed = Deserializer::framed(&[0, 0, 0, 2, 0x05, 0x06][..]);
+    match framed.read::<u8>() {
+        Err(Error::Uncategorized(..)) => (),
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn roundtrip_through_value() {
+    use messpack_serde::decode::value::Value;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    enum Kind {
+        Plain,
+        Tagged(u8),
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Doc {
+        name: String,
+        scores: Vec<i64>,
+        kinds: Vec<Kind>,
+        ratio: Option<f32>,
+    }
+
+    let doc = Doc {
+        name: "x".into(),
+        scores: vec![-1, 300, i64::MAX],
+        kinds: vec![Kind::Plain, Kind::Tagged(4)],
+        ratio: Some(0.5),
+    };
+
+    let val = messpack_serde::to_value(&doc).unwrap();
+    assert_eq!(
+        rmpv::decode::read_value(&mut &messpack_serde::to_vec(&doc).unwrap()[..]).unwrap(),
+        val
+    );
+    assert!(matches!(val, Value::Array(..)));
+    assert_eq!(doc, messpack_serde::from_value::<Doc>(val).unwrap());
+}
+
+#[test]
+fn roundtrip_timestamp_all_forms() {
+    use messpack_serde::Timestamp;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    for (secs, nanos) in [
+        (0, 0),
+        (1_700_000_000, 0),
+        (1_700_000_000, 999_999_999),
+        (1 << 34, 0),
+        (-62_135_596_800, 0),
+    ] {
+        let ts = Timestamp::new(secs, nanos).unwrap();
+        let buf = messpack_serde::to_vec(&ts).unwrap();
+        assert_eq!(ts, messpack_serde::from_slice::<Timestamp>(&buf).unwrap());
+    }
+
+    let before = UNIX_EPOCH - Duration::from_millis(1500);
+    assert_eq!(
+        Timestamp::new(-2, 500_000_000),
+        Some(Timestamp::from(before))
+    );
+}
+
+#[test]
+fn roundtrip_ip_addrs_compact() {
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Addr(#[serde(with = "messpack_serde::ip")] IpAddr);
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct V4(#[serde(with = "messpack_serde::ip::v4")] Ipv4Addr);
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct V6(#[serde(with = "messpack_serde::ip::v6")] Ipv6Addr);
+
+    let v4 = Ipv4Addr::new(192, 168, 1, 200);
+    let v6 = Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0xff00, 0x42);
+
+    let buf = messpack_serde::to_vec(&V4(v4)).unwrap();
+    assert_eq!([0xc4, 0x04, 192, 168, 1, 200], buf[..]);
+    assert_eq!(V4(v4), messpack_serde::from_slice(&buf).unwrap());
+
+    let buf = messpack_serde::to_vec(&V6(v6)).unwrap();
+    assert_eq!(18, buf.len());
+    assert_eq!([0xc4, 0x10], buf[..2]);
+    assert_eq!(V6(v6), messpack_serde::from_slice(&buf).unwrap());
+
+    // The fixext marker and type id take the place of the bin header.
+    let buf = messpack_serde::to_vec(&Addr(v4.into())).unwrap();
+    assert_eq!([0xd6, 0x04, 192, 168, 1, 200], buf[..]);
+    assert_eq!(Addr(v4.into()), messpack_serde::from_slice(&buf).unwrap());
+
+    let buf = messpack_serde::to_vec(&Addr(v6.into())).unwrap();
+    assert_eq!(18, buf.len());
+    assert_eq!([0xd8, 0x06], buf[..2]);
+    assert_eq!(Addr(v6.into()), messpack_serde::from_slice(&buf).unwrap());
+
+    // Serde's default writes an array of integers, each byte above 127 taking two.
+    assert_eq!(8, messpack_serde::to_vec(&v4).unwrap().len());
+}
+
+#[test]
+fn roundtrip_ip_addrs_human_readable() {
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Addr(#[serde(with = "messpack_serde::ip")] IpAddr);
+
+    let addr = Addr(IpAddr::V6(Ipv6Addr::LOCALHOST));
+    let mut buf = Vec::new();
+    addr.serialize(&mut Serializer::new(&mut buf).with_human_readable())
+        .unwrap();
+    assert_eq!(b"\xa3::1", &buf[..]);
+
+    let mut de = Deserializer::new(&buf[..]).with_human_readable();
+    assert_eq!(addr, Addr::deserialize(&mut de).unwrap());
+}
+
+#[test]
+fn fail_ip_addr_unknown_family() {
+    #[derive(Debug, Deserialize)]
+    struct Addr(
+        #[serde(with = "messpack_serde::ip")]
+        #[allow(dead_code)]
+        IpAddr,
+    );
+
+    // fixext 4 of type 5, and fixext 4 of type 6 which wants 16 bytes
+    for buf in [[0xd6, 0x05, 1, 2, 3, 4], [0xd6, 0x06, 1, 2, 3, 4]] {
+        assert!(messpack_serde::from_slice::<Addr>(&buf).is_err());
+    }
+}
+
+#[test]
+fn roundtrip_char_field() {
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Glyph {
+        ch: char,
+        n: u8,
+    }
+
+    for ch in ['a', 'é', '€', '😀'] {
+        let val = Glyph { ch, n: 1 };
+        let buf = messpack_serde::to_vec(&val).unwrap();
+        // A fixstr holding the UTF-8 encoding.
+        assert_eq!(0xa0 | ch.len_utf8() as u8, buf[1]);
+        assert_eq!(val, messpack_serde::from_slice(&buf).unwrap());
+        assert_roundtrips(val);
+    }
+}
+
+#[test]
+fn fail_char_from_wrong_length_or_type() {
+    use messpack_serde::decode::Error;
+
+    // "", "ab" and 7, all rejected by serde's char visitor
+    for buf in [&[0xa0][..], &[0xa2, 0x61, 0x62][..], &[0x07][..]] {
+        match messpack_serde::from_slice::<char>(buf) {
+            Err(Error::Syntax(msg)) => assert!(msg.contains("expected a character"), "{msg}"),
+            other => panic!("unexpected result: {other:?}"),
+        }
+    }
+}
+
 #[ignore]
 #[test]
 fn roundtrip_some_failures() {
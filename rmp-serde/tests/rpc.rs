@@ -0,0 +1,146 @@
+use messpack_serde::decode::value::Value;
+use messpack_serde::decode::Error;
+use messpack_serde::rpc::{
+    read_rpc_notification, read_rpc_request, read_rpc_response, write_rpc_notification,
+    write_rpc_response, MAX_METHOD_LEN,
+};
+
+#[test]
+fn pass_read_rpc_request() {
+    // [0, 300, "sum", [1, "two"]]
+    let buf = [
+        0x94, 0x00, 0xcd, 0x01, 0x2c, 0xa3, 0x73, 0x75, 0x6d, 0x92, 0x01, 0xa3, 0x74, 0x77, 0x6f,
+    ];
+    let mut rd = &buf[..];
+
+    let (msgid, method, params) = read_rpc_request(&mut rd).unwrap();
+
+    assert_eq!(300, msgid);
+    assert_eq!("sum", method);
+    assert_eq!(
+        Value::Array(vec![Value::from(1), Value::from("two")]),
+        params
+    );
+    assert!(rd.is_empty());
+}
+
+#[test]
+fn fail_read_rpc_request_wrong_array_len() {
+    // [0, 1, "m"]
+    let buf = [0x93, 0x00, 0x01, 0xa1, 0x6d];
+
+    match read_rpc_request(&mut &buf[..]) {
+        Err(Error::LengthMismatch {
+            expected: 4,
+            actual: 3,
+        }) => (),
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn fail_read_rpc_request_wrong_message_type() {
+    // [2, 1, "m", []]
+    let buf = [0x94, 0x02, 0x01, 0xa1, 0x6d, 0x90];
+
+    match read_rpc_request(&mut &buf[..]) {
+        Err(Error::RpcTypeMismatch {
+            expected: 0,
+            actual: 2,
+        }) => (),
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn fail_read_rpc_request_truncated_method() {
+    // [0, 1, "method" cut short
+    let buf = [0x94, 0x00, 0x01, 0xa6, 0x6d, 0x65];
+
+    match read_rpc_request(&mut &buf[..]) {
+        Err(Error::InvalidDataRead(..)) => (),
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn fail_read_rpc_request_method_too_long() {
+    // [0, 1, <str 32 header claiming 4 GiB>]
+    let buf = [0x94, 0x00, 0x01, 0xdb, 0xff, 0xff, 0xff, 0xff];
+
+    match read_rpc_request(&mut &buf[..]) {
+        Err(Error::LengthLimitExceeded { len, max_len }) => {
+            assert_eq!(u32::MAX, len);
+            assert_eq!(MAX_METHOD_LEN, max_len);
+        }
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn pass_rpc_success_response_roundtrip() {
+    let result = Value::Map(vec![(Value::from("sum"), Value::from(3))]);
+
+    let mut buf = Vec::new();
+    write_rpc_response(&mut buf, 300, Ok(&result)).unwrap();
+
+    let mut rd = &buf[..];
+    assert_eq!((300, Ok(result)), read_rpc_response(&mut rd).unwrap());
+    assert!(rd.is_empty());
+}
+
+#[test]
+fn pass_rpc_error_response_roundtrip() {
+    let error = Value::from("no such method");
+
+    let mut buf = Vec::new();
+    write_rpc_response(&mut buf, 1, Err(&error)).unwrap();
+
+    // [1, 1, "no such method", nil]
+    assert_eq!([0x94, 0x01, 0x01, 0xae], buf[..4]);
+    assert_eq!(Some(&0xc0), buf.last());
+    assert_eq!((1, Err(error)), read_rpc_response(&mut &buf[..]).unwrap());
+}
+
+#[test]
+fn pass_rpc_notification_roundtrip() {
+    let params = Value::Array(vec![Value::from("event"), Value::Nil]);
+
+    let mut buf = Vec::new();
+    write_rpc_notification(&mut buf, "notify", &params).unwrap();
+
+    let mut rd = &buf[..];
+    assert_eq!(
+        ("notify".to_owned(), params),
+        read_rpc_notification(&mut rd).unwrap()
+    );
+    assert!(rd.is_empty());
+}
+
+#[test]
+fn fail_read_rpc_response_from_request() {
+    // [0, 1, "m", []]
+    let buf = [0x94, 0x00, 0x01, 0xa1, 0x6d, 0x90];
+
+    match read_rpc_response(&mut &buf[..]) {
+        Err(Error::RpcTypeMismatch {
+            expected: 1,
+            actual: 0,
+        }) => (),
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn fail_read_rpc_notification_wrong_array_len() {
+    // [2, "m", [], nil]
+    let buf = [0x94, 0x02, 0xa1, 0x6d, 0x90, 0xc0];
+
+    match read_rpc_notification(&mut &buf[..]) {
+        Err(Error::LengthMismatch {
+            expected: 3,
+            actual: 4,
+        }) => (),
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
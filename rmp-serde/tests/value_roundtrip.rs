@@ -0,0 +1,81 @@
+//! Randomized round-trip checks over generated `Value` trees.
+//!
+//! Every generated value is encoded and decoded again through each of the crate's code paths, and
+//! must come back unchanged. Generation is seeded, so failures are reproducible.
+
+use messpack_serde::decode::value::{read_value, read_value_checked, Value};
+use messpack_serde::encode::write_value_checked;
+use messpack_serde::test_support::generate_value;
+use serde::{Deserialize, Serialize};
+
+const ITERATIONS: u64 = 2000;
+const SEED: u64 = 0x2545_f491_4f6c_dd1d;
+const MAX_DEPTH: u32 = 4;
+const MAX_LEN: u64 = 6;
+
+/// Replaces NaN floats, which never compare equal, with a fixed value.
+fn without_nan(val: Value) -> Value {
+    match val {
+        Value::F32(v) if v.is_nan() => Value::F32(0.5),
+        Value::F64(v) if v.is_nan() => Value::F64(0.5),
+        Value::Array(vals) => Value::Array(vals.into_iter().map(without_nan).collect()),
+        Value::Map(entries) => Value::Map(
+            entries
+                .into_iter()
+                .map(|(k, v)| (without_nan(k), without_nan(v)))
+                .collect(),
+        ),
+        val => val,
+    }
+}
+
+fn for_each_value(mut f: impl FnMut(&Value)) {
+    for i in 0..ITERATIONS {
+        f(&without_nan(generate_value(SEED + i, MAX_DEPTH, MAX_LEN)));
+    }
+}
+
+#[test]
+fn roundtrip_generated_values_through_value_reader() {
+    for_each_value(|val| {
+        let mut buf = Vec::new();
+        val.serialize(&mut messpack_serde::Serializer::new(&mut buf))
+            .unwrap();
+
+        let mut rd = &buf[..];
+        assert_eq!(val, &read_value(&mut rd).unwrap());
+        assert!(rd.is_empty(), "trailing bytes after {val:?}");
+
+        let mut buf = Vec::new();
+        write_value_checked(&mut buf, val).unwrap();
+
+        let mut rd = &buf[..];
+        assert_eq!(val, &read_value_checked(&mut rd).unwrap());
+        assert!(rd.is_empty(), "trailing bytes after {val:?}");
+    });
+}
+
+#[test]
+fn roundtrip_generated_values_through_serde() {
+    for_each_value(|val| {
+        let buf = messpack_serde::to_vec(val).unwrap();
+        let decoded: Value = messpack_serde::from_slice(&buf).unwrap();
+        assert_eq!(val, &decoded);
+
+        let decoded = Value::deserialize(&mut messpack_serde::Deserializer::new(&buf[..])).unwrap();
+        assert_eq!(val, &decoded);
+    });
+}
+
+#[test]
+fn generated_values_encode_identically() {
+    for_each_value(|val| {
+        let mut expected = Vec::new();
+        rmpv::encode::write_value(&mut expected, val).unwrap();
+
+        let mut buf = Vec::new();
+        val.serialize(&mut messpack_serde::Serializer::new(&mut buf))
+            .unwrap();
+        assert_eq!(expected, buf, "{val:?}");
+    });
+}